@@ -0,0 +1,180 @@
+// This file is part of radicle-surf
+// <https://github.com/radicle-dev/radicle-surf>
+//
+// Copyright (C) 2019-2020 The Radicle Team <dev@radicle.xyz>
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License version 3 or
+// later as published by the Free Software Foundation.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Export the tree of a commit as an archive, see
+//! [`crate::Repository::archive`].
+
+use std::{
+    io::{self, Seek, Write},
+    path::Path,
+};
+
+use thiserror::Error;
+
+/// The format of an archive produced by [`crate::Repository::archive`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ArchiveFormat {
+    /// An uncompressed `tar` archive.
+    Tar,
+    /// A `zip` archive, where files are compressed using `deflate`.
+    Zip,
+}
+
+#[derive(Debug, Error)]
+#[non_exhaustive]
+pub enum Error {
+    #[error(transparent)]
+    Git(#[from] git2::Error),
+    #[error(transparent)]
+    Io(#[from] io::Error),
+    #[error(transparent)]
+    Zip(#[from] zip::result::ZipError),
+}
+
+/// A file to be written to an archive.
+struct ArchiveEntry {
+    path: String,
+    oid: git2::Oid,
+    mode: i32,
+}
+
+impl ArchiveEntry {
+    fn is_symlink(&self) -> bool {
+        self.mode == i32::from(git2::FileMode::Link)
+    }
+
+    fn permissions(&self) -> u32 {
+        if self.mode == i32::from(git2::FileMode::BlobExecutable) {
+            0o755
+        } else {
+            0o644
+        }
+    }
+}
+
+fn entries(tree: &git2::Tree) -> Result<Vec<ArchiveEntry>, Error> {
+    let mut entries = vec![];
+    tree.walk(git2::TreeWalkMode::PreOrder, |root, entry| {
+        if entry.kind() == Some(git2::ObjectType::Blob) {
+            let name = String::from_utf8_lossy(entry.name_bytes());
+            entries.push(ArchiveEntry {
+                path: format!("{root}{name}"),
+                oid: entry.id(),
+                mode: entry.filemode(),
+            });
+        }
+        git2::TreeWalkResult::Ok
+    })?;
+    Ok(entries)
+}
+
+/// Write the contents of `tree` to `writer` as a `tar` archive.
+///
+/// Only blobs, i.e. regular files, executables and symbolic links, are
+/// archived. Submodules are skipped. All entries are given `mtime` as
+/// their modification time, in seconds since the UNIX epoch.
+pub(crate) fn write_tar<W: Write>(
+    repo: &git2::Repository,
+    tree: &git2::Tree,
+    mtime: i64,
+    writer: W,
+) -> Result<(), Error> {
+    let mut builder = tar::Builder::new(writer);
+    for entry in entries(tree)? {
+        let blob = repo.find_blob(entry.oid)?;
+        let mut header = tar::Header::new_gnu();
+        header.set_mtime(mtime.max(0) as u64);
+        if entry.is_symlink() {
+            let target = String::from_utf8_lossy(blob.content());
+            header.set_entry_type(tar::EntryType::Symlink);
+            header.set_mode(0o777);
+            header.set_size(0);
+            builder.append_link(&mut header, &entry.path, Path::new(target.as_ref()))?;
+        } else {
+            header.set_entry_type(tar::EntryType::Regular);
+            header.set_mode(entry.permissions());
+            header.set_size(blob.size() as u64);
+            builder.append_data(&mut header, &entry.path, blob.content())?;
+        }
+    }
+    builder.into_inner()?.flush()?;
+    Ok(())
+}
+
+/// Write the contents of `tree` to `writer` as a `zip` archive, see
+/// [`write_tar`].
+///
+/// Unlike `tar`, `zip` needs to seek back to write the sizes of the
+/// compressed files.
+pub(crate) fn write_zip<W: Write + Seek>(
+    repo: &git2::Repository,
+    tree: &git2::Tree,
+    mtime: i64,
+    writer: W,
+) -> Result<(), Error> {
+    let mut zip = zip::ZipWriter::new(writer);
+    let options = zip::write::FileOptions::default()
+        .compression_method(zip::CompressionMethod::Deflated)
+        .last_modified_time(zip_time(mtime));
+    for entry in entries(tree)? {
+        let blob = repo.find_blob(entry.oid)?;
+        if entry.is_symlink() {
+            let target = String::from_utf8_lossy(blob.content());
+            zip.add_symlink(entry.path.as_str(), target, options)?;
+        } else {
+            zip.start_file(
+                entry.path.as_str(),
+                options.unix_permissions(entry.permissions()),
+            )?;
+            zip.write_all(blob.content())?;
+        }
+    }
+    zip.finish()?.flush()?;
+    Ok(())
+}
+
+/// Convert `secs` since the UNIX epoch into a `zip` timestamp, falling back
+/// to the default timestamp if it is not representable, e.g. before 1980.
+fn zip_time(secs: i64) -> zip::DateTime {
+    // Converts days since the UNIX epoch to a civil date, see
+    // <http://howardhinnant.github.io/date_algorithms.html#civil_from_days>.
+    let days = secs.div_euclid(86400) + 719468;
+    let era = days.div_euclid(146097);
+    let doe = days.rem_euclid(146097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+
+    let secs = secs.rem_euclid(86400);
+    u16::try_from(year)
+        .ok()
+        .and_then(|year| {
+            zip::DateTime::from_date_and_time(
+                year,
+                month as u8,
+                day as u8,
+                (secs / 3600) as u8,
+                (secs % 3600 / 60) as u8,
+                (secs % 60) as u8,
+            )
+            .ok()
+        })
+        .unwrap_or_default()
+}
@@ -0,0 +1,251 @@
+// Copyright © 2022 The Radicle Git Contributors
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! The built-in filters of the [gitattributes] of a commit, applied to
+//! file contents as a checkout would.
+//!
+//! `libgit2` only consults the `.gitattributes` files of a working
+//! directory or index, so this implements the subset of the format needed
+//! for the `text`, `eol` and `ident` attributes found in a commit's tree.
+//! Filter drivers, i.e. the `filter` attribute, are external commands and
+//! are never run.
+//!
+//! [gitattributes]: https://git-scm.com/docs/gitattributes
+
+use std::{
+    borrow::Cow,
+    path::{Path, PathBuf},
+};
+
+use git_ext::Oid;
+
+use crate::ignore::wildmatch;
+
+/// The name of the files containing attributes.
+pub(crate) const GITATTRIBUTES: &str = ".gitattributes";
+
+/// The line ending settings of the repository configuration.
+#[derive(Clone, Copy, Debug, Default)]
+pub(crate) struct EolConfig {
+    /// `core.autocrlf` is `true`.
+    pub autocrlf: bool,
+    /// `core.eol` is `crlf`.
+    pub crlf: bool,
+}
+
+impl EolConfig {
+    pub(crate) fn new(config: &git2::Config) -> Self {
+        Self {
+            autocrlf: config.get_bool("core.autocrlf").unwrap_or(false),
+            crlf: config
+                .get_string("core.eol")
+                .is_ok_and(|eol| eol.eq_ignore_ascii_case("crlf")),
+        }
+    }
+}
+
+/// A set of attribute rules, collected from `.gitattributes` files.
+///
+/// Rules are added from the top-most `.gitattributes` file downwards, and
+/// the last rule matching a path and mentioning an attribute decides its
+/// state. This gives rules of nested files precedence over their parents'.
+#[derive(Debug, Default)]
+pub(crate) struct Attributes {
+    rules: Vec<Rule>,
+}
+
+#[derive(Debug)]
+struct Rule {
+    /// The directory of the `.gitattributes` file this rule came from.
+    base: PathBuf,
+    pattern: Vec<u8>,
+    /// The pattern contains a `/` and is matched against the path relative
+    /// to `base`, rather than the file name only.
+    anchored: bool,
+    attrs: Vec<(String, State)>,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum State {
+    /// `attr`
+    Set,
+    /// `-attr`
+    Unset,
+    /// `attr=value`
+    Value(String),
+    /// `!attr`
+    Unspecified,
+}
+
+impl Attributes {
+    /// Add the rules of the `.gitattributes` file, with the given
+    /// `content`, found in the directory `base`.
+    pub(crate) fn add(&mut self, base: &Path, content: &[u8]) {
+        for line in content.split(|b| *b == b'\n') {
+            if let Some(rule) = Rule::parse(base, line) {
+                self.rules.push(rule);
+            }
+        }
+    }
+
+    /// Applies the filters of the file at `path`, relative to the
+    /// repository root, to its `content`, as checking it out would.
+    ///
+    /// `id` is the blob id that an `ident` attribute expands to.
+    pub(crate) fn smudge<'a>(
+        &self,
+        path: &Path,
+        id: Oid,
+        content: &'a [u8],
+        config: EolConfig,
+    ) -> Cow<'a, [u8]> {
+        let mut content = Cow::Borrowed(content);
+        if self.get(path, "ident") == Some(&State::Set) {
+            content = Cow::Owned(expand_ident(&content, id));
+        }
+        if self.is_crlf(path, &content, config) {
+            content = Cow::Owned(lf_to_crlf(&content));
+        }
+        content
+    }
+
+    /// Whether the line endings of the file at `path` are converted to
+    /// CRLF on checkout.
+    fn is_crlf(&self, path: &Path, content: &[u8], config: EolConfig) -> bool {
+        // Whether `eol`, or the legacy `crlf=input`, asks for CRLF.
+        let eol = match self.get(path, "eol") {
+            Some(State::Value(value)) => Some(value == "crlf"),
+            Some(_) => None,
+            None => match self.get(path, "crlf") {
+                Some(State::Value(value)) if value == "input" => Some(false),
+                _ => None,
+            },
+        };
+        // Whether the file is only converted if it looks like text.
+        let auto = match self.get(path, "text").or_else(|| self.get(path, "crlf")) {
+            Some(State::Unset) => return false,
+            Some(State::Value(value)) if value == "auto" => true,
+            Some(_) => false,
+            // Setting `eol` sets `text` too.
+            None if eol.is_some() => false,
+            None if config.autocrlf => true,
+            None => return false,
+        };
+
+        let crlf = eol.unwrap_or(config.autocrlf || config.crlf);
+        // Only files that look like text, and were not committed with
+        // CRLF line endings, are converted automatically.
+        let is_text = !auto || !(content.contains(&0) || content.contains(&b'\r'));
+        crlf && is_text
+    }
+
+    /// Returns the state of the attribute `name` for `path`, if it is
+    /// specified.
+    fn get(&self, path: &Path, name: &str) -> Option<&State> {
+        self.rules
+            .iter()
+            .rev()
+            .filter(|rule| rule.matches(path))
+            .find_map(|rule| {
+                rule.attrs
+                    .iter()
+                    .rev()
+                    .find(|(attr, _)| attr == name)
+                    .map(|(_, state)| state)
+            })
+            .filter(|state| **state != State::Unspecified)
+    }
+}
+
+impl Rule {
+    fn parse(base: &Path, line: &[u8]) -> Option<Self> {
+        let line = String::from_utf8_lossy(line);
+        let mut fields = line.split_ascii_whitespace();
+        let pattern = fields.next()?;
+        // Macro definitions, quoted patterns and negated patterns, which
+        // are forbidden, are not supported.
+        if pattern.starts_with('#')
+            || pattern.starts_with("[attr]")
+            || pattern.starts_with('"')
+            || pattern.starts_with('!')
+        {
+            return None;
+        }
+        // Patterns matching directories only never match a file.
+        if pattern.ends_with('/') {
+            return None;
+        }
+
+        let mut attrs = Vec::new();
+        for field in fields {
+            // The built-in `binary` macro.
+            if field == "binary" {
+                for name in ["diff", "merge", "text"] {
+                    attrs.push((name.to_owned(), State::Unset));
+                }
+                continue;
+            }
+            let attr = match field.split_once('=') {
+                Some((name, value)) => (name.to_owned(), State::Value(value.to_owned())),
+                None => match (field.strip_prefix('-'), field.strip_prefix('!')) {
+                    (Some(name), _) => (name.to_owned(), State::Unset),
+                    (_, Some(name)) => (name.to_owned(), State::Unspecified),
+                    _ => (field.to_owned(), State::Set),
+                },
+            };
+            attrs.push(attr);
+        }
+
+        let anchored = pattern.contains('/');
+        let pattern = pattern.strip_prefix('/').unwrap_or(pattern);
+        Some(Self {
+            base: base.to_path_buf(),
+            pattern: pattern.as_bytes().to_vec(),
+            anchored,
+            attrs,
+        })
+    }
+
+    fn matches(&self, path: &Path) -> bool {
+        let path = match path.strip_prefix(&self.base) {
+            Ok(path) => path.to_string_lossy(),
+            Err(_) => return false,
+        };
+        let path = path.as_bytes();
+        if self.anchored {
+            wildmatch(&self.pattern, path)
+        } else {
+            let name = path.rsplit(|b| *b == b'/').next().unwrap_or(path);
+            wildmatch(&self.pattern, name)
+        }
+    }
+}
+
+/// Expands each `$Id$` in `content` to `$Id: <id> $`.
+fn expand_ident(content: &[u8], id: Oid) -> Vec<u8> {
+    let expanded = format!("$Id: {id} $");
+    let mut out = Vec::with_capacity(content.len());
+    let mut rest = content;
+    while let Some(i) = rest.windows(4).position(|w| w == b"$Id$") {
+        out.extend_from_slice(&rest[..i]);
+        out.extend_from_slice(expanded.as_bytes());
+        rest = &rest[i + 4..];
+    }
+    out.extend_from_slice(rest);
+    out
+}
+
+/// Converts the LF line endings of `content` to CRLF, leaving existing
+/// CRLF line endings untouched.
+fn lf_to_crlf(content: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(content.len());
+    let mut previous = None;
+    for b in content {
+        if *b == b'\n' && previous != Some(b'\r') {
+            out.push(b'\r');
+        }
+        out.push(*b);
+        previous = Some(*b);
+    }
+    out
+}
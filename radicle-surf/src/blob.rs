@@ -93,6 +93,13 @@ impl<'a> Blob<BlobRef<'a>> {
     }
 }
 
+impl Blob<Vec<u8>> {
+    /// Replaces the content of the blob, e.g. with its filtered content.
+    pub(crate) fn with_content(self, content: Vec<u8>) -> Self {
+        Self { content, ..self }
+    }
+}
+
 /// Represents a blob with borrowed content bytes.
 pub struct BlobRef<'a> {
     inner: git2::Blob<'a>,
@@ -3,7 +3,10 @@ use std::{
     str::{self, FromStr},
 };
 
-use git_ext::ref_format::{component, lit, Component, Qualified, RefStr, RefString};
+use git_ext::{
+    ref_format::{component, lit, Component, Qualified, RefStr, RefString},
+    Oid,
+};
 
 use crate::refs::refstr_join;
 
@@ -294,6 +297,45 @@ impl FromStr for Remote {
     }
 }
 
+/// The target of the `HEAD` reference of a repository, see
+/// [`crate::Repository::head_ref`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum HeadRef {
+    /// `HEAD` is attached to a [`Branch`], which may not have any commits
+    /// yet.
+    Branch(Branch),
+    /// `HEAD` is detached and points directly to a commit.
+    Detached(Oid),
+}
+
+/// A remote configured in a repository, see
+/// [`crate::Repository::branch_remote`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize),
+    serde(rename_all = "camelCase")
+)]
+pub struct RemoteConfig {
+    /// The name of the remote, e.g. `origin`.
+    pub name: String,
+    /// The URL the remote is fetched from.
+    pub url: Option<String>,
+    /// The URL the remote is pushed to, if it differs from `url`.
+    pub push_url: Option<String>,
+}
+
+impl From<&git2::Remote<'_>> for RemoteConfig {
+    fn from(remote: &git2::Remote<'_>) -> Self {
+        Self {
+            name: remote.name().unwrap_or_default().to_owned(),
+            url: remote.url().map(str::to_owned),
+            push_url: remote.pushurl().map(str::to_owned),
+        }
+    }
+}
+
 pub mod error {
     use radicle_git_ext::ref_format::{self, RefString};
     use thiserror::Error;
@@ -0,0 +1,216 @@
+// Copyright © 2022 The Radicle Git Contributors
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! A [`Repository`] that memoizes the results of its most expensive
+//! queries, see [`CachedRepository`].
+
+use std::{
+    cell::RefCell,
+    collections::{BTreeMap, HashMap},
+    hash::Hash,
+    ops::Deref,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    diff::Diff,
+    fs::Directory,
+    repo::error,
+    Commit,
+    Error,
+    Oid,
+    Repository,
+    Revision,
+    ToCommit,
+};
+
+/// A [`Repository`] that memoizes the results of [`Repository::directory`],
+/// [`Repository::diff`], [`Repository::last_commit`] and
+/// [`Repository::merge_base`], keeping the `capacity` most recently used
+/// results of each.
+///
+/// The revisions passed to these methods are resolved on every call, and
+/// the results are cached by the commits they resolve to. Cached results
+/// are therefore never stale, and [`CachedRepository::clear`] is only
+/// needed to release the memory they hold.
+///
+/// All the other methods of [`Repository`] are available through
+/// [`Deref`], and are not cached.
+pub struct CachedRepository {
+    repo: Repository,
+    directories: RefCell<Lru<(Oid, PathBuf), Directory>>,
+    diffs: RefCell<Lru<(Oid, Oid), Diff>>,
+    last_commits: RefCell<Lru<(Oid, PathBuf), Option<Commit>>>,
+    merge_bases: RefCell<Lru<(Oid, Oid), Option<Oid>>>,
+}
+
+impl CachedRepository {
+    /// Wraps `repo`, caching up to `capacity` results of each query.
+    pub fn new(repo: Repository, capacity: usize) -> Self {
+        Self {
+            repo,
+            directories: RefCell::new(Lru::new(capacity)),
+            diffs: RefCell::new(Lru::new(capacity)),
+            last_commits: RefCell::new(Lru::new(capacity)),
+            merge_bases: RefCell::new(Lru::new(capacity)),
+        }
+    }
+
+    /// Returns the wrapped [`Repository`].
+    pub fn into_inner(self) -> Repository {
+        self.repo
+    }
+
+    /// Removes all the cached results.
+    pub fn clear(&self) {
+        self.directories.borrow_mut().clear();
+        self.diffs.borrow_mut().clear();
+        self.last_commits.borrow_mut().clear();
+        self.merge_bases.borrow_mut().clear();
+    }
+
+    /// Returns the [`Directory`] for `path` in `commit`, see
+    /// [`Repository::directory`].
+    pub fn directory<C: ToCommit, P: AsRef<Path>>(
+        &self,
+        commit: C,
+        path: &P,
+    ) -> Result<Directory, Error> {
+        let commit = self.to_commit(commit)?;
+        let key = (commit.id, path.as_ref().to_path_buf());
+        if let Some(directory) = self.directories.borrow_mut().get(&key) {
+            return Ok(directory.clone());
+        }
+        let directory = self.repo.directory(commit, path)?;
+        self.directories.borrow_mut().insert(key, directory.clone());
+        Ok(directory)
+    }
+
+    /// Returns the [`Diff`] between `from` and `to`, see
+    /// [`Repository::diff`].
+    pub fn diff(&self, from: impl Revision, to: impl Revision) -> Result<Diff, Error> {
+        let key = (self.repo.object_id(&from)?, self.repo.object_id(&to)?);
+        if let Some(diff) = self.diffs.borrow_mut().get(&key) {
+            return Ok(diff.clone());
+        }
+        let diff = self.repo.diff(key.0, key.1)?;
+        self.diffs.borrow_mut().insert(key, diff.clone());
+        Ok(diff)
+    }
+
+    /// Returns the last commit that touched `path` in the history of
+    /// `rev`, see [`Repository::last_commit`].
+    pub fn last_commit<P, C>(&self, path: &P, rev: C) -> Result<Option<Commit>, Error>
+    where
+        P: AsRef<Path>,
+        C: ToCommit,
+    {
+        let commit = self.to_commit(rev)?;
+        let key = (commit.id, path.as_ref().to_path_buf());
+        if let Some(last) = self.last_commits.borrow_mut().get(&key) {
+            return Ok(last.clone());
+        }
+        let last = self.repo.last_commit(path, commit)?;
+        self.last_commits.borrow_mut().insert(key, last.clone());
+        Ok(last)
+    }
+
+    /// Returns the merge base of `one` and `two`, see
+    /// [`Repository::merge_base`].
+    pub fn merge_base(&self, one: impl Revision, two: impl Revision) -> Result<Option<Oid>, Error> {
+        let (one, two) = (self.repo.object_id(&one)?, self.repo.object_id(&two)?);
+        // The merge base does not depend on the order of the commits.
+        let key = (one.min(two), one.max(two));
+        if let Some(merge_base) = self.merge_bases.borrow_mut().get(&key) {
+            return Ok(*merge_base);
+        }
+        let merge_base = self.repo.merge_base(key.0, key.1)?;
+        self.merge_bases.borrow_mut().insert(key, merge_base);
+        Ok(merge_base)
+    }
+
+    /// Returns the `base...head` [`Diff`], see
+    /// [`Repository::triple_dot_diff`].
+    ///
+    /// Both the merge base of `base` and `head`, and the diff, are cached.
+    pub fn triple_dot_diff(&self, base: impl Revision, head: impl Revision) -> Result<Diff, Error> {
+        let base = self.repo.object_id(&base)?;
+        let head = self.repo.object_id(&head)?;
+        let merge_base = self
+            .merge_base(base, head)?
+            .ok_or(error::Repo::NoMergeBase(base, head))?;
+        self.diff(merge_base, head)
+    }
+
+    fn to_commit<C: ToCommit>(&self, commit: C) -> Result<Commit, Error> {
+        commit
+            .to_commit(&self.repo)
+            .map_err(|err| Error::ToCommit(err.into()))
+    }
+}
+
+impl Deref for CachedRepository {
+    type Target = Repository;
+
+    fn deref(&self) -> &Self::Target {
+        &self.repo
+    }
+}
+
+impl From<Repository> for CachedRepository {
+    /// Wraps `repo` with a capacity of 128 results per query.
+    fn from(repo: Repository) -> Self {
+        Self::new(repo, 128)
+    }
+}
+
+/// A map holding at most `capacity` entries, evicting the least recently
+/// used one when it is full.
+struct Lru<K, V> {
+    capacity: usize,
+    tick: u64,
+    entries: HashMap<K, (V, u64)>,
+    /// The keys of `entries`, by the tick they were last used at.
+    used: BTreeMap<u64, K>,
+}
+
+impl<K: Clone + Eq + Hash, V> Lru<K, V> {
+    fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            tick: 0,
+            entries: HashMap::new(),
+            used: BTreeMap::new(),
+        }
+    }
+
+    fn get(&mut self, key: &K) -> Option<&V> {
+        let (value, used) = self.entries.get_mut(key)?;
+        self.tick += 1;
+        let key = self.used.remove(used)?;
+        *used = self.tick;
+        self.used.insert(self.tick, key);
+        Some(value)
+    }
+
+    fn insert(&mut self, key: K, value: V) {
+        if self.capacity == 0 {
+            return;
+        }
+        if let Some((_, used)) = self.entries.remove(&key) {
+            self.used.remove(&used);
+        } else if self.entries.len() == self.capacity {
+            if let Some((_, oldest)) = self.used.pop_first() {
+                self.entries.remove(&oldest);
+            }
+        }
+        self.tick += 1;
+        self.used.insert(self.tick, key.clone());
+        self.entries.insert(key, (value, self.tick));
+    }
+
+    fn clear(&mut self) {
+        self.entries.clear();
+        self.used.clear();
+    }
+}
@@ -15,11 +15,14 @@
 // You should have received a copy of the GNU General Public License
 // along with this program. If not, see <https://www.gnu.org/licenses/>.
 
-use std::{convert::TryFrom, str};
+use std::{convert::TryFrom, ops::Range, str, sync::OnceLock};
 
 use radicle_git_ext::Oid;
+use regex::Regex;
 use thiserror::Error;
 
+use crate::Repository;
+
 #[cfg(feature = "serde")]
 use serde::{ser::SerializeStruct, Deserialize, Deserializer, Serialize, Serializer};
 
@@ -29,10 +32,23 @@ pub enum Error {
     /// failed.
     #[error("an error occurred trying to get a commit's summary")]
     MissingSummary,
+    /// The commit message could not be decoded using the encoding declared
+    /// by the commit's `encoding` header.
+    #[error("the commit message is not valid '{0}'")]
+    InvalidEncoding(String),
     #[error(transparent)]
     Utf8Error(#[from] str::Utf8Error),
 }
 
+/// An error creating a [`ReferencePattern`].
+#[derive(Debug, Error)]
+#[non_exhaustive]
+pub enum PatternError {
+    /// The pattern is not a valid regular expression.
+    #[error(transparent)]
+    Regex(#[from] regex::Error),
+}
+
 /// Represents the authorship of actions in a git repo.
 #[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
 #[derive(Clone, PartialEq, Eq, PartialOrd, Ord)]
@@ -53,7 +69,7 @@ pub struct Author {
 }
 
 /// Time used in the authorship of an action in a git repo.
-#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
 pub struct Time {
     inner: git2::Time,
 }
@@ -78,6 +94,11 @@ impl Time {
     pub fn offset_minutes(&self) -> i32 {
         self.inner.offset_minutes()
     }
+
+    /// Returns the same point in time, with a timezone offset of zero.
+    pub fn to_utc(&self) -> Self {
+        Self::new(self.seconds(), 0)
+    }
 }
 
 #[cfg(feature = "serde")]
@@ -125,6 +146,46 @@ impl<'repo> TryFrom<git2::Signature<'repo>> for Author {
     }
 }
 
+/// Whether a commit's changes are already present upstream, as reported by
+/// [`crate::Repository::cherry`].
+#[cfg_attr(feature = "serde", derive(Serialize), serde(rename_all = "camelCase"))]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CherryStatus {
+    /// An equivalent change, i.e. one with the same patch-id, exists
+    /// upstream.
+    Equivalent,
+    /// The change is not found upstream.
+    Unique,
+}
+
+/// The role a person had in a commit, as used by
+/// [`crate::Repository::commits_by`].
+#[cfg_attr(feature = "serde", derive(Serialize), serde(rename_all = "camelCase"))]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AuthorRole {
+    /// The person wrote the changes of the commit.
+    Author,
+    /// The person committed the commit, e.g. when applying or merging
+    /// someone else's changes.
+    Committer,
+    /// The person is either the author or the committer of the commit.
+    Either,
+}
+
+impl AuthorRole {
+    /// Returns `true` if the person with `email` had this role in
+    /// `commit`.
+    pub(crate) fn matches(&self, commit: &Commit, email: &str) -> bool {
+        let author = commit.author.email == email;
+        let committer = commit.committer.email == email;
+        match self {
+            Self::Author => author,
+            Self::Committer => committer,
+            Self::Either => author || committer,
+        }
+    }
+}
+
 /// `Commit` is the metadata of a [Git commit][git-commit].
 ///
 /// [git-commit]: https://git-scm.com/book/en/v2/Git-Internals-Git-Objects
@@ -146,6 +207,26 @@ pub struct Commit {
 }
 
 impl Commit {
+    /// Returns the time the commit was authored, in the author's timezone.
+    pub fn author_time(&self) -> Time {
+        self.author.time
+    }
+
+    /// Returns the time the commit was committed, in the committer's
+    /// timezone.
+    ///
+    /// This can differ from [`Commit::author_time`], e.g. when a commit was
+    /// rebased or applied from a patch.
+    pub fn commit_time(&self) -> Time {
+        self.committer.time
+    }
+
+    /// Returns the shortest abbreviation of the commit's id that is
+    /// unambiguous in `repo`, see [`Repository::short_id`].
+    pub fn short_id(&self, repo: &Repository) -> Result<String, crate::Error> {
+        repo.short_id(self.id)
+    }
+
     /// Returns the commit description text. This is the text after the one-line
     /// summary.
     #[must_use]
@@ -155,6 +236,103 @@ impl Commit {
             .unwrap_or(&self.message)
             .trim()
     }
+
+    /// Returns the issue references, e.g. `#123`, found in the commit
+    /// message, using [`ReferencePattern::issue`].
+    pub fn references(&self) -> Vec<MessageReference> {
+        self.references_with(&[ReferencePattern::issue()])
+    }
+
+    /// Returns the references matching any of `patterns` found in the
+    /// commit message, in the order they appear.
+    ///
+    /// Where matches of different patterns overlap, the match starting
+    /// first is kept, or the match of the first pattern if they start at
+    /// the same offset.
+    pub fn references_with(&self, patterns: &[ReferencePattern]) -> Vec<MessageReference> {
+        let mut references = Vec::new();
+        for pattern in patterns {
+            for captures in pattern.regex.captures_iter(&self.message) {
+                let whole = captures.get(0).expect("a match has a group 0");
+                let id = captures.get(1).unwrap_or(whole);
+                references.push(MessageReference {
+                    pattern: pattern.name.clone(),
+                    text: whole.as_str().to_owned(),
+                    id: id.as_str().to_owned(),
+                    range: whole.range(),
+                });
+            }
+        }
+        // The sort is stable, keeping the order of the patterns.
+        references.sort_by_key(|r| r.range.start);
+        let mut end = 0;
+        references.retain(|r| {
+            let keep = r.range.start >= end;
+            if keep {
+                end = r.range.end;
+            }
+            keep
+        });
+        references
+    }
+}
+
+/// A pattern of references to look for in commit messages, see
+/// [`Commit::references_with`].
+///
+/// Forges can define their own patterns to link commits to their issues,
+/// patches, etc.
+#[derive(Debug, Clone)]
+pub struct ReferencePattern {
+    name: String,
+    regex: Regex,
+}
+
+impl ReferencePattern {
+    /// Creates a pattern from the regular expression `pattern`, using the
+    /// [`regex`] syntax.
+    ///
+    /// If `pattern` has a capture group, the first group is the
+    /// [`MessageReference::id`], e.g. `123` for `#(\d+)`, otherwise it is
+    /// the whole match.
+    ///
+    /// [`regex`]: https://docs.rs/regex
+    pub fn new(name: impl Into<String>, pattern: &str) -> Result<Self, PatternError> {
+        Ok(Self {
+            name: name.into(),
+            regex: Regex::new(pattern)?,
+        })
+    }
+
+    /// The pattern named `issue`, matching `#` followed by a number,
+    /// e.g. `#123`, which is not part of a longer word.
+    pub fn issue() -> Self {
+        static ISSUE: OnceLock<ReferencePattern> = OnceLock::new();
+        ISSUE
+            .get_or_init(|| {
+                Self::new("issue", r"\B#([0-9]+)\b").expect("the issue pattern is valid")
+            })
+            .clone()
+    }
+
+    /// Returns the name of the pattern.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+/// A reference found in a commit message, see [`Commit::references`].
+#[cfg_attr(feature = "serde", derive(Serialize))]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MessageReference {
+    /// The name of the [`ReferencePattern`] that matched.
+    pub pattern: String,
+    /// The matched text, e.g. `#123`.
+    pub text: String,
+    /// The identifier of the referenced item, e.g. `123`.
+    pub id: String,
+    /// The byte offsets of `text` in the commit message.
+    pub range: Range<usize>,
 }
 
 #[cfg(feature = "serde")]
@@ -189,10 +367,10 @@ impl<'repo> TryFrom<git2::Commit<'repo>> for Commit {
         let id = commit.id().into();
         let author = Author::try_from(commit.author())?;
         let committer = Author::try_from(commit.committer())?;
-        let message_raw = commit.message_bytes();
-        let message = str::from_utf8(message_raw)?.into();
+        let encoding = commit.message_encoding();
+        let message = decode_message(commit.message_bytes(), encoding)?;
         let summary_raw = commit.summary_bytes().ok_or(Error::MissingSummary)?;
-        let summary = str::from_utf8(summary_raw)?.into();
+        let summary = decode_message(summary_raw, encoding)?;
         let parents = commit.parent_ids().map(|oid| oid.into()).collect();
 
         Ok(Commit {
@@ -205,3 +383,18 @@ impl<'repo> TryFrom<git2::Commit<'repo>> for Commit {
         })
     }
 }
+
+/// Decode the `raw` bytes of a commit message to UTF-8, using the
+/// `encoding` declared in the commit's `encoding` header.
+///
+/// If there is no `encoding` header, or it is not a known encoding, the
+/// message is expected to be UTF-8, which is `git`'s default.
+fn decode_message(raw: &[u8], encoding: Option<&str>) -> Result<String, Error> {
+    match encoding.and_then(|label| encoding_rs::Encoding::for_label(label.as_bytes())) {
+        Some(encoding) if encoding != encoding_rs::UTF_8 => encoding
+            .decode_without_bom_handling_and_without_replacement(raw)
+            .map(|message| message.into_owned())
+            .ok_or_else(|| Error::InvalidEncoding(encoding.name().to_string())),
+        _ => Ok(str::from_utf8(raw)?.to_string()),
+    }
+}
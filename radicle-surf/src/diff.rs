@@ -17,7 +17,14 @@
 
 //! Types that represent diff(s) in a Git repo.
 
-use std::{borrow::Cow, ops::Range, path::PathBuf, string::FromUtf8Error};
+use std::{
+    borrow::Cow,
+    collections::{BTreeMap, BTreeSet, HashMap},
+    fmt,
+    ops::{Range, RangeInclusive},
+    path::{Path, PathBuf},
+    string::FromUtf8Error,
+};
 
 #[cfg(feature = "serde")]
 use serde::{ser, ser::SerializeStruct, Serialize, Serializer};
@@ -25,6 +32,23 @@ use serde::{ser, ser::SerializeStruct, Serialize, Serializer};
 use git_ext::Oid;
 
 pub mod git;
+#[cfg(feature = "json")]
+pub mod v1;
+
+pub mod error {
+    use std::path::PathBuf;
+
+    use thiserror::Error;
+
+    #[derive(Debug, Error)]
+    #[non_exhaustive]
+    pub enum Compose {
+        #[error("cannot compose diffs with the conflicted file '{0}'")]
+        Conflicted(PathBuf),
+        #[error("the diffs are not consecutive, see the file '{0}'")]
+        NotConsecutive(PathBuf),
+    }
+}
 
 /// The serializable representation of a `git diff`.
 ///
@@ -35,6 +59,7 @@ pub mod git;
 pub struct Diff {
     files: Vec<FileDiff>,
     stats: Stats,
+    truncated: bool,
 }
 
 impl Diff {
@@ -88,10 +113,357 @@ impl Diff {
         })
     }
 
+    /// Returns an iterator of the files that are in a conflicted state,
+    /// e.g. when diffing against an index in the middle of a merge.
+    pub fn conflicted(&self) -> impl Iterator<Item = &Conflicted> {
+        self.files().filter_map(|x| match x {
+            FileDiff::Conflicted(a) => Some(a),
+            _ => None,
+        })
+    }
+
     pub fn stats(&self) -> &Stats {
         &self.stats
     }
 
+    /// Returns `true` if some files were left out of this diff, because
+    /// it was computed with [`Options::max_files`].
+    ///
+    /// The [`Stats::files_changed`] still count all the files, but the
+    /// insertions and deletions only count the files of this diff.
+    pub fn is_truncated(&self) -> bool {
+        self.truncated
+    }
+
+    /// Serializes the diff to JSON in the version 1 format of [`v1`].
+    ///
+    /// Unlike the `Serialize` implementation of `Diff`, this format is
+    /// independent of the layout of the Rust types, and is stable for
+    /// exchanging diffs with other components. Use [`Diff::from_json_v1`]
+    /// to parse it back.
+    #[cfg(feature = "json")]
+    pub fn to_json_v1(&self) -> Result<String, serde_json::Error> {
+        serde_json::to_string(&v1::Diff::from(self))
+    }
+
+    /// Parses a diff from JSON in the version 1 format of [`v1`], as
+    /// written by [`Diff::to_json_v1`].
+    ///
+    /// # Errors
+    ///
+    /// * [`v1::error::Decode::UnsupportedVersion`] if the `version` of the
+    ///   diff is not `1`.
+    #[cfg(feature = "json")]
+    pub fn from_json_v1(json: &str) -> Result<Self, v1::error::Decode> {
+        let diff = serde_json::from_str::<v1::Diff>(json)?;
+        Self::try_from(diff)
+    }
+
+    /// Returns the subset of this diff for the files whose path matches
+    /// `predicate`, e.g. to view the changes under a directory without
+    /// computing the diff again.
+    ///
+    /// Moved and copied files match if either their old or new path
+    /// matches. The [`Stats`] are recomputed for the matching files.
+    pub fn filter_paths<P>(&self, predicate: P) -> Diff
+    where
+        P: Fn(&Path) -> bool,
+    {
+        let mut diff = Diff::new();
+        diff.truncated = self.truncated;
+        for file in self.files() {
+            let (matches, content) = match file {
+                FileDiff::Added(added) => (predicate(&added.path), Some(&added.diff)),
+                FileDiff::Deleted(deleted) => (predicate(&deleted.path), Some(&deleted.diff)),
+                FileDiff::Modified(modified) => (predicate(&modified.path), Some(&modified.diff)),
+                FileDiff::Moved(moved) => (
+                    predicate(&moved.old_path) || predicate(&moved.new_path),
+                    Some(&moved.diff),
+                ),
+                FileDiff::Copied(copied) => (
+                    predicate(&copied.old_path) || predicate(&copied.new_path),
+                    Some(&copied.diff),
+                ),
+                FileDiff::Conflicted(conflicted) => (predicate(&conflicted.path), None),
+            };
+            if !matches {
+                continue;
+            }
+            diff.stats.files_changed += 1;
+            if let Some(DiffContent::Plain { hunks, .. }) = content {
+                for line in hunks.iter().flat_map(|hunk| &hunk.lines) {
+                    match line {
+                        Modification::Addition(_) => diff.stats.insertions += 1,
+                        Modification::Deletion(_) => diff.stats.deletions += 1,
+                        Modification::Context { .. } => {},
+                    }
+                }
+            }
+            diff.files.push(file.clone());
+        }
+        diff
+    }
+
+    /// Compose this diff, from `A` to `B`, with `other`, from `B` to `C`,
+    /// into the path-level changes from `A` to `C`.
+    ///
+    /// The changes of the files are not composed, only how their paths
+    /// changed. For example, a file created in this diff and deleted in
+    /// `other` cancels out, and a file moved in this diff and modified in
+    /// `other` is reported as moved.
+    ///
+    /// # Errors
+    ///
+    /// * [`error::Compose::Conflicted`] if either diff has conflicted files.
+    /// * [`error::Compose::NotConsecutive`] if `other` does not start where
+    ///   this diff ends, e.g. it modifies a file that this diff deleted.
+    pub fn compose(&self, other: &Diff) -> Result<Vec<PathChange>, error::Compose> {
+        use error::Compose::NotConsecutive;
+
+        // How the files in `B`, and then `C`, came to be, relative to `A`.
+        let mut origins = BTreeMap::new();
+        // The paths of `A` that are deleted, and that no longer exist.
+        let mut deleted = BTreeSet::new();
+        let mut gone = BTreeSet::new();
+
+        for file in self.files() {
+            let (path, origin) = match file {
+                FileDiff::Added(added) => (&added.path, Origin::Added),
+                FileDiff::Deleted(file) => {
+                    deleted.insert(file.path.clone());
+                    gone.insert(file.path.clone());
+                    continue;
+                },
+                FileDiff::Modified(modified) => (&modified.path, Origin::Modified),
+                FileDiff::Moved(moved) => {
+                    gone.insert(moved.old_path.clone());
+                    (&moved.new_path, Origin::Moved(moved.old_path.clone()))
+                },
+                FileDiff::Copied(copied) => {
+                    (&copied.new_path, Origin::Copied(copied.old_path.clone()))
+                },
+                FileDiff::Conflicted(file) => {
+                    return Err(error::Compose::Conflicted(file.path.clone()))
+                },
+            };
+            if origins.insert(path.clone(), origin).is_some() {
+                return Err(NotConsecutive(path.clone()));
+            }
+        }
+
+        for file in other.files() {
+            match file {
+                FileDiff::Added(added) => {
+                    let path = &added.path;
+                    if origins.contains_key(path) {
+                        return Err(NotConsecutive(path.clone()));
+                    }
+                    let origin = if deleted.remove(path) {
+                        Origin::Modified
+                    } else if gone.contains(path) {
+                        // The file was moved away, so it was copied instead.
+                        for origin in origins.values_mut() {
+                            if *origin == Origin::Moved(path.clone()) {
+                                *origin = Origin::Copied(path.clone());
+                            }
+                        }
+                        Origin::Modified
+                    } else {
+                        Origin::Added
+                    };
+                    gone.remove(path);
+                    origins.insert(path.clone(), origin);
+                },
+                FileDiff::Deleted(file) => {
+                    let path = &file.path;
+                    if gone.contains(path) {
+                        return Err(NotConsecutive(path.clone()));
+                    }
+                    match origins.remove(path) {
+                        Some(Origin::Added) | Some(Origin::Copied(_)) => {},
+                        Some(Origin::Moved(old)) => {
+                            deleted.insert(old);
+                        },
+                        Some(Origin::Modified) | None => {
+                            deleted.insert(path.clone());
+                        },
+                    }
+                    gone.insert(path.clone());
+                },
+                FileDiff::Modified(modified) => {
+                    let path = &modified.path;
+                    if gone.contains(path) {
+                        return Err(NotConsecutive(path.clone()));
+                    }
+                    origins.entry(path.clone()).or_insert(Origin::Modified);
+                },
+                FileDiff::Moved(moved) => {
+                    let (old, new) = (&moved.old_path, &moved.new_path);
+                    if gone.contains(old) {
+                        return Err(NotConsecutive(old.clone()));
+                    }
+                    if origins.contains_key(new) {
+                        return Err(NotConsecutive(new.clone()));
+                    }
+                    let origin = match origins.remove(old) {
+                        Some(Origin::Added) => Origin::Added,
+                        Some(Origin::Modified) | None => Origin::Moved(old.clone()),
+                        // The file was moved back to where it started.
+                        Some(Origin::Moved(first)) if first == *new => Origin::Modified,
+                        Some(Origin::Moved(first)) => Origin::Moved(first),
+                        Some(Origin::Copied(first)) => Origin::Copied(first),
+                    };
+                    gone.insert(old.clone());
+                    gone.remove(new);
+                    deleted.remove(new);
+                    origins.insert(new.clone(), origin);
+                },
+                FileDiff::Copied(copied) => {
+                    let (old, new) = (&copied.old_path, &copied.new_path);
+                    if gone.contains(old) {
+                        return Err(NotConsecutive(old.clone()));
+                    }
+                    if origins.contains_key(new) {
+                        return Err(NotConsecutive(new.clone()));
+                    }
+                    let origin = match origins.get(old) {
+                        Some(Origin::Added) => Origin::Added,
+                        Some(Origin::Modified) | None => Origin::Copied(old.clone()),
+                        Some(Origin::Moved(first)) | Some(Origin::Copied(first)) => {
+                            Origin::Copied(first.clone())
+                        },
+                    };
+                    gone.remove(new);
+                    deleted.remove(new);
+                    origins.insert(new.clone(), origin);
+                },
+                FileDiff::Conflicted(file) => {
+                    return Err(error::Compose::Conflicted(file.path.clone()))
+                },
+            }
+        }
+
+        let mut changes = origins
+            .into_iter()
+            .map(|(path, origin)| match origin {
+                Origin::Added => PathChange::Added { path },
+                Origin::Modified => PathChange::Modified { path },
+                Origin::Moved(old_path) => PathChange::Moved {
+                    old_path,
+                    new_path: path,
+                },
+                Origin::Copied(old_path) => PathChange::Copied {
+                    old_path,
+                    new_path: path,
+                },
+            })
+            .chain(deleted.into_iter().map(|path| PathChange::Deleted { path }))
+            .collect::<Vec<_>>();
+        changes.sort_by(|x, y| x.path().cmp(y.path()));
+        Ok(changes)
+    }
+
+    /// Detects blocks of at least `min_lines` consecutive lines that were
+    /// deleted in one place and added in another, within the same file or
+    /// across files, e.g. a function that was relocated.
+    ///
+    /// Lines are compared ignoring leading and trailing whitespace, so
+    /// re-indented code is still detected as moved. Blocks of only blank
+    /// lines are ignored, and each deleted or added line is part of at
+    /// most one block, preferring the longest blocks.
+    ///
+    /// To bound the work on large diffs, a line that was added more than
+    /// [`MAX_MOVED_LINE_MATCHES`] times, e.g. a lone `}`, does not start
+    /// a block, though it can still extend one.
+    pub fn moved_lines(&self, min_lines: usize) -> Vec<MovedLines> {
+        let mut deleted = Vec::new();
+        let mut added = Vec::new();
+        for file in self.files() {
+            let (path, diff) = match file {
+                FileDiff::Added(added) => (&added.path, &added.diff),
+                FileDiff::Deleted(deleted) => (&deleted.path, &deleted.diff),
+                FileDiff::Modified(modified) => (&modified.path, &modified.diff),
+                FileDiff::Moved(_) | FileDiff::Copied(_) | FileDiff::Conflicted(_) => continue,
+            };
+            if let DiffContent::Plain { hunks, .. } = diff {
+                line_runs(path, hunks, &mut deleted, &mut added);
+            }
+        }
+
+        // Index the added lines by their content, so that only matching
+        // lines are compared.
+        let mut index = HashMap::<&[u8], Vec<(usize, usize)>>::new();
+        for (a, add) in added.iter().enumerate() {
+            for (j, (_, line)) in add.lines.iter().enumerate() {
+                index.entry(line).or_default().push((a, j));
+            }
+        }
+
+        // Find the maximal matching blocks between every deleted and added
+        // run, as `(len, deleted run, start, added run, start)`.
+        let min_lines = min_lines.max(1);
+        let mut candidates = Vec::new();
+        for (d, del) in deleted.iter().enumerate() {
+            // Records the block ending before the deleted line `end`.
+            let mut push = |end: usize, a: usize, (i, j): (usize, usize)| {
+                let is_blank = del.lines[i..end].iter().all(|(_, line)| line.is_empty());
+                if end - i >= min_lines && !is_blank {
+                    candidates.push((end - i, d, i, a, j));
+                }
+            };
+            // The blocks matching the deleted lines up to `i`, by the
+            // position of their last added line, with the positions of
+            // their first deleted and added lines.
+            let mut open = HashMap::<(usize, usize), (usize, usize)>::new();
+            for (i, (_, line)) in del.lines.iter().enumerate() {
+                let mut next = HashMap::with_capacity(open.len());
+                for ((a, j), start) in open {
+                    if added[a].lines.get(j + 1).is_some_and(|(_, l)| l == line) {
+                        next.insert((a, j + 1), start);
+                    } else {
+                        push(i, a, start);
+                    }
+                }
+                let hits = index.get(line).map(Vec::as_slice).unwrap_or_default();
+                if hits.len() <= MAX_MOVED_LINE_MATCHES {
+                    for &(a, j) in hits {
+                        next.entry((a, j)).or_insert((i, j));
+                    }
+                }
+                open = next;
+            }
+            for ((a, _), start) in open {
+                push(del.lines.len(), a, start);
+            }
+        }
+        candidates.sort_by(|x, y| y.0.cmp(&x.0).then(x.cmp(y)));
+
+        let mut used_deleted = BTreeSet::new();
+        let mut used_added = BTreeSet::new();
+        let mut moved = Vec::new();
+        for (block, d, i, a, j) in candidates {
+            if (i..i + block).any(|k| used_deleted.contains(&(d, k)))
+                || (j..j + block).any(|k| used_added.contains(&(a, k)))
+            {
+                continue;
+            }
+            used_deleted.extend((i..i + block).map(|k| (d, k)));
+            used_added.extend((j..j + block).map(|k| (a, k)));
+
+            let (del, add) = (&deleted[d], &added[a]);
+            moved.push(MovedLines {
+                old_path: del.path.to_path_buf(),
+                old_lines: del.lines[i].0..del.lines[i + block - 1].0 + 1,
+                new_path: add.path.to_path_buf(),
+                new_lines: add.lines[j].0..add.lines[j + block - 1].0 + 1,
+            });
+        }
+        moved.sort_by(|x, y| {
+            (&x.old_path, x.old_lines.start).cmp(&(&y.old_path, y.old_lines.start))
+        });
+        moved
+    }
+
     fn insert_modified(&mut self, path: PathBuf, diff: DiffContent, old: DiffFile, new: DiffFile) {
         let diff = FileDiff::Modified(Modified {
             path,
@@ -102,20 +474,30 @@ impl Diff {
         self.files.push(diff)
     }
 
-    fn insert_moved(&mut self, old_path: PathBuf, new_path: PathBuf) {
+    fn insert_moved(&mut self, old_path: PathBuf, new_path: PathBuf, old: DiffFile, new: DiffFile) {
         let diff = FileDiff::Moved(Moved {
             old_path,
             new_path,
             diff: DiffContent::Empty,
+            old,
+            new,
         });
         self.files.push(diff);
     }
 
-    fn insert_copied(&mut self, old_path: PathBuf, new_path: PathBuf) {
+    fn insert_copied(
+        &mut self,
+        old_path: PathBuf,
+        new_path: PathBuf,
+        old: DiffFile,
+        new: DiffFile,
+    ) {
         let diff = FileDiff::Copied(Copied {
             old_path,
             new_path,
             diff: DiffContent::Empty,
+            old,
+            new,
         });
         self.files.push(diff);
     }
@@ -129,6 +511,210 @@ impl Diff {
         let diff = FileDiff::Deleted(Deleted { path, diff, old });
         self.files.push(diff);
     }
+
+    fn insert_conflicted(
+        &mut self,
+        path: PathBuf,
+        ancestor: Option<DiffFile>,
+        ours: Option<DiffFile>,
+        theirs: Option<DiffFile>,
+    ) {
+        let diff = FileDiff::Conflicted(Conflicted {
+            path,
+            ancestor,
+            ours,
+            theirs,
+        });
+        self.files.push(diff);
+    }
+}
+
+/// How a file came to be, relative to the start of the first of two
+/// composed diffs, see [`Diff::compose`].
+#[derive(Debug, PartialEq, Eq)]
+enum Origin {
+    Added,
+    Modified,
+    Moved(PathBuf),
+    Copied(PathBuf),
+}
+
+/// The path-level change of a file, see [`Diff::compose`].
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize),
+    serde(tag = "type", rename_all = "camelCase")
+)]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum PathChange {
+    Added {
+        path: PathBuf,
+    },
+    Deleted {
+        path: PathBuf,
+    },
+    Modified {
+        path: PathBuf,
+    },
+    #[cfg_attr(feature = "serde", serde(rename_all = "camelCase"))]
+    Moved {
+        old_path: PathBuf,
+        new_path: PathBuf,
+    },
+    #[cfg_attr(feature = "serde", serde(rename_all = "camelCase"))]
+    Copied {
+        old_path: PathBuf,
+        new_path: PathBuf,
+    },
+}
+
+impl PathChange {
+    /// Returns the path of the file after the change, or before it if the
+    /// file was deleted.
+    pub fn path(&self) -> &Path {
+        match self {
+            Self::Added { path } | Self::Deleted { path } | Self::Modified { path } => path,
+            Self::Moved { new_path, .. } | Self::Copied { new_path, .. } => new_path,
+        }
+    }
+}
+
+/// The number of times a line can be added and still start a block of
+/// [`Diff::moved_lines`].
+pub const MAX_MOVED_LINE_MATCHES: usize = 64;
+
+/// A run of consecutive deleted, or added, lines of a file, as pairs of
+/// line numbers and trimmed line contents.
+struct LineRun<'a> {
+    path: &'a Path,
+    lines: Vec<(u32, &'a [u8])>,
+}
+
+/// Collects the runs of consecutive deleted and added lines in `hunks`.
+fn line_runs<'a>(
+    path: &'a Path,
+    hunks: &'a Hunks<Modification>,
+    deleted: &mut Vec<LineRun<'a>>,
+    added: &mut Vec<LineRun<'a>>,
+) {
+    fn push<'a>(runs: &mut Vec<LineRun<'a>>, path: &'a Path, line: &'a Line, line_no: u32) {
+        let line = (line_no, line.as_bytes().trim_ascii());
+        match runs.last_mut() {
+            // Lines that are interrupted by context lines, or are in
+            // different hunks, are not consecutive.
+            Some(run)
+                if run.path == path && run.lines.last().is_some_and(|(n, _)| n + 1 == line_no) =>
+            {
+                run.lines.push(line)
+            },
+            _ => runs.push(LineRun {
+                path,
+                lines: vec![line],
+            }),
+        }
+    }
+
+    for line in hunks.iter().flat_map(|hunk| &hunk.lines) {
+        match line {
+            Modification::Deletion(deletion) => {
+                push(deleted, path, &deletion.line, deletion.line_no)
+            },
+            Modification::Addition(addition) => push(added, path, &addition.line, addition.line_no),
+            Modification::Context { .. } => {},
+        }
+    }
+}
+
+/// A block of lines that was deleted in one place and added in another,
+/// see [`Diff::moved_lines`].
+#[cfg_attr(feature = "serde", derive(Serialize), serde(rename_all = "camelCase"))]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MovedLines {
+    /// The path of the file the lines were deleted from.
+    pub old_path: PathBuf,
+    /// The line numbers of the deleted lines in the old file.
+    pub old_lines: Range<u32>,
+    /// The path of the file the lines were added to.
+    pub new_path: PathBuf,
+    /// The line numbers of the added lines in the new file.
+    pub new_lines: Range<u32>,
+}
+
+/// A changed file, as listed by `git diff --name-status`, see
+/// [`crate::Repository::diff_name_status`].
+#[cfg_attr(feature = "serde", derive(Serialize), serde(rename_all = "camelCase"))]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct NameStatus {
+    /// The status letter of the change: `A`dded, `D`eleted, `M`odified,
+    /// `R`enamed, `C`opied, or `T` if the type of the file changed, e.g.
+    /// from a regular file to a symbolic link.
+    pub status: char,
+    /// How similar, in percent, a renamed or copied file is to the file
+    /// it came from.
+    pub similarity: Option<u8>,
+    /// The path of a renamed or copied file before the change.
+    pub old_path: Option<PathBuf>,
+    /// The path of the file after the change, or before it if the file
+    /// was deleted.
+    pub path: PathBuf,
+}
+
+impl fmt::Display for NameStatus {
+    /// Formats the change as a line of `git diff --name-status`, e.g.
+    /// `M\tsrc/lib.rs` or `R087\told.rs\tnew.rs`.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.status)?;
+        if let Some(similarity) = self.similarity {
+            write!(f, "{similarity:03}")?;
+        }
+        if let Some(old_path) = &self.old_path {
+            write!(f, "\t{}", old_path.display())?;
+        }
+        write!(f, "\t{}", self.path.display())
+    }
+}
+
+/// Options for computing a [`Diff`], see
+/// [`crate::Repository::diff_with_options`].
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Options {
+    binary_extensions: Vec<String>,
+    max_files: Option<usize>,
+}
+
+impl Options {
+    /// Treat files ending in `.<extension>` as binary, e.g. `svg` or
+    /// `min.js`, in addition to the files that `git` detects as binary.
+    ///
+    /// The changes of these files are reported as [`DiffContent::Binary`]
+    /// and no patch is generated for them, so their lines are not counted
+    /// in the [`Stats`].
+    pub fn binary_extension(mut self, extension: impl Into<String>) -> Self {
+        self.binary_extensions.push(extension.into());
+        self
+    }
+
+    /// Only convert the first `max_files` files of the diff, e.g. to bound
+    /// the size of a response for a commit touching thousands of files.
+    ///
+    /// The remaining files are left out without generating their patches,
+    /// see [`Diff::is_truncated`].
+    pub fn max_files(mut self, max_files: usize) -> Self {
+        self.max_files = Some(max_files);
+        self
+    }
+
+    /// Returns `true` if `path` should be treated as binary.
+    pub(crate) fn is_binary(&self, path: &Path) -> bool {
+        let name = match path.file_name() {
+            Some(name) => name.to_string_lossy(),
+            None => return false,
+        };
+        self.binary_extensions.iter().any(|ext| {
+            name.strip_suffix(ext.as_str())
+                .is_some_and(|stem| stem.len() > 1 && stem.ends_with('.'))
+        })
+    }
 }
 
 /// A file that was added within a [`Diff`].
@@ -159,6 +745,10 @@ pub struct Moved {
     /// The new path to this file, relative to the repository root.
     pub new_path: PathBuf,
     pub diff: DiffContent,
+    /// The file at the old path.
+    pub old: DiffFile,
+    /// The file at the new path.
+    pub new: DiffFile,
 }
 
 #[cfg(feature = "serde")]
@@ -167,9 +757,11 @@ impl Serialize for Moved {
     where
         S: Serializer,
     {
-        let mut state = serializer.serialize_struct("Moved", 2)?;
+        let mut state = serializer.serialize_struct("Moved", 4)?;
         state.serialize_field("oldPath", &self.old_path)?;
         state.serialize_field("newPath", &self.new_path)?;
+        state.serialize_field("old", &self.old)?;
+        state.serialize_field("new", &self.new)?;
         // `DiffContent` is not serialized yet for `Moved`, only
         // to keep the serialization same as before.
         state.end()
@@ -185,6 +777,29 @@ pub struct Copied {
     /// The new path to this file, relative to the repository root.
     pub new_path: PathBuf,
     pub diff: DiffContent,
+    /// The file at the old path.
+    pub old: DiffFile,
+    /// The file at the new path.
+    pub new: DiffFile,
+}
+
+/// A file that is in a conflicted, i.e. unmerged, state within a [`Diff`].
+///
+/// The sides of the conflict are those of the index the diff was made
+/// with, see [`crate::Repository::merge_diff`]. Any side may be missing,
+/// e.g. if the file was added on only one side of the merge, and they are
+/// all missing if the index is unknown.
+#[cfg_attr(feature = "serde", derive(Serialize), serde(rename_all = "camelCase"))]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Conflicted {
+    /// The path to this file, relative to the repository root.
+    pub path: PathBuf,
+    /// The file in the common ancestor, if it exists.
+    pub ancestor: Option<DiffFile>,
+    /// Our side of the conflict, if it exists.
+    pub ours: Option<DiffFile>,
+    /// Their side of the conflict, if it exists.
+    pub theirs: Option<DiffFile>,
 }
 
 #[cfg_attr(feature = "serde", derive(Serialize), serde(rename_all = "camelCase"))]
@@ -212,6 +827,39 @@ pub struct Modified {
     pub new: DiffFile,
 }
 
+impl Modified {
+    /// Returns `true` if the file's changes only add, remove or move
+    /// whitespace.
+    ///
+    /// The deleted and added lines of each hunk are compared as a whole,
+    /// with all their whitespace removed, so re-indented lines, as well as
+    /// lines that were split or joined, are whitespace changes. This is
+    /// more lenient than `git diff -w`, which compares line by line and
+    /// still shows split or joined lines. A file whose mode changed, or
+    /// that is binary, is not a whitespace change.
+    pub fn is_whitespace_only(&self) -> bool {
+        if self.old.mode != self.new.mode {
+            return false;
+        }
+        let hunks = match &self.diff {
+            DiffContent::Plain { hunks, .. } => hunks,
+            _ => return false,
+        };
+        hunks.iter().all(|hunk| {
+            let (mut deleted, mut added) = (Vec::<u8>::new(), Vec::<u8>::new());
+            for line in &hunk.lines {
+                let (text, out) = match line {
+                    Modification::Deletion(Deletion { line, .. }) => (line, &mut deleted),
+                    Modification::Addition(Addition { line, .. }) => (line, &mut added),
+                    Modification::Context { .. } => continue,
+                };
+                out.extend(text.as_bytes().iter().filter(|b| !b.is_ascii_whitespace()));
+            }
+            deleted == added
+        })
+    }
+}
+
 /// The set of changes for a given file.
 #[cfg_attr(
     feature = "serde",
@@ -221,13 +869,31 @@ pub struct Modified {
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub enum DiffContent {
     /// The file is a binary file and so no set of changes can be provided.
-    Binary,
+    ///
+    /// The sizes, in bytes, of the old and new versions of the file are
+    /// given instead, where `None` means the file was added or deleted.
+    #[cfg_attr(feature = "serde", serde(rename_all = "camelCase"))]
+    Binary {
+        old_size: Option<u64>,
+        new_size: Option<u64>,
+    },
     /// The set of changes, as [`Hunks`] for a plaintext file.
     #[cfg_attr(feature = "serde", serde(rename_all = "camelCase"))]
     Plain {
         hunks: Hunks<Modification>,
         eof: EofNewLine,
     },
+    /// The file is a symbolic link, so its content is the path it points
+    /// to.
+    ///
+    /// The targets of the old and new versions of the link are given
+    /// instead of a set of changes, where `None` means the link was added
+    /// or deleted.
+    #[cfg_attr(feature = "serde", serde(rename_all = "camelCase"))]
+    SymlinkTarget {
+        old: Option<PathBuf>,
+        new: Option<PathBuf>,
+    },
     Empty,
 }
 
@@ -238,6 +904,150 @@ impl DiffContent {
             _ => None,
         }
     }
+
+    /// Map the line number `line_no` on the given `side` of the diff to
+    /// the corresponding line number on the other side.
+    ///
+    /// If the line is unchanged, the returned line number is the same line
+    /// on the other side, along with [`LineMapping::Context`]. If the line
+    /// was added or deleted, the returned line number is the position on
+    /// the other side at which the change took place, i.e. the line that
+    /// follows it, along with [`LineMapping::Added`] or
+    /// [`LineMapping::Deleted`].
+    ///
+    /// Returns `None` if the content is not [`DiffContent::Plain`], or
+    /// `line_no` is `0`.
+    pub fn map_line(&self, side: Side, line_no: u32) -> Option<(u32, LineMapping)> {
+        let hunks = match self {
+            Self::Plain { hunks, .. } => hunks,
+            _ => return None,
+        };
+        if line_no == 0 {
+            return None;
+        }
+
+        // The offset to apply to lines that come after a hunk.
+        let mut offset: i64 = 0;
+        for hunk in hunks.iter() {
+            let (this, other) = match side {
+                Side::Old => (&hunk.old, &hunk.new),
+                Side::New => (&hunk.new, &hunk.old),
+            };
+            // An empty range starts at the line *before* the hunk.
+            let (first, precedes) = if this.is_empty() {
+                (this.start + 1, this.start < line_no)
+            } else {
+                (this.start, this.end <= line_no)
+            };
+            if precedes {
+                offset += other.len() as i64 - this.len() as i64;
+                continue;
+            }
+            if line_no < first {
+                break;
+            }
+            return hunk.map_line(side, line_no);
+        }
+
+        u32::try_from(line_no as i64 + offset)
+            .ok()
+            .map(|line| (line, LineMapping::Context))
+    }
+
+    /// Whether the changes touch the `lines` of the new version of the
+    /// file, i.e. a line in `lines` was added, or lines were deleted
+    /// between two lines in `lines`.
+    ///
+    /// Changes other than [`DiffContent::Plain`] and
+    /// [`DiffContent::Empty`] replace the whole file, so they touch any
+    /// lines.
+    pub(crate) fn touches(&self, lines: &RangeInclusive<u32>) -> bool {
+        match self {
+            Self::Plain { hunks, .. } => hunks.iter().any(|hunk| hunk.touches(lines)),
+            Self::Empty => false,
+            Self::Binary { .. } | Self::SymlinkTarget { .. } => true,
+        }
+    }
+
+    /// Returns the changes, keeping only the hunks that touch the `lines`
+    /// of the new version of the file, see [`DiffContent::touches`].
+    pub(crate) fn restrict_to(&self, lines: &RangeInclusive<u32>) -> Self {
+        match self {
+            Self::Plain { hunks, eof } => Self::Plain {
+                hunks: Hunks(
+                    hunks
+                        .iter()
+                        .filter(|hunk| hunk.touches(lines))
+                        .cloned()
+                        .collect(),
+                ),
+                eof: eof.clone(),
+            },
+            content => content.clone(),
+        }
+    }
+
+    /// Maps the `lines` of the new version of the file to the lines of
+    /// the old version they come from.
+    ///
+    /// If either end of `lines` is in a block of changed lines, the old
+    /// lines of the whole block are included, as `git log -L` does.
+    /// Deleted lines in between are included too.
+    ///
+    /// Returns `None` if all of `lines` were added, or if the changes
+    /// replace the whole file.
+    pub(crate) fn map_lines_to_old(
+        &self,
+        lines: &RangeInclusive<u32>,
+    ) -> Option<RangeInclusive<u32>> {
+        let hunks = match self {
+            Self::Plain { hunks, .. } => hunks,
+            Self::Empty => return Some(lines.clone()),
+            Self::Binary { .. } | Self::SymlinkTarget { .. } => return None,
+        };
+        let blocks = hunks.iter().flat_map(Hunk::blocks).collect::<Vec<_>>();
+        // Maps `line_no` to the old version, using `in_block` for the lines
+        // that are in a block.
+        let map = |line_no: u32, in_block: fn(&Block) -> Option<u32>| {
+            let mut offset: i64 = 0;
+            for block in &blocks {
+                if line_no < block.new.start {
+                    break;
+                }
+                if line_no < block.new.end {
+                    return in_block(block);
+                }
+                offset += block.old.len() as i64 - block.new.len() as i64;
+            }
+            u32::try_from(line_no as i64 + offset).ok()
+        };
+        let start = map(*lines.start(), |block| Some(block.old.start))?;
+        let end = map(*lines.end(), |block| block.old.end.checked_sub(1))?;
+        (start <= end).then_some(start..=end)
+    }
+}
+
+/// A side of a diff, used by [`DiffContent::map_line`].
+#[cfg_attr(feature = "serde", derive(Serialize), serde(rename_all = "camelCase"))]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Side {
+    /// The old version of the file.
+    Old,
+    /// The new version of the file.
+    New,
+}
+
+/// How a line on one side of a diff relates to the other side, as returned
+/// by [`DiffContent::map_line`].
+#[cfg_attr(feature = "serde", derive(Serialize), serde(rename_all = "camelCase"))]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LineMapping {
+    /// The line is unchanged and exists on both sides.
+    Context,
+    /// The line was added in the new version.
+    Added,
+    /// The line was deleted from the old version.
+    Deleted,
 }
 
 /// File mode in a diff.
@@ -285,6 +1095,7 @@ pub enum FileDiff {
     Modified(Modified),
     Moved(Moved),
     Copied(Copied),
+    Conflicted(Conflicted),
 }
 
 #[cfg(feature = "serde")]
@@ -315,6 +1126,12 @@ impl Serialize for FileDiff {
                 state.serialize_field("oldPath", &x.old_path)?;
                 state.serialize_field("newPath", &x.new_path)?
             },
+            FileDiff::Conflicted(x) => {
+                state.serialize_field("path", &x.path)?;
+                state.serialize_field("ancestor", &x.ancestor)?;
+                state.serialize_field("ours", &x.ours)?;
+                state.serialize_field("theirs", &x.theirs)?
+            },
         }
         state.end()
     }
@@ -326,13 +1143,15 @@ impl Serialize for Diff {
     where
         S: Serializer,
     {
-        let mut state = serializer.serialize_struct("Diff", 6)?;
+        let mut state = serializer.serialize_struct("Diff", 8)?;
         state.serialize_field("added", &self.added().collect::<Vec<_>>())?;
         state.serialize_field("deleted", &self.deleted().collect::<Vec<_>>())?;
         state.serialize_field("moved", &self.moved().collect::<Vec<_>>())?;
         state.serialize_field("copied", &self.copied().collect::<Vec<_>>())?;
         state.serialize_field("modified", &self.modified().collect::<Vec<_>>())?;
+        state.serialize_field("conflicted", &self.conflicted().collect::<Vec<_>>())?;
         state.serialize_field("stats", &self.stats())?;
+        state.serialize_field("truncated", &self.truncated)?;
         state.end()
     }
 }
@@ -369,6 +1188,162 @@ pub struct Hunk<T> {
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub struct Hunks<T>(pub Vec<Hunk<T>>);
 
+impl<T> Hunk<T> {
+    /// The code context of the hunk, i.e. the text following the line
+    /// ranges of its [`Hunk::header`], e.g. `fn main() {` for
+    /// `@@ -1,3 +1,4 @@ fn main() {`.
+    ///
+    /// This is the line `git` found to enclose the hunk, using the
+    /// `xfuncname` of the file's diff driver. Returns `None` if the header
+    /// has no context, or if it is not valid UTF-8.
+    pub fn function_context(&self) -> Option<&str> {
+        let header = std::str::from_utf8(self.header.as_bytes()).ok()?;
+        let (_, context) = header.strip_prefix("@@")?.split_once("@@")?;
+        let context = context.trim();
+        (!context.is_empty()).then_some(context)
+    }
+}
+
+impl Hunk<Modification> {
+    /// Map a line number within this hunk to the other side, see
+    /// [`DiffContent::map_line`].
+    fn map_line(&self, side: Side, line_no: u32) -> Option<(u32, LineMapping)> {
+        let next = |range: &Range<u32>| {
+            if range.is_empty() {
+                range.start + 1
+            } else {
+                range.start
+            }
+        };
+        // The next line number on each side of the hunk.
+        let mut old = next(&self.old);
+        let mut new = next(&self.new);
+        for line in &self.lines {
+            match line {
+                Modification::Context {
+                    line_no_old,
+                    line_no_new,
+                    ..
+                } => {
+                    let (this, other) = match side {
+                        Side::Old => (*line_no_old, *line_no_new),
+                        Side::New => (*line_no_new, *line_no_old),
+                    };
+                    if this == line_no {
+                        return Some((other, LineMapping::Context));
+                    }
+                    old = line_no_old + 1;
+                    new = line_no_new + 1;
+                },
+                Modification::Addition(addition) => {
+                    if side == Side::New && addition.line_no == line_no {
+                        return Some((old, LineMapping::Added));
+                    }
+                    new = addition.line_no + 1;
+                },
+                Modification::Deletion(deletion) => {
+                    if side == Side::Old && deletion.line_no == line_no {
+                        return Some((new, LineMapping::Deleted));
+                    }
+                    old = deletion.line_no + 1;
+                },
+            }
+        }
+        None
+    }
+}
+
+/// A run of deleted and added lines, between unchanged lines, given as
+/// the ranges of their line numbers in the old and new versions.
+///
+/// The range of a side without lines is empty, and starts at the line
+/// that follows the block.
+struct Block {
+    old: Range<u32>,
+    new: Range<u32>,
+}
+
+impl Hunk<Modification> {
+    /// Returns the [`Block`]s of changed lines of the hunk.
+    fn blocks(&self) -> Vec<Block> {
+        let next = |range: &Range<u32>| {
+            if range.is_empty() {
+                range.start + 1
+            } else {
+                range.start
+            }
+        };
+        // The next line number on each side of the hunk.
+        let mut old = next(&self.old);
+        let mut new = next(&self.new);
+        let mut blocks = Vec::new();
+        let mut block: Option<Block> = None;
+        for line in &self.lines {
+            match line {
+                Modification::Context {
+                    line_no_old,
+                    line_no_new,
+                    ..
+                } => {
+                    blocks.extend(block.take());
+                    old = line_no_old + 1;
+                    new = line_no_new + 1;
+                },
+                Modification::Deletion(deletion) => {
+                    old = deletion.line_no + 1;
+                    block
+                        .get_or_insert(Block {
+                            old: deletion.line_no..deletion.line_no,
+                            new: new..new,
+                        })
+                        .old
+                        .end = old;
+                },
+                Modification::Addition(addition) => {
+                    new = addition.line_no + 1;
+                    block
+                        .get_or_insert(Block {
+                            old: old..old,
+                            new: addition.line_no..addition.line_no,
+                        })
+                        .new
+                        .end = new;
+                },
+            }
+        }
+        blocks.extend(block);
+        blocks
+    }
+
+    /// Whether the hunk adds a line in `lines` of the new version of the
+    /// file, or deletes lines between two lines in `lines`.
+    fn touches(&self, lines: &RangeInclusive<u32>) -> bool {
+        // The next line number of the new version.
+        let mut new = if self.new.is_empty() {
+            self.new.start + 1
+        } else {
+            self.new.start
+        };
+        for line in &self.lines {
+            match line {
+                Modification::Context { line_no_new, .. } => new = line_no_new + 1,
+                Modification::Addition(addition) => {
+                    if lines.contains(&addition.line_no) {
+                        return true;
+                    }
+                    new = addition.line_no + 1;
+                },
+                Modification::Deletion(_) => {
+                    if *lines.start() < new && new <= *lines.end() {
+                        return true;
+                    }
+                },
+            }
+        }
+        false
+    }
+}
+
 impl<T> Default for Hunks<T> {
     fn default() -> Self {
         Self(Default::default())
@@ -403,6 +1378,41 @@ impl Line {
     pub fn from_utf8_lossy(&self) -> Cow<str> {
         String::from_utf8_lossy(&self.0)
     }
+
+    /// Returns the [`Whitespace`] markers of the line, excluding its line
+    /// terminator.
+    pub fn whitespace(&self) -> Whitespace {
+        let content = self
+            .0
+            .strip_suffix(b"\n")
+            .map(|content| content.strip_suffix(b"\r").unwrap_or(content))
+            .unwrap_or(&self.0);
+        let start = content
+            .iter()
+            .rposition(|b| !matches!(b, b' ' | b'\t'))
+            .map_or(0, |i| i + 1);
+        Whitespace {
+            trailing: (start < content.len()).then_some(start..content.len()),
+            tabs: content
+                .iter()
+                .enumerate()
+                .filter_map(|(i, b)| (*b == b'\t').then_some(i))
+                .collect(),
+        }
+    }
+}
+
+/// The whitespace markers of a [`Line`], e.g. for highlighting trailing
+/// whitespace in added lines.
+///
+/// The positions are byte offsets into the line.
+#[cfg_attr(feature = "serde", derive(Serialize), serde(rename_all = "camelCase"))]
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Whitespace {
+    /// The range of trailing spaces and tabs, if any.
+    pub trailing: Option<Range<usize>>,
+    /// The positions of all tabs.
+    pub tabs: Vec<usize>,
 }
 
 impl From<Vec<u8>> for Line {
@@ -554,4 +1564,14 @@ impl Modification {
             line_no_new,
         }
     }
+
+    /// Returns the [`Whitespace`] markers of the line if it is an
+    /// [`Modification::Addition`], since only whitespace that is
+    /// introduced is worth highlighting.
+    pub fn whitespace(&self) -> Option<Whitespace> {
+        match self {
+            Self::Addition(addition) => Some(addition.line.whitespace()),
+            Self::Deletion(_) | Self::Context { .. } => None,
+        }
+    }
 }
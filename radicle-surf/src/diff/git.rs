@@ -15,7 +15,10 @@
 // You should have received a copy of the GNU General Public License
 // along with this program. If not, see <https://www.gnu.org/licenses/>.
 
-use std::convert::TryFrom;
+use std::{
+    convert::TryFrom,
+    path::{Path, PathBuf},
+};
 
 use super::{
     Diff,
@@ -27,6 +30,7 @@ use super::{
     Hunks,
     Line,
     Modification,
+    Options,
     Stats,
 };
 
@@ -118,6 +122,24 @@ impl<'a> TryFrom<git2::DiffFile<'a>> for DiffFile {
     }
 }
 
+impl TryFrom<git2::IndexEntry> for DiffFile {
+    type Error = error::FileMode;
+
+    fn try_from(value: git2::IndexEntry) -> Result<Self, Self::Error> {
+        let mode = match value.mode {
+            0o100644 => git2::FileMode::Blob,
+            0o100755 => git2::FileMode::BlobExecutable,
+            0o120000 => git2::FileMode::Link,
+            0o160000 => git2::FileMode::Commit,
+            _ => git2::FileMode::Unreadable,
+        };
+        Ok(Self {
+            mode: mode.try_into()?,
+            oid: value.id.into(),
+        })
+    }
+}
+
 impl TryFrom<git2::FileMode> for FileMode {
     type Error = error::FileMode;
 
@@ -154,37 +176,10 @@ impl TryFrom<git2::Patch<'_>> for DiffContent {
         let mut new_missing_eof = false;
 
         for h in 0..patch.num_hunks() {
-            let (hunk, hunk_lines) = patch.hunk(h)?;
-            let header = Line(hunk.header().to_owned());
-            let mut lines: Vec<Modification> = Vec::new();
-
-            for l in 0..hunk_lines {
-                let line = patch.line_in_hunk(h, l)?;
-                match line.origin_value() {
-                    git2::DiffLineType::ContextEOFNL => {
-                        new_missing_eof = true;
-                        old_missing_eof = true;
-                        continue;
-                    },
-                    git2::DiffLineType::AddEOFNL => {
-                        old_missing_eof = true;
-                        continue;
-                    },
-                    git2::DiffLineType::DeleteEOFNL => {
-                        new_missing_eof = true;
-                        continue;
-                    },
-                    _ => {},
-                }
-                let line = Modification::try_from(line)?;
-                lines.push(line);
-            }
-            hunks.push(Hunk {
-                header,
-                lines,
-                old: hunk.old_start()..hunk.old_start() + hunk.old_lines(),
-                new: hunk.new_start()..hunk.new_start() + hunk.new_lines(),
-            });
+            let (hunk, old_missing, new_missing) = hunk(&patch, h)?;
+            old_missing_eof |= old_missing;
+            new_missing_eof |= new_missing;
+            hunks.push(hunk);
         }
         let eof = match (old_missing_eof, new_missing_eof) {
             (true, true) => EofNewLine::BothMissing,
@@ -199,6 +194,82 @@ impl TryFrom<git2::Patch<'_>> for DiffContent {
     }
 }
 
+/// Converts the hunk at index `h` of `patch`.
+///
+/// Also returns whether the old and new side of the hunk are missing
+/// the newline at the end of the file, respectively.
+fn hunk(patch: &git2::Patch, h: usize) -> Result<(Hunk<Modification>, bool, bool), error::Hunk> {
+    let (hunk, hunk_lines) = patch.hunk(h)?;
+    let header = Line(hunk.header().to_owned());
+    let mut lines: Vec<Modification> = Vec::new();
+    let mut old_missing_eof = false;
+    let mut new_missing_eof = false;
+
+    for l in 0..hunk_lines {
+        let line = patch.line_in_hunk(h, l)?;
+        match line.origin_value() {
+            git2::DiffLineType::ContextEOFNL => {
+                new_missing_eof = true;
+                old_missing_eof = true;
+                continue;
+            },
+            git2::DiffLineType::AddEOFNL => {
+                old_missing_eof = true;
+                continue;
+            },
+            git2::DiffLineType::DeleteEOFNL => {
+                new_missing_eof = true;
+                continue;
+            },
+            _ => {},
+        }
+        let line = Modification::try_from(line)?;
+        lines.push(line);
+    }
+    let hunk = Hunk {
+        header,
+        lines,
+        old: hunk.old_start()..hunk.old_start() + hunk.old_lines(),
+        new: hunk.new_start()..hunk.new_start() + hunk.new_lines(),
+    };
+    Ok((hunk, old_missing_eof, new_missing_eof))
+}
+
+/// Walks `git_diff` one file at a time, calling `file_cb` with the path of
+/// each file and `hunk_cb` with each of its [`Hunk`]s.
+///
+/// Only the patch of the file currently being visited is held in memory.
+/// If either callback returns `false` the walk stops early.
+pub(crate) fn foreach<F, H>(
+    git_diff: &git2::Diff<'_>,
+    mut file_cb: F,
+    mut hunk_cb: H,
+) -> Result<(), error::Diff>
+where
+    F: FnMut(&Path) -> bool,
+    H: FnMut(&Path, Hunk<Modification>) -> bool,
+{
+    for (idx, delta) in git_diff.deltas().enumerate() {
+        let path = delta
+            .new_file()
+            .path()
+            .or_else(|| delta.old_file().path())
+            .ok_or(error::Diff::PathUnavailable)?;
+        if !file_cb(path) {
+            return Ok(());
+        }
+        if let Some(patch) = git2::Patch::from_diff(git_diff, idx)? {
+            for h in 0..patch.num_hunks() {
+                let (hunk, _, _) = hunk(&patch, h)?;
+                if !hunk_cb(path, hunk) {
+                    return Ok(());
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
 impl<'a> TryFrom<git2::DiffLine<'a>> for Modification {
     type Error = error::Modification;
 
@@ -226,47 +297,98 @@ impl<'a> TryFrom<git2::Diff<'a>> for Diff {
     type Error = error::Diff;
 
     fn try_from(git_diff: git2::Diff) -> Result<Diff, Self::Error> {
+        Diff::from_git(git_diff, None, None, &Options::default())
+    }
+}
+
+impl Diff {
+    /// Convert `git_diff` into a [`Diff`], respecting the given `opts`.
+    ///
+    /// The sizes of binary files are read from `odb`, if given, rather
+    /// than from `git_diff`, which only knows them for the files whose
+    /// content it loaded.
+    ///
+    /// The ancestor, our and their sides of conflicted files are read from
+    /// the conflicts of `index`, if given, which `git_diff` was made with.
+    ///
+    /// Only the files kept by [`Options::max_files`] are patched, so the
+    /// insertions and deletions of the [`Stats`] only count those files.
+    pub(crate) fn from_git(
+        git_diff: git2::Diff<'_>,
+        odb: Option<&git2::Odb<'_>>,
+        index: Option<&git2::Index>,
+        opts: &Options,
+    ) -> Result<Diff, error::Diff> {
         use git2::Delta;
 
         let mut diff = Diff::new();
-        diff.stats = git_diff.stats()?.into();
+        let mut stats = Stats {
+            files_changed: git_diff.deltas().len(),
+            insertions: 0,
+            deletions: 0,
+        };
 
         for (idx, delta) in git_diff.deltas().enumerate() {
+            if opts.max_files.is_some_and(|max| idx >= max) {
+                diff.truncated = true;
+                break;
+            }
+            // Files forced to binary are neither patched nor counted in the
+            // insertions and deletions.
+            let forced = delta
+                .new_file()
+                .path()
+                .or_else(|| delta.old_file().path())
+                .is_some_and(|path| opts.is_binary(path));
+            let patch = if forced {
+                None
+            } else {
+                git2::Patch::from_diff(&git_diff, idx)?
+            };
+            if let Some(patch) = &patch {
+                let (_, insertions, deletions) = patch.line_stats()?;
+                stats.insertions += insertions;
+                stats.deletions += deletions;
+            }
             match delta.status() {
-                Delta::Added => created(&mut diff, &git_diff, idx, &delta)?,
-                Delta::Deleted => deleted(&mut diff, &git_diff, idx, &delta)?,
-                Delta::Modified => modified(&mut diff, &git_diff, idx, &delta)?,
+                Delta::Added => created(&mut diff, patch, &delta, odb, opts)?,
+                Delta::Deleted => deleted(&mut diff, patch, &delta, odb, opts)?,
+                Delta::Modified => modified(&mut diff, patch, &delta, odb, opts)?,
                 Delta::Renamed => renamed(&mut diff, &delta)?,
                 Delta::Copied => copied(&mut diff, &delta)?,
+                Delta::Conflicted => conflicted(&mut diff, &delta, index)?,
                 status => {
                     return Err(error::Diff::DeltaUnhandled(status));
                 },
             }
         }
 
+        diff.stats = stats;
         Ok(diff)
     }
 }
 
 fn created(
     diff: &mut Diff,
-    git_diff: &git2::Diff<'_>,
-    idx: usize,
+    patch: Option<git2::Patch<'_>>,
     delta: &git2::DiffDelta<'_>,
+    odb: Option<&git2::Odb<'_>>,
+    opts: &Options,
 ) -> Result<(), error::Diff> {
     let diff_file = delta.new_file();
-    let is_binary = diff_file.is_binary();
     let path = diff_file
         .path()
         .ok_or(error::Diff::PathUnavailable)?
         .to_path_buf();
+    let is_binary = diff_file.is_binary();
     let new = DiffFile::try_from(diff_file)?;
 
-    let patch = git2::Patch::from_diff(git_diff, idx)?;
-    if let Some(patch) = patch {
-        diff.insert_added(path, DiffContent::try_from(patch)?, new);
+    if opts.is_binary(&path) {
+        diff.insert_added(path, binary(delta, odb)?, new);
+    } else if let Some(patch) = patch {
+        diff.insert_added(path, patch_content(patch, odb)?, new);
     } else if is_binary {
-        diff.insert_added(path, DiffContent::Binary, new);
+        diff.insert_added(path, binary(delta, odb)?, new);
     } else {
         return Err(error::Diff::PatchUnavailable(path));
     }
@@ -275,23 +397,25 @@ fn created(
 
 fn deleted(
     diff: &mut Diff,
-    git_diff: &git2::Diff<'_>,
-    idx: usize,
+    patch: Option<git2::Patch<'_>>,
     delta: &git2::DiffDelta<'_>,
+    odb: Option<&git2::Odb<'_>>,
+    opts: &Options,
 ) -> Result<(), error::Diff> {
     let diff_file = delta.old_file();
-    let is_binary = diff_file.is_binary();
     let path = diff_file
         .path()
         .ok_or(error::Diff::PathUnavailable)?
         .to_path_buf();
-    let patch = git2::Patch::from_diff(git_diff, idx)?;
+    let is_binary = diff_file.is_binary();
     let old = DiffFile::try_from(diff_file)?;
 
-    if let Some(patch) = patch {
-        diff.insert_deleted(path, DiffContent::try_from(patch)?, old);
+    if opts.is_binary(&path) {
+        diff.insert_deleted(path, binary(delta, odb)?, old);
+    } else if let Some(patch) = patch {
+        diff.insert_deleted(path, patch_content(patch, odb)?, old);
     } else if is_binary {
-        diff.insert_deleted(path, DiffContent::Binary, old);
+        diff.insert_deleted(path, binary(delta, odb)?, old);
     } else {
         return Err(error::Diff::PatchUnavailable(path));
     }
@@ -300,30 +424,104 @@ fn deleted(
 
 fn modified(
     diff: &mut Diff,
-    git_diff: &git2::Diff<'_>,
-    idx: usize,
+    patch: Option<git2::Patch<'_>>,
     delta: &git2::DiffDelta<'_>,
+    odb: Option<&git2::Odb<'_>>,
+    opts: &Options,
 ) -> Result<(), error::Diff> {
     let diff_file = delta.new_file();
     let path = diff_file
         .path()
         .ok_or(error::Diff::PathUnavailable)?
         .to_path_buf();
-    let patch = git2::Patch::from_diff(git_diff, idx)?;
     let old = DiffFile::try_from(delta.old_file())?;
     let new = DiffFile::try_from(delta.new_file())?;
 
-    if let Some(patch) = patch {
-        diff.insert_modified(path, DiffContent::try_from(patch)?, old, new);
+    if opts.is_binary(&path) {
+        diff.insert_modified(path, binary(delta, odb)?, old, new);
+        Ok(())
+    } else if let Some(patch) = patch {
+        diff.insert_modified(path, patch_content(patch, odb)?, old, new);
         Ok(())
     } else if diff_file.is_binary() {
-        diff.insert_modified(path, DiffContent::Binary, old, new);
+        diff.insert_modified(path, binary(delta, odb)?, old, new);
         Ok(())
     } else {
         Err(error::Diff::PatchUnavailable(path))
     }
 }
 
+/// Converts `patch` into [`DiffContent`], unless `git` detected the file
+/// as binary while loading its content, or the file is a symbolic link.
+fn patch_content(
+    patch: git2::Patch<'_>,
+    odb: Option<&git2::Odb<'_>>,
+) -> Result<DiffContent, error::Hunk> {
+    let delta = patch.delta();
+    if delta.flags().is_binary() {
+        Ok(binary(&delta, odb)?)
+    } else if is_symlink(&delta) {
+        symlink_target(&patch)
+    } else {
+        DiffContent::try_from(patch)
+    }
+}
+
+/// Returns whether the files of `delta` that exist are symbolic links.
+fn is_symlink(delta: &git2::DiffDelta<'_>) -> bool {
+    let files = [delta.old_file(), delta.new_file()];
+    let mut existing = files.iter().filter(|file| file.exists()).peekable();
+    existing.peek().is_some() && existing.all(|file| file.mode() == git2::FileMode::Link)
+}
+
+/// Returns [`DiffContent::SymlinkTarget`] with the targets of the links of
+/// `patch` that exist, which are the deleted and added lines of the patch.
+fn symlink_target(patch: &git2::Patch<'_>) -> Result<DiffContent, error::Hunk> {
+    let mut old = Vec::new();
+    let mut new = Vec::new();
+    for h in 0..patch.num_hunks() {
+        for l in 0..patch.num_lines_in_hunk(h)? {
+            let line = patch.line_in_hunk(h, l)?;
+            match line.origin_value() {
+                git2::DiffLineType::Deletion => old.extend_from_slice(line.content()),
+                git2::DiffLineType::Addition => new.extend_from_slice(line.content()),
+                _ => {},
+            }
+        }
+    }
+    let delta = patch.delta();
+    let target = |file: git2::DiffFile<'_>, target: Vec<u8>| {
+        file.exists()
+            .then(|| PathBuf::from(String::from_utf8_lossy(&target).into_owned()))
+    };
+    Ok(DiffContent::SymlinkTarget {
+        old: target(delta.old_file(), old),
+        new: target(delta.new_file(), new),
+    })
+}
+
+/// Returns [`DiffContent::Binary`] with the sizes of the files of `delta`
+/// that exist.
+///
+/// The sizes are read from the object headers in `odb`, without loading
+/// the blobs. Without `odb`, they are the sizes known to `delta`.
+fn binary(
+    delta: &git2::DiffDelta<'_>,
+    odb: Option<&git2::Odb<'_>>,
+) -> Result<DiffContent, git2::Error> {
+    let size = |file: git2::DiffFile<'_>| -> Result<_, git2::Error> {
+        Ok(match odb {
+            _ if !file.exists() => None,
+            Some(odb) => Some(odb.read_header(file.id())?.0 as u64),
+            None => Some(file.size()),
+        })
+    };
+    Ok(DiffContent::Binary {
+        old_size: size(delta.old_file())?,
+        new_size: size(delta.new_file())?,
+    })
+}
+
 fn renamed(diff: &mut Diff, delta: &git2::DiffDelta<'_>) -> Result<(), error::Diff> {
     let old = delta
         .old_file()
@@ -334,7 +532,12 @@ fn renamed(diff: &mut Diff, delta: &git2::DiffDelta<'_>) -> Result<(), error::Di
         .path()
         .ok_or(error::Diff::PathUnavailable)?;
 
-    diff.insert_moved(old.to_path_buf(), new.to_path_buf());
+    diff.insert_moved(
+        old.to_path_buf(),
+        new.to_path_buf(),
+        DiffFile::try_from(delta.old_file())?,
+        DiffFile::try_from(delta.new_file())?,
+    );
     Ok(())
 }
 
@@ -348,6 +551,37 @@ fn copied(diff: &mut Diff, delta: &git2::DiffDelta<'_>) -> Result<(), error::Dif
         .path()
         .ok_or(error::Diff::PathUnavailable)?;
 
-    diff.insert_copied(old.to_path_buf(), new.to_path_buf());
+    diff.insert_copied(
+        old.to_path_buf(),
+        new.to_path_buf(),
+        DiffFile::try_from(delta.old_file())?,
+        DiffFile::try_from(delta.new_file())?,
+    );
+    Ok(())
+}
+
+fn conflicted(
+    diff: &mut Diff,
+    delta: &git2::DiffDelta<'_>,
+    index: Option<&git2::Index>,
+) -> Result<(), error::Diff> {
+    let path = delta
+        .new_file()
+        .path()
+        .or_else(|| delta.old_file().path())
+        .ok_or(error::Diff::PathUnavailable)?
+        .to_path_buf();
+
+    // The stages of the index entries of a conflicted file are 1 for the
+    // ancestor, 2 for our side and 3 for their side.
+    let side = |stage: i32| {
+        index
+            .and_then(|index| index.get_path(&path, stage))
+            .map(DiffFile::try_from)
+            .transpose()
+    };
+    let (ancestor, ours, theirs) = (side(1)?, side(2)?, side(3)?);
+
+    diff.insert_conflicted(path, ancestor, ours, theirs);
     Ok(())
 }
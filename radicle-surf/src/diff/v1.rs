@@ -0,0 +1,558 @@
+// Copyright © 2022 The Radicle Git Contributors
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Version 1 of the wire format of a [`super::Diff`], see
+//! [`super::Diff::to_json_v1`].
+//!
+//! Unlike the `Serialize` implementations of the [`super`] types, which
+//! follow their Rust layout, the types of this module are the format
+//! itself. Their layout only changes with a new version of the format, so
+//! that other components can rely on it.
+//!
+//! A diff is an object with the fields:
+//!
+//! * `version`: always `1`.
+//! * `files`: the changed [`File`]s, tagged by their `change`.
+//! * `stats`: the [`Stats`] of the diff.
+//! * `truncated`: whether files were left out of the diff.
+//!
+//! Lines, and hunk headers, are strings if they are valid UTF-8, and
+//! otherwise objects with their bytes in a `base64` field, see [`Text`].
+//! Paths are always strings, and paths that are not valid UTF-8 are
+//! converted lossily.
+
+use std::{convert::TryFrom, ops, path::Path, str::FromStr as _};
+
+use git_ext::Oid;
+use serde::{Deserialize, Serialize};
+
+use super::{Hunks, Line};
+
+pub mod error {
+    use thiserror::Error;
+
+    #[derive(Debug, Error)]
+    #[non_exhaustive]
+    pub enum Decode {
+        #[error("invalid base64 content: {0}")]
+        Base64(#[from] base64::DecodeError),
+        #[error("invalid object id {0}")]
+        Oid(String),
+        #[error(transparent)]
+        Json(#[from] serde_json::Error),
+        #[error("unsupported diff format version {0}")]
+        UnsupportedVersion(u32),
+    }
+}
+
+/// The version of the format of this module.
+pub const VERSION: u32 = 1;
+
+/// A [`super::Diff`] in the version 1 format.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Diff {
+    pub version: u32,
+    pub files: Vec<File>,
+    pub stats: Stats,
+    pub truncated: bool,
+}
+
+/// A changed file.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "change", rename_all = "camelCase")]
+pub enum File {
+    Added {
+        path: String,
+        new: Blob,
+        content: Content,
+    },
+    Deleted {
+        path: String,
+        old: Blob,
+        content: Content,
+    },
+    Modified {
+        path: String,
+        old: Blob,
+        new: Blob,
+        content: Content,
+    },
+    #[serde(rename_all = "camelCase")]
+    Moved {
+        old_path: String,
+        new_path: String,
+        old: Blob,
+        new: Blob,
+        content: Content,
+    },
+    #[serde(rename_all = "camelCase")]
+    Copied {
+        old_path: String,
+        new_path: String,
+        old: Blob,
+        new: Blob,
+        content: Content,
+    },
+    Conflicted {
+        path: String,
+        ancestor: Option<Blob>,
+        ours: Option<Blob>,
+        theirs: Option<Blob>,
+    },
+}
+
+/// A side of a changed file.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Blob {
+    /// The hex object id of the blob.
+    pub oid: String,
+    pub mode: Mode,
+}
+
+/// The mode of a [`Blob`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Mode {
+    Blob,
+    BlobExecutable,
+    Tree,
+    Link,
+    Commit,
+}
+
+/// The changes of a [`File`].
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum Content {
+    #[serde(rename_all = "camelCase")]
+    Plain {
+        hunks: Vec<Hunk>,
+        eof: Eof,
+    },
+    #[serde(rename_all = "camelCase")]
+    Binary {
+        old_size: Option<u64>,
+        new_size: Option<u64>,
+    },
+    #[serde(rename_all = "camelCase")]
+    SymlinkTarget {
+        old: Option<String>,
+        new: Option<String>,
+    },
+    Empty,
+}
+
+/// Which sides of a [`Content::Plain`] file have no newline at their end.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Eof {
+    OldMissing,
+    NewMissing,
+    BothMissing,
+    NoneMissing,
+}
+
+/// A hunk of a [`Content::Plain`] file.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Hunk {
+    pub header: Text,
+    /// The lines of the old file in the hunk, as a half-open range.
+    pub old: Range,
+    /// The lines of the new file in the hunk, as a half-open range.
+    pub new: Range,
+    pub lines: Vec<Change>,
+}
+
+/// A half-open range of line numbers.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Range {
+    pub start: u32,
+    pub end: u32,
+}
+
+/// A line of a [`Hunk`].
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum Change {
+    #[serde(rename_all = "camelCase")]
+    Addition { line: Text, line_no: u32 },
+    #[serde(rename_all = "camelCase")]
+    Deletion { line: Text, line_no: u32 },
+    #[serde(rename_all = "camelCase")]
+    Context {
+        line: Text,
+        line_no_old: u32,
+        line_no_new: u32,
+    },
+}
+
+/// The [`super::Stats`] of a [`Diff`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Stats {
+    pub files_changed: usize,
+    pub insertions: usize,
+    pub deletions: usize,
+}
+
+/// The bytes of a line, as a string if they are valid UTF-8, or as an
+/// object with a `base64` field otherwise.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum Text {
+    Utf8(String),
+    Base64 { base64: String },
+}
+
+impl From<&super::Diff> for Diff {
+    fn from(diff: &super::Diff) -> Self {
+        Self {
+            version: VERSION,
+            files: diff.files.iter().map(File::from).collect(),
+            stats: Stats {
+                files_changed: diff.stats.files_changed,
+                insertions: diff.stats.insertions,
+                deletions: diff.stats.deletions,
+            },
+            truncated: diff.truncated,
+        }
+    }
+}
+
+impl TryFrom<Diff> for super::Diff {
+    type Error = error::Decode;
+
+    fn try_from(diff: Diff) -> Result<Self, Self::Error> {
+        if diff.version != VERSION {
+            return Err(error::Decode::UnsupportedVersion(diff.version));
+        }
+        Ok(Self {
+            files: diff
+                .files
+                .into_iter()
+                .map(super::FileDiff::try_from)
+                .collect::<Result<_, _>>()?,
+            stats: super::Stats {
+                files_changed: diff.stats.files_changed,
+                insertions: diff.stats.insertions,
+                deletions: diff.stats.deletions,
+            },
+            truncated: diff.truncated,
+        })
+    }
+}
+
+impl From<&super::FileDiff> for File {
+    fn from(file: &super::FileDiff) -> Self {
+        use super::FileDiff;
+
+        match file {
+            FileDiff::Added(added) => Self::Added {
+                path: path(&added.path),
+                new: Blob::from(&added.new),
+                content: Content::from(&added.diff),
+            },
+            FileDiff::Deleted(deleted) => Self::Deleted {
+                path: path(&deleted.path),
+                old: Blob::from(&deleted.old),
+                content: Content::from(&deleted.diff),
+            },
+            FileDiff::Modified(modified) => Self::Modified {
+                path: path(&modified.path),
+                old: Blob::from(&modified.old),
+                new: Blob::from(&modified.new),
+                content: Content::from(&modified.diff),
+            },
+            FileDiff::Moved(moved) => Self::Moved {
+                old_path: path(&moved.old_path),
+                new_path: path(&moved.new_path),
+                old: Blob::from(&moved.old),
+                new: Blob::from(&moved.new),
+                content: Content::from(&moved.diff),
+            },
+            FileDiff::Copied(copied) => Self::Copied {
+                old_path: path(&copied.old_path),
+                new_path: path(&copied.new_path),
+                old: Blob::from(&copied.old),
+                new: Blob::from(&copied.new),
+                content: Content::from(&copied.diff),
+            },
+            FileDiff::Conflicted(conflicted) => Self::Conflicted {
+                path: path(&conflicted.path),
+                ancestor: conflicted.ancestor.as_ref().map(Blob::from),
+                ours: conflicted.ours.as_ref().map(Blob::from),
+                theirs: conflicted.theirs.as_ref().map(Blob::from),
+            },
+        }
+    }
+}
+
+impl TryFrom<File> for super::FileDiff {
+    type Error = error::Decode;
+
+    fn try_from(file: File) -> Result<Self, Self::Error> {
+        use super::{Added, Conflicted, Copied, Deleted, DiffFile, Modified, Moved};
+
+        Ok(match file {
+            File::Added { path, new, content } => Self::Added(Added {
+                path: path.into(),
+                diff: content.try_into()?,
+                new: new.try_into()?,
+            }),
+            File::Deleted { path, old, content } => Self::Deleted(Deleted {
+                path: path.into(),
+                diff: content.try_into()?,
+                old: old.try_into()?,
+            }),
+            File::Modified {
+                path,
+                old,
+                new,
+                content,
+            } => Self::Modified(Modified {
+                path: path.into(),
+                diff: content.try_into()?,
+                old: old.try_into()?,
+                new: new.try_into()?,
+            }),
+            File::Moved {
+                old_path,
+                new_path,
+                old,
+                new,
+                content,
+            } => Self::Moved(Moved {
+                old_path: old_path.into(),
+                new_path: new_path.into(),
+                diff: content.try_into()?,
+                old: old.try_into()?,
+                new: new.try_into()?,
+            }),
+            File::Copied {
+                old_path,
+                new_path,
+                old,
+                new,
+                content,
+            } => Self::Copied(Copied {
+                old_path: old_path.into(),
+                new_path: new_path.into(),
+                diff: content.try_into()?,
+                old: old.try_into()?,
+                new: new.try_into()?,
+            }),
+            File::Conflicted {
+                path,
+                ancestor,
+                ours,
+                theirs,
+            } => Self::Conflicted(Conflicted {
+                path: path.into(),
+                ancestor: ancestor.map(DiffFile::try_from).transpose()?,
+                ours: ours.map(DiffFile::try_from).transpose()?,
+                theirs: theirs.map(DiffFile::try_from).transpose()?,
+            }),
+        })
+    }
+}
+
+impl From<&super::DiffFile> for Blob {
+    fn from(file: &super::DiffFile) -> Self {
+        use super::FileMode;
+
+        Self {
+            oid: file.oid.to_string(),
+            mode: match file.mode {
+                FileMode::Blob => Mode::Blob,
+                FileMode::BlobExecutable => Mode::BlobExecutable,
+                FileMode::Tree => Mode::Tree,
+                FileMode::Link => Mode::Link,
+                FileMode::Commit => Mode::Commit,
+            },
+        }
+    }
+}
+
+impl TryFrom<Blob> for super::DiffFile {
+    type Error = error::Decode;
+
+    fn try_from(blob: Blob) -> Result<Self, Self::Error> {
+        use super::FileMode;
+
+        Ok(Self {
+            oid: Oid::from_str(&blob.oid).map_err(|_| error::Decode::Oid(blob.oid))?,
+            mode: match blob.mode {
+                Mode::Blob => FileMode::Blob,
+                Mode::BlobExecutable => FileMode::BlobExecutable,
+                Mode::Tree => FileMode::Tree,
+                Mode::Link => FileMode::Link,
+                Mode::Commit => FileMode::Commit,
+            },
+        })
+    }
+}
+
+impl From<&super::DiffContent> for Content {
+    fn from(content: &super::DiffContent) -> Self {
+        use super::{DiffContent, EofNewLine};
+
+        match content {
+            DiffContent::Plain { hunks, eof } => Self::Plain {
+                hunks: hunks.iter().map(Hunk::from).collect(),
+                eof: match eof {
+                    EofNewLine::OldMissing => Eof::OldMissing,
+                    EofNewLine::NewMissing => Eof::NewMissing,
+                    EofNewLine::BothMissing => Eof::BothMissing,
+                    EofNewLine::NoneMissing => Eof::NoneMissing,
+                },
+            },
+            DiffContent::Binary { old_size, new_size } => Self::Binary {
+                old_size: *old_size,
+                new_size: *new_size,
+            },
+            DiffContent::SymlinkTarget { old, new } => Self::SymlinkTarget {
+                old: old.as_deref().map(path),
+                new: new.as_deref().map(path),
+            },
+            DiffContent::Empty => Self::Empty,
+        }
+    }
+}
+
+impl TryFrom<Content> for super::DiffContent {
+    type Error = error::Decode;
+
+    fn try_from(content: Content) -> Result<Self, Self::Error> {
+        use super::EofNewLine;
+
+        Ok(match content {
+            Content::Plain { hunks, eof } => Self::Plain {
+                hunks: Hunks(
+                    hunks
+                        .into_iter()
+                        .map(super::Hunk::try_from)
+                        .collect::<Result<_, _>>()?,
+                ),
+                eof: match eof {
+                    Eof::OldMissing => EofNewLine::OldMissing,
+                    Eof::NewMissing => EofNewLine::NewMissing,
+                    Eof::BothMissing => EofNewLine::BothMissing,
+                    Eof::NoneMissing => EofNewLine::NoneMissing,
+                },
+            },
+            Content::Binary { old_size, new_size } => Self::Binary { old_size, new_size },
+            Content::SymlinkTarget { old, new } => Self::SymlinkTarget {
+                old: old.map(Into::into),
+                new: new.map(Into::into),
+            },
+            Content::Empty => Self::Empty,
+        })
+    }
+}
+
+impl From<&super::Hunk<super::Modification>> for Hunk {
+    fn from(hunk: &super::Hunk<super::Modification>) -> Self {
+        use super::Modification;
+
+        Self {
+            header: Text::from(&hunk.header),
+            old: Range::from(&hunk.old),
+            new: Range::from(&hunk.new),
+            lines: hunk
+                .lines
+                .iter()
+                .map(|line| match line {
+                    Modification::Addition(addition) => Change::Addition {
+                        line: Text::from(&addition.line),
+                        line_no: addition.line_no,
+                    },
+                    Modification::Deletion(deletion) => Change::Deletion {
+                        line: Text::from(&deletion.line),
+                        line_no: deletion.line_no,
+                    },
+                    Modification::Context {
+                        line,
+                        line_no_old,
+                        line_no_new,
+                    } => Change::Context {
+                        line: Text::from(line),
+                        line_no_old: *line_no_old,
+                        line_no_new: *line_no_new,
+                    },
+                })
+                .collect(),
+        }
+    }
+}
+
+impl TryFrom<Hunk> for super::Hunk<super::Modification> {
+    type Error = error::Decode;
+
+    fn try_from(hunk: Hunk) -> Result<Self, Self::Error> {
+        use super::Modification;
+
+        Ok(Self {
+            header: hunk.header.try_into()?,
+            old: hunk.old.start..hunk.old.end,
+            new: hunk.new.start..hunk.new.end,
+            lines: hunk
+                .lines
+                .into_iter()
+                .map(|change| {
+                    Ok(match change {
+                        Change::Addition { line, line_no } => {
+                            Modification::addition(Line::try_from(line)?, line_no)
+                        },
+                        Change::Deletion { line, line_no } => {
+                            Modification::deletion(Line::try_from(line)?, line_no)
+                        },
+                        Change::Context {
+                            line,
+                            line_no_old,
+                            line_no_new,
+                        } => Modification::context(Line::try_from(line)?, line_no_old, line_no_new),
+                    })
+                })
+                .collect::<Result<_, error::Decode>>()?,
+        })
+    }
+}
+
+impl From<&ops::Range<u32>> for Range {
+    fn from(range: &ops::Range<u32>) -> Self {
+        Self {
+            start: range.start,
+            end: range.end,
+        }
+    }
+}
+
+impl From<&Line> for Text {
+    fn from(line: &Line) -> Self {
+        match std::str::from_utf8(line.as_bytes()) {
+            Ok(line) => Self::Utf8(line.to_owned()),
+            Err(_) => Self::Base64 {
+                base64: base64::encode(line.as_bytes()),
+            },
+        }
+    }
+}
+
+impl TryFrom<Text> for Line {
+    type Error = error::Decode;
+
+    fn try_from(text: Text) -> Result<Self, Self::Error> {
+        match text {
+            Text::Utf8(line) => Ok(Line::from(line)),
+            Text::Base64 { base64 } => Ok(Line::from(base64::decode(base64)?)),
+        }
+    }
+}
+
+fn path(path: &Path) -> String {
+    path.to_string_lossy().into_owned()
+}
@@ -19,12 +19,16 @@
 //! error types transparently.
 
 use crate::{commit, diff, fs, glob, namespace, refs, repo};
+use git_ext::Oid;
 use thiserror::Error;
 
 /// The crate level error type that wraps up module level error types.
 #[derive(Debug, Error)]
 #[non_exhaustive]
 pub enum Error {
+    #[cfg(feature = "archive")]
+    #[error(transparent)]
+    Archive(#[from] crate::archive::Error),
     #[error(transparent)]
     Branches(#[from] refs::error::Branch),
     #[error(transparent)]
@@ -46,6 +50,8 @@ pub enum Error {
     #[error(transparent)]
     RefFormat(#[from] git_ext::ref_format::Error),
     #[error(transparent)]
+    References(#[from] refs::error::Reference),
+    #[error(transparent)]
     Revision(Box<dyn std::error::Error + Send + Sync + 'static>),
     #[error(transparent)]
     ToCommit(Box<dyn std::error::Error + Send + Sync + 'static>),
@@ -54,3 +60,19 @@ pub enum Error {
     #[error(transparent)]
     Repo(#[from] repo::error::Repo),
 }
+
+impl Error {
+    /// Returns the id of the object that is missing from the object
+    /// database, if that is the cause of this error.
+    ///
+    /// This happens for partial clones, where some objects are only
+    /// fetched on demand, so callers may fetch the object and retry.
+    pub fn missing_object(&self) -> Option<Oid> {
+        match self {
+            Self::Directory(fs::error::Directory::ObjectMissing(oid))
+            | Self::Directory(fs::error::Directory::File(fs::error::File::ObjectMissing(oid)))
+            | Self::File(fs::error::File::ObjectMissing(oid)) => Some(*oid),
+            _ => None,
+        }
+    }
+}
@@ -24,18 +24,21 @@ use std::{
     cmp::Ordering,
     collections::BTreeMap,
     convert::{Infallible, Into as _},
-    path::{Path, PathBuf},
+    path::{Component, Path, PathBuf},
 };
 
 use git2::Blob;
 use radicle_git_ext::{is_not_found_err, Oid};
 use radicle_std_ext::result::ResultExt as _;
+#[cfg(feature = "serde")]
+use serde::Serialize;
 
 use crate::{Repository, Revision};
 
 pub mod error {
     use std::path::PathBuf;
 
+    use radicle_git_ext::Oid;
     use thiserror::Error;
 
     #[derive(Debug, Error, PartialEq)]
@@ -48,6 +51,10 @@ pub mod error {
         InvalidPath(PathBuf),
         #[error("the entry at '{0}' must be of type {1}")]
         InvalidType(PathBuf, &'static str),
+        /// The tree is missing from the object database, e.g. in a partial
+        /// clone, and may need to be fetched.
+        #[error("the tree {0} is missing from the object database")]
+        ObjectMissing(Oid),
         #[error("the entry name was not valid UTF-8")]
         Utf8Error,
         #[error("the path {0} not found")]
@@ -58,6 +65,10 @@ pub mod error {
     pub enum File {
         #[error(transparent)]
         Git(#[from] git2::Error),
+        /// The blob is missing from the object database, e.g. in a partial
+        /// clone, and may need to be fetched.
+        #[error("the blob {0} is missing from the object database")]
+        ObjectMissing(Oid),
     }
 }
 
@@ -127,7 +138,9 @@ impl File {
     /// This function will fail if it could not find the `git` blob
     /// for the `Oid` of this `File`.
     pub fn content<'a>(&self, repo: &'a Repository) -> Result<FileContent<'a>, error::File> {
-        let blob = repo.find_blob(self.id)?;
+        let blob = repo.find_blob(self.id).or_matches(is_not_found_err, || {
+            Err(error::File::ObjectMissing(self.id))
+        })?;
         Ok(FileContent { blob })
     }
 }
@@ -154,6 +167,70 @@ impl<'a> FileContent<'a> {
     pub(crate) fn new(blob: Blob<'a>) -> Self {
         Self { blob }
     }
+
+    /// Returns the [`LfsPointer`] of the file, if it is the pointer to an
+    /// object stored with [Git LFS][lfs], rather than the object itself.
+    ///
+    /// The object is not part of the repository, so the pointer should
+    /// be shown instead of the content, e.g. as "LFS object (4.2 MB)".
+    ///
+    /// [lfs]: https://git-lfs.com
+    pub fn lfs_pointer(&self) -> Option<LfsPointer> {
+        LfsPointer::parse(self.as_bytes())
+    }
+}
+
+/// The pointer to an object stored with Git LFS, see
+/// [`FileContent::lfs_pointer`].
+#[cfg_attr(feature = "serde", derive(Serialize), serde(rename_all = "camelCase"))]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LfsPointer {
+    /// The SHA-256 of the object, in hexadecimal.
+    pub oid: String,
+    /// The size of the object, in bytes.
+    pub size: u64,
+}
+
+impl LfsPointer {
+    /// Pointer files are never larger than this, as per the
+    /// [specification][spec].
+    ///
+    /// [spec]: https://github.com/git-lfs/git-lfs/blob/main/docs/spec.md
+    const MAX_SIZE: usize = 1024;
+
+    /// Parses a pointer file, which is made of `key value` lines, starting
+    /// with the `version` of the specification.
+    fn parse(content: &[u8]) -> Option<Self> {
+        if content.len() > Self::MAX_SIZE {
+            return None;
+        }
+        let content = std::str::from_utf8(content).ok()?;
+        let mut lines = content.strip_suffix('\n')?.split('\n');
+        match lines.next()?.strip_prefix("version ")? {
+            "https://git-lfs.github.com/spec/v1" | "https://hawser.github.com/spec/v1" => {},
+            _ => return None,
+        }
+
+        let (mut oid, mut size) = (None, None);
+        for line in lines {
+            match line.split_once(' ')? {
+                ("oid", value) => {
+                    let hash = value.strip_prefix("sha256:")?;
+                    if hash.len() != 64 || !hash.bytes().all(|b| b.is_ascii_hexdigit()) {
+                        return None;
+                    }
+                    oid = Some(hash.to_owned());
+                },
+                ("size", value) => size = Some(value.parse().ok()?),
+                // Other keys are extensions of the specification.
+                _ => {},
+            }
+        }
+        Some(Self {
+            oid: oid?,
+            size: size?,
+        })
+    }
 }
 
 /// A representations of a [`Directory`]'s entries.
@@ -162,6 +239,14 @@ pub struct Entries {
 }
 
 impl Entries {
+    /// Keep only the entries for which `f` returns `true`.
+    pub(crate) fn retain<F>(&mut self, mut f: F)
+    where
+        F: FnMut(&Entry) -> bool,
+    {
+        self.listing.retain(|_, entry| f(entry))
+    }
+
     /// Return the name of each [`Entry`].
     pub fn names(&self) -> impl Iterator<Item = &String> {
         self.listing.keys()
@@ -266,6 +351,19 @@ impl Entry {
     }
 }
 
+/// The paths of the files that differ between two [`Directory`]s, see
+/// [`Directory::path_diff`].
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct PathDiff {
+    /// The files that only exist in the new directory.
+    pub created: Vec<PathBuf>,
+    /// The files that only exist in the old directory.
+    pub deleted: Vec<PathBuf>,
+    /// The files that exist in both directories, but differ in content or
+    /// mode.
+    pub changed: Vec<PathBuf>,
+}
+
 /// A `Directory` is the representation of a file system directory, for a given
 /// [`git` tree][git-tree].
 ///
@@ -345,7 +443,7 @@ impl Directory {
     /// This function will fail if it could not find the `git` tree
     /// for the `Oid`.
     pub fn entries(&self, repo: &Repository) -> Result<Entries, error::Directory> {
-        let tree = repo.find_tree(self.id)?;
+        let tree = self.tree(repo)?;
 
         let mut entries = BTreeMap::new();
         let mut error = None;
@@ -379,14 +477,98 @@ impl Directory {
         }
     }
 
+    /// Returns a page of at most `limit` [`Entry`]s, skipping the first
+    /// `offset` entries, along with the total number of entries.
+    ///
+    /// The entries are ordered by name, as in [`Directory::entries`], but
+    /// only the entries of the requested page are constructed.
+    ///
+    /// # Errors
+    ///
+    /// This function will fail if it could not find the `git` tree
+    /// for the `Oid`.
+    pub fn entries_page(
+        &self,
+        repo: &Repository,
+        offset: usize,
+        limit: usize,
+    ) -> Result<(Vec<Entry>, usize), error::Directory> {
+        let tree = self.tree(repo)?;
+        let mut entries = tree.iter().collect::<Vec<_>>();
+        entries.sort_by(|x, y| x.name_bytes().cmp(y.name_bytes()));
+
+        let path = self.path();
+        let page = entries
+            .iter()
+            .skip(offset)
+            .take(limit)
+            .map(|entry| Entry::from_entry(entry, path.clone()))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok((page, entries.len()))
+    }
+
+    /// Compare the files of this `Directory` with those of `other`, without
+    /// computing a full [`crate::diff::Diff`].
+    ///
+    /// The paths of the returned [`PathDiff`] are relative to the
+    /// directories themselves.
+    pub fn path_diff(
+        &self,
+        other: &Directory,
+        repo: &Repository,
+    ) -> Result<PathDiff, error::Directory> {
+        if self.id == other.id {
+            return Ok(PathDiff::default());
+        }
+        let old = self.files(repo)?;
+        let mut new = other.files(repo)?;
+
+        let mut diff = PathDiff::default();
+        for (path, file) in old {
+            match new.remove(&path) {
+                Some(other) if other != file => diff.changed.push(path),
+                Some(_) => {},
+                None => diff.deleted.push(path),
+            }
+        }
+        diff.created = new.into_keys().collect();
+        Ok(diff)
+    }
+
+    /// Returns the blob id and mode of all the files reachable from this
+    /// `Directory`, by their path relative to it.
+    fn files(&self, repo: &Repository) -> Result<BTreeMap<PathBuf, (Oid, i32)>, error::Directory> {
+        let mut files = BTreeMap::new();
+        self.tree(repo)?
+            .walk(git2::TreeWalkMode::PreOrder, |root, entry| {
+                if entry.kind() != Some(git2::ObjectType::Tree) {
+                    let name = String::from_utf8_lossy(entry.name_bytes());
+                    files.insert(
+                        Path::new(root).join(name.as_ref()),
+                        (entry.id().into(), entry.filemode()),
+                    );
+                }
+                git2::TreeWalkResult::Ok
+            })?;
+        Ok(files)
+    }
+
     /// Find the [`Entry`] found at a non-empty `path`, if it exists.
+    ///
+    /// Any `.` and `..` components of `path` are collapsed before the
+    /// lookup, e.g. `src/../text/./garden.txt` finds `text/garden.txt`.
+    /// An absolute `path`, or one that escapes this `Directory`, is
+    /// invalid.
     pub fn find_entry<P>(&self, path: &P, repo: &Repository) -> Result<Entry, error::Directory>
     where
         P: AsRef<Path>,
     {
+        let path = normalize(path.as_ref())
+            .ok_or_else(|| error::Directory::InvalidPath(path.as_ref().to_path_buf()))?;
+        let path = path.as_path();
+
         // Search the path in git2 tree.
-        let path = path.as_ref();
-        let git2_tree = repo.find_tree(self.id)?;
+        let git2_tree = self.tree(repo)?;
         let entry = git2_tree
             .get_path(path)
             .or_matches::<error::Directory, _, _>(is_not_found_err, || {
@@ -416,12 +598,13 @@ impl Directory {
 
     /// Find the `Directory` found at `path`, if it exists.
     ///
-    /// If `path` is `ROOT_DIR` (i.e. an empty path), returns self.
+    /// If `path` is `ROOT_DIR` (i.e. an empty path), or normalizes to it,
+    /// returns self.
     pub fn find_directory<P>(&self, path: &P, repo: &Repository) -> Result<Self, error::Directory>
     where
         P: AsRef<Path>,
     {
-        if path.as_ref() == Path::new(ROOT_DIR) {
+        if normalize(path.as_ref()).as_deref() == Some(Path::new(ROOT_DIR)) {
             return Ok(self.clone());
         }
 
@@ -441,6 +624,13 @@ impl Directory {
         unimplemented!()
     }
 
+    /// Finds the `git` tree of this `Directory`.
+    fn tree<'a>(&self, repo: &'a Repository) -> Result<git2::Tree<'a>, error::Directory> {
+        repo.find_tree(self.id).or_matches(is_not_found_err, || {
+            Err(error::Directory::ObjectMissing(self.id))
+        })
+    }
+
     /// Get the total size, in bytes, of a `Directory`. The size is
     /// the sum of all files that can be reached from this `Directory`.
     pub fn size(&self, repo: &Repository) -> Result<usize, error::Directory> {
@@ -450,6 +640,58 @@ impl Directory {
         })
     }
 
+    /// Returns `true` if this `Directory`, and its sub-directories, only
+    /// contain placeholder files named after one of `keepfiles`, e.g.
+    /// `.gitkeep`.
+    ///
+    /// `git` does not track empty directories, so placeholder files are
+    /// committed to keep them. Such directories can then be shown as
+    /// empty.
+    pub fn is_effectively_empty(
+        &self,
+        repo: &Repository,
+        keepfiles: &[&str],
+    ) -> Result<bool, error::Directory> {
+        for entry in self.entries(repo)?.entries() {
+            let empty = match entry {
+                Entry::File(file) => keepfiles.contains(&file.name()),
+                Entry::Directory(directory) => directory.is_effectively_empty(repo, keepfiles)?,
+            };
+            if !empty {
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    }
+
+    /// Get the groups of paths, reachable from this `Directory`, that only
+    /// differ by case, e.g. `README.md` and `readme.md`.
+    ///
+    /// Such paths clash when checked out on a case-insensitive file
+    /// system, like the defaults on macOS and Windows.
+    pub fn case_conflicts(&self, repo: &Repository) -> Result<Vec<Vec<PathBuf>>, error::Directory> {
+        let folded = self.traverse::<error::Directory, _, _>(
+            repo,
+            BTreeMap::<String, Vec<PathBuf>>::new(),
+            &mut |mut folded, entry| {
+                let path = entry.path();
+                folded
+                    .entry(path.to_string_lossy().to_lowercase())
+                    .or_default()
+                    .push(path);
+                Ok(folded)
+            },
+        )?;
+        Ok(folded
+            .into_values()
+            .filter(|paths| paths.len() > 1)
+            .map(|mut paths| {
+                paths.sort();
+                paths
+            })
+            .collect())
+    }
+
     /// Traverse the entire `Directory` using the `initial`
     /// accumulator and the function `f`.
     ///
@@ -496,3 +738,24 @@ impl Revision for Directory {
 fn escaped_name(name: &str) -> String {
     name.replace('\\', r"\\")
 }
+
+/// Collapses the `.` and `..` components of a relative `path`.
+///
+/// Returns `None` if `path` is absolute, or if a `..` would go above the
+/// start of `path`.
+fn normalize(path: &Path) -> Option<PathBuf> {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::Normal(c) => normalized.push(c),
+            Component::CurDir => {},
+            Component::ParentDir => {
+                if !normalized.pop() {
+                    return None;
+                }
+            },
+            Component::RootDir | Component::Prefix(_) => return None,
+        }
+    }
+    Some(normalized)
+}
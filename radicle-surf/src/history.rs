@@ -20,7 +20,16 @@ use std::{
     path::{Path, PathBuf},
 };
 
-use crate::{Commit, Error, Repository, ToCommit};
+use crate::{
+    diff::{Diff, DiffContent},
+    Commit,
+    Error,
+    Repository,
+    ToCommit,
+};
+
+#[cfg(feature = "serde")]
+use serde::Serialize;
 
 /// An iterator that produces the history of commits for a given `head`.
 ///
@@ -32,9 +41,28 @@ pub struct History<'a> {
     filter_by: Option<FilterBy>,
 }
 
+/// A commit that changed a file, see [`Repository::file_evolution`].
+#[cfg_attr(feature = "serde", derive(Serialize), serde(rename_all = "camelCase"))]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FileRevision {
+    /// The commit that changed the file.
+    pub commit: Commit,
+    /// The path of the file in `commit`.
+    pub path: PathBuf,
+    /// The path the file had before `commit`, if `commit` renamed it.
+    pub old_path: Option<PathBuf>,
+    /// The changes `commit` made to the file, in respect to its first
+    /// parent.
+    pub diff: DiffContent,
+}
+
 /// Internal implementation, subject to refactoring.
 enum FilterBy {
-    File { path: PathBuf },
+    File {
+        path: PathBuf,
+        follow_renames: bool,
+        combined_merges: bool,
+    },
 }
 
 impl<'a> History<'a> {
@@ -69,9 +97,58 @@ impl<'a> History<'a> {
     {
         self.filter_by = Some(FilterBy::File {
             path: path.as_ref().to_path_buf(),
+            follow_renames: false,
+            combined_merges: false,
         });
         self
     }
+
+    /// Continues the history of a file under its old path when it was
+    /// renamed, similar to `git log --follow`.
+    ///
+    /// This only has an effect when the `History` is filtered by a path
+    /// (see [`History::by_path`]).
+    pub fn follow_renames(mut self) -> Self {
+        if let Some(FilterBy::File { follow_renames, .. }) = &mut self.filter_by {
+            *follow_renames = true;
+        }
+        self
+    }
+
+    /// Pairs each commit of the history with its [`Diff`], see
+    /// [`Repository::diff_commit`].
+    ///
+    /// The diffs are computed lazily, as the iterator is advanced, so
+    /// taking the first few commits only diffs those.
+    pub fn with_diffs(self) -> impl Iterator<Item = Result<(Commit, Diff), Error>> + 'a {
+        let repo = self.repo;
+        self.map(move |commit| {
+            let commit = commit?;
+            let diff = repo.diff_commit(commit.clone())?;
+            Ok((commit, diff))
+        })
+    }
+
+    /// Only keeps the merge commits that changed the file differently from
+    /// all of their parents, i.e. whose combined diff has the file, similar
+    /// to `git log --cc`.
+    ///
+    /// By default, merge commits are compared to their first parent only,
+    /// so a merge that merely brings in changes made to the file on
+    /// another branch is kept too. With this, only the merges that, e.g.
+    /// resolved a conflict in the file, are kept.
+    ///
+    /// This only has an effect when the `History` is filtered by a path
+    /// (see [`History::by_path`]).
+    pub fn combined_merges(mut self) -> Self {
+        if let Some(FilterBy::File {
+            combined_merges, ..
+        }) = &mut self.filter_by
+        {
+            *combined_merges = true;
+        }
+        self
+    }
 }
 
 impl<'a> Iterator for History<'a> {
@@ -86,13 +163,28 @@ impl<'a> Iterator for History<'a> {
                     let commit = self.repo.find_commit(oid.into())?;
 
                     // Handles the optional filter_by.
-                    if let Some(FilterBy::File { path }) = &self.filter_by {
+                    if let Some(FilterBy::File {
+                        path,
+                        follow_renames,
+                        combined_merges,
+                    }) = &mut self.filter_by
+                    {
                         // Only check the commit diff if the path is not empty.
                         if !path.as_os_str().is_empty() {
-                            let path_opt = self.repo.diff_commit_and_parents(path, &commit)?;
-                            if path_opt.is_none() {
+                            let changed = if *combined_merges && commit.parent_count() > 1 {
+                                self.repo.changed_from_all_parents(path, &commit)?
+                            } else {
+                                self.repo.diff_commit_and_parents(path, &commit)?.is_some()
+                            };
+                            if !changed {
                                 return Ok(None); // Filter out this commit.
                             }
+                            // Track the old path for the rest of the history.
+                            if *follow_renames {
+                                if let Some(old_path) = self.repo.renamed_from(path, &commit)? {
+                                    *path = old_path;
+                                }
+                            }
                         }
                     }
 
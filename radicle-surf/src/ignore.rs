@@ -0,0 +1,223 @@
+// Copyright © 2022 The Radicle Git Contributors
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Matching of paths against the rules of committed `.gitignore` files.
+//!
+//! `libgit2` only consults the `.gitignore` files of a working directory,
+//! so this implements the subset of the [gitignore] format needed for
+//! applying the rules found in a commit's tree.
+//!
+//! [gitignore]: https://git-scm.com/docs/gitignore
+
+use std::path::{Path, PathBuf};
+
+/// The name of the files containing ignore rules.
+pub(crate) const GITIGNORE: &str = ".gitignore";
+
+/// A set of ignore rules, collected from `.gitignore` files.
+///
+/// Rules are added from the top-most `.gitignore` file downwards, and the
+/// last rule matching a path decides whether it is ignored. This gives
+/// rules of nested `.gitignore` files precedence over their parents'.
+#[derive(Debug, Default)]
+pub(crate) struct Ignore {
+    rules: Vec<Rule>,
+}
+
+#[derive(Debug)]
+struct Rule {
+    /// The directory of the `.gitignore` file this rule came from.
+    base: PathBuf,
+    pattern: Vec<u8>,
+    /// The rule starts with `!` and re-includes matching paths.
+    negated: bool,
+    /// The rule ends with `/` and only matches directories.
+    dir_only: bool,
+    /// The rule contains a `/` and is matched against the path relative
+    /// to `base`, rather than the file name only.
+    anchored: bool,
+}
+
+impl Ignore {
+    /// Add the rules of the `.gitignore` file, with the given `content`,
+    /// found in the directory `base`.
+    pub(crate) fn add(&mut self, base: &Path, content: &[u8]) {
+        for line in content.split(|b| *b == b'\n') {
+            if let Some(rule) = Rule::parse(base, line) {
+                self.rules.push(rule);
+            }
+        }
+    }
+
+    /// Whether `path`, relative to the repository root, is ignored.
+    pub(crate) fn is_ignored(&self, path: &Path, is_dir: bool) -> bool {
+        self.rules
+            .iter()
+            .rev()
+            .find(|rule| rule.matches(path, is_dir))
+            .is_some_and(|rule| !rule.negated)
+    }
+}
+
+impl Rule {
+    fn parse(base: &Path, line: &[u8]) -> Option<Self> {
+        let mut line = line.strip_suffix(b"\r").unwrap_or(line);
+        // Trailing spaces are ignored, unless escaped.
+        while line.ends_with(b" ") && !line.ends_with(b"\\ ") {
+            line = &line[..line.len() - 1];
+        }
+        if line.is_empty() || line.starts_with(b"#") {
+            return None;
+        }
+
+        let (negated, line) = match line.strip_prefix(b"!") {
+            Some(line) => (true, line),
+            None => (false, line),
+        };
+        let (dir_only, line) = match line.strip_suffix(b"/") {
+            Some(line) => (true, line),
+            None => (false, line),
+        };
+        let anchored = line.contains(&b'/');
+        let line = line.strip_prefix(b"/").unwrap_or(line);
+        if line.is_empty() {
+            return None;
+        }
+
+        Some(Self {
+            base: base.to_path_buf(),
+            pattern: line.to_vec(),
+            negated,
+            dir_only,
+            anchored,
+        })
+    }
+
+    fn matches(&self, path: &Path, is_dir: bool) -> bool {
+        if self.dir_only && !is_dir {
+            return false;
+        }
+        let path = match path.strip_prefix(&self.base) {
+            Ok(path) => path.to_string_lossy(),
+            Err(_) => return false,
+        };
+        let path = path.as_bytes();
+        if self.anchored {
+            wildmatch(&self.pattern, path)
+        } else {
+            let name = path.rsplit(|b| *b == b'/').next().unwrap_or(path);
+            wildmatch(&self.pattern, name)
+        }
+    }
+}
+
+/// Match `text` against the glob `pattern`, where `*` and `?` do not match
+/// a `/`, and `**` matches across directories when it is a whole path
+/// segment. Otherwise, `**` is the same as `*`.
+///
+/// The match is iterative and only backtracks to the last `*` and the last
+/// `**/`, so it takes polynomial time for any `pattern`.
+pub(crate) fn wildmatch(pattern: &[u8], text: &[u8]) -> bool {
+    let (mut p, mut t) = (0, 0);
+    // Where to resume after the last `*`, and after the last `**/`.
+    let mut star: Option<(usize, usize)> = None;
+    let mut globstar: Option<(usize, usize)> = None;
+
+    loop {
+        if p == pattern.len() && t == text.len() {
+            return true;
+        }
+        if pattern[p..].starts_with(b"**") && (p == 0 || pattern[p - 1] == b'/') {
+            match pattern.get(p + 2) {
+                None => return true,
+                Some(b'/') => {
+                    p += 3;
+                    globstar = Some((p, t));
+                    star = None;
+                    continue;
+                },
+                Some(_) => {},
+            }
+        }
+        if pattern.get(p) == Some(&b'*') {
+            while pattern.get(p) == Some(&b'*') {
+                p += 1;
+            }
+            star = Some((p, t));
+            continue;
+        }
+
+        let next = match &pattern[p..] {
+            [] => None,
+            [b'?', ..] => text.get(t).filter(|c| **c != b'/').map(|_| p + 1),
+            [b'[', class @ ..] => match class_match(class, text.get(t)) {
+                Some((true, rest)) => Some(pattern.len() - rest.len()),
+                Some((false, _)) => None,
+                // An unterminated class is matched literally.
+                None => (text.get(t) == Some(&b'[')).then_some(p + 1),
+            },
+            [b'\\', c, ..] => (text.get(t) == Some(c)).then_some(p + 2),
+            [c, ..] => (text.get(t) == Some(c)).then_some(p + 1),
+        };
+        if let Some(next) = next {
+            p = next;
+            t += 1;
+            continue;
+        }
+
+        // Let the last `*` match one more character, up to the end of the
+        // path segment, or else let the last `**/` match one more segment.
+        match star {
+            Some((sp, st)) if text.get(st).is_some_and(|c| *c != b'/') => {
+                star = Some((sp, st + 1));
+                (p, t) = (sp, st + 1);
+            },
+            _ => {
+                star = None;
+                let (gp, gt) = match globstar {
+                    Some(globstar) => globstar,
+                    None => return false,
+                };
+                match text[gt..].iter().position(|c| *c == b'/') {
+                    Some(i) => {
+                        globstar = Some((gp, gt + i + 1));
+                        (p, t) = (gp, gt + i + 1);
+                    },
+                    None => return false,
+                }
+            },
+        }
+    }
+}
+
+/// Match `c` against the character class at the start of `class`, i.e.
+/// after the opening `[`.
+///
+/// Returns whether it matched and the remaining pattern after the closing
+/// `]`, or `None` if the class is not terminated.
+fn class_match<'a>(class: &'a [u8], c: Option<&u8>) -> Option<(bool, &'a [u8])> {
+    let (negated, mut class) = match class {
+        [b'!' | b'^', class @ ..] => (true, class),
+        _ => (false, class),
+    };
+    let mut matched = false;
+    let mut first = true;
+    loop {
+        match class {
+            [] => return None,
+            [b']', rest @ ..] if !first => {
+                let matched = c.is_some_and(|c| *c != b'/' && matched != negated);
+                return Some((matched, rest));
+            },
+            [lo, b'-', hi, rest @ ..] if *hi != b']' => {
+                matched |= c.is_some_and(|c| (lo..=hi).contains(&c));
+                class = rest;
+            },
+            [b'\\', x, rest @ ..] | [x, rest @ ..] => {
+                matched |= c == Some(x);
+                class = rest;
+            },
+        }
+        first = false;
+    }
+}
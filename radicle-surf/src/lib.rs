@@ -30,6 +30,9 @@
 //! Many types in this crate support serialization using [`Serde`][serde]
 //! through the `serde` feature flag for this crate.
 //!
+//! The `json` feature flag additionally enables reading and writing diffs
+//! in a stable JSON format, see `diff::Diff::to_json_v1`.
+//!
 //! [serde]: https://crates.io/crates/serde
 
 extern crate radicle_git_ext as git_ext;
@@ -40,40 +43,65 @@ pub use radicle_git_ext::ref_format;
 /// Represents an object id in Git. Re-exported from `radicle-git-ext`.
 pub type Oid = radicle_git_ext::Oid;
 
+#[cfg(feature = "archive")]
+pub mod archive;
 pub mod blob;
 pub mod diff;
 pub mod fs;
+pub mod repo;
 pub mod tree;
 
-/// Private modules with their public types.
-mod repo;
 pub use repo::Repository;
 
+/// Private modules with their public types.
+mod cache;
+pub use cache::CachedRepository;
+
 mod glob;
 pub use glob::Glob;
 
 mod history;
-pub use history::History;
+pub use history::{FileRevision, History};
 
 mod branch;
-pub use branch::{Branch, Local, Remote};
+pub use branch::{Branch, HeadRef, Local, Remote, RemoteConfig};
 
 mod tag;
 pub use tag::Tag;
 
 mod commit;
-pub use commit::{Author, Commit, Time};
+pub use commit::{
+    Author,
+    AuthorRole,
+    CherryStatus,
+    Commit,
+    MessageReference,
+    PatternError,
+    ReferencePattern,
+    Time,
+};
 
 mod namespace;
 pub use namespace::Namespace;
 
 mod stats;
-pub use stats::Stats;
+pub use stats::{Churn, FileChurn, OdbStats, Stats};
 
 mod revision;
-pub use revision::{Revision, Signature, ToCommit};
+pub use revision::{ResolvedRev, Revision, Signature, SignatureFormat, ToCommit};
 
 mod refs;
+pub use refs::{RefUpdate, RefsDiff};
+
+mod ignore;
+
+mod attributes;
+
+mod stash;
+pub use stash::StashEntry;
+
+mod submodule;
+pub use submodule::Submodule;
 
 mod error;
 pub use error::Error;
@@ -23,6 +23,7 @@ use std::{
 
 use git_ext::ref_format::{
     self,
+    refname,
     refspec::{NamespacedPattern, PatternString, QualifiedPattern},
     Component,
     Namespaced,
@@ -125,6 +126,25 @@ impl Namespace {
     }
 }
 
+impl Namespace {
+    /// Returns the reference name of the `HEAD` of this `Namespace`, e.g.
+    /// `refs/namespaces/surf/refs/namespaces/git/HEAD`.
+    pub(crate) fn head(&self) -> RefString {
+        self.to_namespaced_name(&refname!("HEAD"))
+    }
+
+    /// Returns the reference name of `name` in this `Namespace`, which,
+    /// unlike [`Namespace::to_namespaced`], can be `HEAD`, e.g.
+    /// `refs/namespaces/surf/refs/namespaces/git/HEAD`.
+    pub(crate) fn to_namespaced_name(&self, name: &RefStr) -> RefString {
+        let mut name = name.to_ref_string();
+        for ns in self.namespaces.components().rev() {
+            name = refname!("refs/namespaces").join(ns).join(name);
+        }
+        name
+    }
+}
+
 impl fmt::Display for Namespace {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(f, "{}", self.namespaces)
@@ -2,11 +2,14 @@
 // using Generic associated types supported in Rust 1.65.0.
 
 use std::{
-    collections::{btree_set, BTreeSet},
+    collections::{btree_set, BTreeMap, BTreeSet, HashMap},
     convert::TryFrom as _,
 };
 
-use git_ext::ref_format::{self, lit, name::Components, Component, Qualified, RefString};
+use git_ext::{
+    ref_format::{self, lit, name::Components, Component, Qualified, RefString},
+    Oid,
+};
 
 use crate::{tag, Branch, Namespace, Tag};
 
@@ -89,6 +92,11 @@ pub struct BranchNames<'a> {
     inner: Branches<'a>,
 }
 
+/// Iterator over [`Branch`]es paired with the [`Oid`] of their tip commit.
+pub struct BranchTips<'a> {
+    inner: Branches<'a>,
+}
+
 impl<'a> Branches<'a> {
     pub(super) fn push(&mut self, references: git2::References<'a>) {
         self.references.push(references)
@@ -97,6 +105,10 @@ impl<'a> Branches<'a> {
     pub fn names(self) -> BranchNames<'a> {
         BranchNames { inner: self }
     }
+
+    pub fn tips(self) -> BranchTips<'a> {
+        BranchTips { inner: self }
+    }
 }
 
 impl<'a> Iterator for Branches<'a> {
@@ -144,6 +156,30 @@ impl<'a> Iterator for BranchNames<'a> {
     }
 }
 
+impl<'a> Iterator for BranchTips<'a> {
+    type Item = Result<(Branch, Oid), error::Branch>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.inner.current < self.inner.references.len() {
+            match self.inner.references.get_mut(self.inner.current) {
+                Some(refs) => match refs.next() {
+                    Some(res) => {
+                        return Some(res.map_err(error::Branch::from).and_then(|r| {
+                            let tip = r.peel_to_commit()?.id().into();
+                            Branch::try_from(&r)
+                                .map(|branch| (branch, tip))
+                                .map_err(error::Branch::from)
+                        }))
+                    },
+                    None => self.inner.current += 1,
+                },
+                None => break,
+            }
+        }
+        None
+    }
+}
+
 // TODO: not sure this buys us much
 /// An iterator for namespaces.
 pub struct Namespaces {
@@ -204,6 +240,105 @@ impl<'a> Iterator for Categories<'a> {
     }
 }
 
+/// Iterator over the [`Qualified`] names of references, paired with the
+/// [`Oid`] they point to.
+#[derive(Default)]
+pub struct References<'a> {
+    references: Vec<git2::References<'a>>,
+    current: usize,
+}
+
+impl<'a> References<'a> {
+    pub(super) fn push(&mut self, references: git2::References<'a>) {
+        self.references.push(references)
+    }
+}
+
+impl<'a> Iterator for References<'a> {
+    type Item = Result<(Qualified<'static>, Oid), error::Reference>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.current < self.references.len() {
+            match self.references.get_mut(self.current) {
+                Some(refs) => match refs.next() {
+                    Some(res) => {
+                        return Some(res.map_err(error::Reference::from).and_then(|r| {
+                            let name = std::str::from_utf8(r.name_bytes())?;
+                            let name = ref_format::RefStr::try_from_str(name)?;
+                            let name = match name.to_namespaced() {
+                                None => name.qualified().ok_or_else(|| {
+                                    error::Reference::NotQualified(name.to_ref_string())
+                                })?,
+                                Some(name) => name.strip_namespace_recursive(),
+                            };
+                            let oid = r.resolve()?.target().ok_or_else(|| {
+                                error::Reference::MissingTarget(name.to_ref_string())
+                            })?;
+                            Ok((name.into_owned(), oid.into()))
+                        }));
+                    },
+                    None => self.current += 1,
+                },
+                None => break,
+            }
+        }
+        None
+    }
+}
+
+/// The references created, deleted and updated between two snapshots of
+/// a repository's references, see [`crate::Repository::diff_refs`].
+///
+/// Each list is sorted by reference name.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct RefsDiff {
+    /// The references that did not exist in the old snapshot, and the
+    /// [`Oid`] they point to.
+    pub created: Vec<(String, Oid)>,
+    /// The references that no longer exist, and the [`Oid`] they pointed
+    /// to.
+    pub deleted: Vec<(String, Oid)>,
+    /// The references that point to a different [`Oid`].
+    pub updated: Vec<RefUpdate>,
+}
+
+/// A reference that points to a different [`Oid`] than it used to.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RefUpdate {
+    pub name: String,
+    pub old: Oid,
+    pub new: Oid,
+}
+
+impl RefsDiff {
+    pub(super) fn new(old: &HashMap<String, Oid>, new: BTreeMap<String, Oid>) -> Self {
+        let mut diff = Self::default();
+        for (name, new) in &new {
+            match old.get(name) {
+                None => diff.created.push((name.clone(), *new)),
+                Some(old) if old != new => diff.updated.push(RefUpdate {
+                    name: name.clone(),
+                    old: *old,
+                    new: *new,
+                }),
+                Some(_) => {},
+            }
+        }
+        diff.deleted = old
+            .iter()
+            .filter(|(name, _)| !new.contains_key(*name))
+            .map(|(name, oid)| (name.clone(), *oid))
+            .collect();
+        diff.deleted.sort();
+        diff
+    }
+
+    /// Returns `true` if no reference changed.
+    pub fn is_empty(&self) -> bool {
+        self.created.is_empty() && self.deleted.is_empty() && self.updated.is_empty()
+    }
+}
+
 pub mod error {
     use std::str;
 
@@ -232,6 +367,20 @@ pub mod error {
         Utf8(#[from] str::Utf8Error),
     }
 
+    #[derive(Debug, Error)]
+    pub enum Reference {
+        #[error(transparent)]
+        Git(#[from] git2::Error),
+        #[error("the reference '{0}' does not point to an object")]
+        MissingTarget(RefString),
+        #[error("the reference '{0}' was expected to be qualified, i.e. 'refs/<category>/<path>'")]
+        NotQualified(RefString),
+        #[error(transparent)]
+        RefFormat(#[from] ref_format::Error),
+        #[error(transparent)]
+        Utf8(#[from] str::Utf8Error),
+    }
+
     #[derive(Debug, Error)]
     pub enum Tag {
         #[error(transparent)]
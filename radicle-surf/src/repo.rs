@@ -15,53 +15,126 @@
 // You should have received a copy of the GNU General Public License
 // along with this program. If not, see <https://www.gnu.org/licenses/>.
 
+//! The [`Repository`] to browse, and its [`error::Repo`] errors.
+
 use std::{
-    collections::BTreeSet,
+    collections::{BTreeMap, BTreeSet, HashMap},
     convert::TryFrom,
+    io::Read as _,
+    ops::RangeInclusive,
     path::{Path, PathBuf},
-    str,
+    str::{self, FromStr},
 };
 
 use git_ext::{
-    ref_format::{refspec::QualifiedPattern, Qualified, RefStr, RefString},
+    ref_format::{refname, refspec::QualifiedPattern, Qualified, RefStr, RefString},
     Oid,
 };
 
+#[cfg(feature = "archive")]
+use crate::archive::{self, ArchiveFormat};
 use crate::{
+    attributes::{self, Attributes, EolConfig},
     blob::{Blob, BlobRef},
-    diff::{Diff, FileDiff},
-    fs::{Directory, File, FileContent},
-    refs::{BranchNames, Branches, Categories, Namespaces, TagNames, Tags},
+    branch,
+    diff::{self, Diff, DiffContent, FileDiff, Hunk, Modification, NameStatus},
+    fs::{self, Directory, File, FileContent},
+    ignore::{self, Ignore},
+    refs::{
+        self,
+        BranchNames,
+        BranchTips,
+        Branches,
+        Categories,
+        Namespaces,
+        References,
+        RefsDiff,
+        TagNames,
+        Tags,
+    },
+    stash::StashEntry,
+    submodule::{self, Submodule},
     tree::{Entry, Tree},
+    AuthorRole,
     Branch,
+    CherryStatus,
+    Churn,
     Commit,
     Error,
+    FileChurn,
+    FileRevision,
     Glob,
+    HeadRef,
     History,
     Namespace,
+    OdbStats,
+    RemoteConfig,
+    ResolvedRev,
     Revision,
     Signature,
     Stats,
     Tag,
+    Time,
     ToCommit,
 };
 
 /// Enumeration of errors that can occur in repo operations.
 pub mod error {
     use std::path::PathBuf;
+
+    use git_ext::{ref_format::RefString, Oid};
     use thiserror::Error;
 
     #[derive(Debug, Error)]
     #[non_exhaustive]
     pub enum Repo {
+        #[error("ambiguous abbreviated object id: {0}")]
+        AmbiguousOid(String),
+        #[error("the repository is empty, i.e. it has no commits")]
+        EmptyRepository,
+        #[error(transparent)]
+        Io(#[from] std::io::Error),
+        #[error("HEAD is neither a symbolic nor a direct reference")]
+        InvalidHead,
+        #[error("invalid line range: {0},{1}")]
+        InvalidLineRange(u32, u32),
+        #[error("invalid symbolic reference target: {0}")]
+        InvalidSymrefTarget(String),
+        #[error("invalid revision syntax: {0}")]
+        InvalidRevision(String),
+        #[error("no merge base found between {0} and {1}")]
+        NoMergeBase(Oid, Oid),
+        #[error("reference has no target: {0}")]
+        NoTarget(String),
+        #[error("no reflog entry for: {0}")]
+        NoReflogEntry(String),
+        #[error("no upstream configured for: {0}")]
+        NoUpstream(String),
+        #[error("object {0} is not a blob")]
+        NotBlob(Oid),
         #[error("path not found for: {0}")]
         PathNotFound(PathBuf),
+        #[error("revision not found: {0}")]
+        RevisionNotFound(String),
+        #[error("symbolic reference cycle: {}", .0.iter().map(|r| r.as_str()).collect::<Vec<_>>().join(" -> "))]
+        SymrefCycle(Vec<RefString>),
     }
 }
 
 /// Represents the state associated with a Git repository.
 ///
 /// Many other types in this crate are derived from methods in this struct.
+///
+/// A `Repository` is read-only: none of its methods write to the
+/// repository on disk. The methods that change how it is browsed, e.g.
+/// [`Repository::switch_namespace`] and [`Repository::add_alternate`],
+/// only affect this value, which is why the underlying `git2::Repository`
+/// is not exposed.
+///
+/// Being read-only does not confine a `Repository` to its own directory,
+/// though: [`Repository::config_get`] also reads the global and system
+/// configuration, and [`Repository::add_alternate`] reads objects from
+/// any path it is given.
 pub struct Repository {
     /// Wrapper around the `git2`'s `git2::Repository` type.
     /// This is to to limit the functionality that we can do
@@ -90,6 +163,50 @@ impl Repository {
         Ok(Self { inner: repo })
     }
 
+    /// Returns the path to the `.git` directory of this repository, or the
+    /// repository itself if it is bare.
+    pub fn path(&self) -> &Path {
+        self.inner.path()
+    }
+
+    /// Returns the path to the working directory of this repository, or
+    /// `None` if it is bare.
+    pub fn workdir(&self) -> Option<&Path> {
+        self.inner.workdir()
+    }
+
+    /// Returns `true` if this repository is bare, i.e. it has no working
+    /// directory.
+    pub fn is_bare(&self) -> bool {
+        self.inner.is_bare()
+    }
+
+    /// Register the object directory at `path` as an alternate, i.e.
+    /// objects that are not found in this repository are also looked up
+    /// in `path`.
+    ///
+    /// The alternate is only registered for this `Repository` value, and is
+    /// not persisted to the repository's `objects/info/alternates`.
+    pub fn add_alternate(&self, path: impl AsRef<Path>) -> Result<(), Error> {
+        let path = path.as_ref().to_string_lossy();
+        Ok(self.inner.odb()?.add_disk_alternate(&path)?)
+    }
+
+    /// Get the value of the configuration `key`, e.g. `user.name`, or
+    /// `None` if it is not set.
+    ///
+    /// The value is read from a snapshot of the configuration as seen by
+    /// this repository, i.e. its own configuration along with the global
+    /// and system ones.
+    pub fn config_get(&self, key: &str) -> Result<Option<String>, Error> {
+        let config = self.inner.config()?.snapshot()?;
+        match config.get_string(key) {
+            Ok(value) => Ok(Some(value)),
+            Err(err) if err.code() == git2::ErrorCode::NotFound => Ok(None),
+            Err(err) => Err(err.into()),
+        }
+    }
+
     /// What is the current namespace we're browsing in.
     pub fn which_namespace(&self) -> Result<Option<Namespace>, Error> {
         self.inner
@@ -113,6 +230,17 @@ impl Repository {
         res
     }
 
+    /// Returns the [`Oid`] of the commit that the `HEAD` of `namespace`
+    /// points to.
+    ///
+    /// This is useful for browsing a `namespace` without knowing the name
+    /// of its default branch in advance.
+    pub fn namespace_head(&self, namespace: &Namespace) -> Result<Oid, Error> {
+        let head = self.inner.find_reference(namespace.head().as_str())?;
+        let commit = head.resolve()?.peel_to_commit()?;
+        Ok(commit.id().into())
+    }
+
     /// Returns an iterator of branches that match `pattern`.
     pub fn branches<G>(&self, pattern: G) -> Result<Branches, Error>
     where
@@ -136,6 +264,66 @@ impl Repository {
         Ok(self.branches(filter)?.names())
     }
 
+    /// Lists branches with `filter`, paired with the [`Oid`] of their tip
+    /// commit.
+    pub fn branch_tips<G>(&self, filter: G) -> Result<BranchTips<'_>, Error>
+    where
+        G: Into<Glob<Branch>>,
+    {
+        Ok(self.branches(filter)?.tips())
+    }
+
+    /// Returns the configured remote that `branch` comes from, e.g. to
+    /// label it with the URL it was fetched from.
+    ///
+    /// For a remote branch, this is its remote. For a local branch, this
+    /// is the remote of its upstream, i.e. `branch.<name>.remote`.
+    /// Returns `None` if there is no such remote configured.
+    pub fn branch_remote(&self, branch: &Branch) -> Result<Option<RemoteConfig>, Error> {
+        let name = match branch {
+            Branch::Remote(remote) => remote.remote().to_string(),
+            Branch::Local(local) => {
+                match self.inner.branch_upstream_remote(local.refname().as_str()) {
+                    Ok(name) => String::from_utf8_lossy(&name).into_owned(),
+                    Err(err) if err.code() == git2::ErrorCode::NotFound => return Ok(None),
+                    Err(err) => return Err(err.into()),
+                }
+            },
+        };
+        match self.inner.find_remote(&name) {
+            Ok(remote) => Ok(Some(RemoteConfig::from(&remote))),
+            Err(err) if err.code() == git2::ErrorCode::NotFound => Ok(None),
+            // The name is not a valid remote name, e.g. `.` for the
+            // repository itself.
+            Err(err) if err.code() == git2::ErrorCode::InvalidSpec => Ok(None),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    /// Lists the branches with `filter` that are merged into `target`, i.e.
+    /// whose tip is reachable from `target`, like `git branch --merged`.
+    ///
+    /// These branches can be deleted without losing any commits that are
+    /// not in `target`.
+    pub fn branches_merged_into<R, G>(&self, target: R, filter: G) -> Result<Vec<Branch>, Error>
+    where
+        R: Revision,
+        G: Into<Glob<Branch>>,
+    {
+        self.branches_by_merged(&target, filter, true)
+    }
+
+    /// Lists the branches with `filter` that are not merged into `target`,
+    /// i.e. whose tip is not reachable from `target`, like
+    /// `git branch --no-merged`.
+    pub fn branches_not_merged_into<R, G>(&self, target: R, filter: G) -> Result<Vec<Branch>, Error>
+    where
+        R: Revision,
+        G: Into<Glob<Branch>>,
+    {
+        self.branches_by_merged(&target, filter, false)
+    }
+
     /// Returns an iterator of tags that match `pattern`.
     pub fn tags(&self, pattern: &Glob<Tag>) -> Result<Tags, Error> {
         let mut tags = Tags::default();
@@ -147,6 +335,30 @@ impl Repository {
         Ok(tags)
     }
 
+    /// Returns the tags that match `pattern`, along with the commit each
+    /// of them points to, e.g. to list releases.
+    ///
+    /// Annotated tags are peeled to the commit they point to, and tags
+    /// that do not point to a commit, e.g. that tag a tree, are skipped.
+    pub fn tags_with_commits(&self, pattern: &Glob<Tag>) -> Result<Vec<(Tag, Commit)>, Error> {
+        let mut tags = Vec::new();
+        for tag in self.tags(pattern)? {
+            let tag = tag?;
+            let object = self.inner.find_object(tag.id().into(), None)?;
+            // Peeling a tag to `Any` peels it to the first object that is
+            // not a tag, whereas other objects would be peeled further.
+            let target = match object.kind() {
+                Some(git2::ObjectType::Tag) => object.peel(git2::ObjectType::Any)?,
+                _ => object,
+            };
+            if let Some(commit) = target.as_commit() {
+                let commit = Commit::try_from(commit.clone())?;
+                tags.push((tag, commit));
+            }
+        }
+        Ok(tags)
+    }
+
     /// Lists tag names in the local RefScope.
     pub fn tag_names(&self, filter: &Glob<Tag>) -> Result<TagNames, Error> {
         Ok(self.tags(filter)?.names())
@@ -162,6 +374,42 @@ impl Repository {
         Ok(cats)
     }
 
+    /// Returns an iterator of references that match `pattern`, paired with
+    /// the [`Oid`] they point to.
+    ///
+    /// Unlike [`Repository::branches`] and [`Repository::tags`], this can
+    /// list references of any category, e.g. `refs/rad/*`. Symbolic
+    /// references are resolved to the [`Oid`] of their target, and
+    /// annotated tags are not peeled.
+    pub fn references(&self, pattern: &Glob<Qualified<'_>>) -> Result<References<'_>, Error> {
+        let mut refs = References::default();
+        for glob in pattern.globs() {
+            let namespaced = self.namespaced_pattern(glob)?;
+            let references = self.inner.references_glob(&namespaced)?;
+            refs.push(references);
+        }
+        Ok(refs)
+    }
+
+    /// Compares a previously captured snapshot of references, `old`,
+    /// against the current references that match `scope`.
+    ///
+    /// `old` maps qualified reference names, e.g. `refs/heads/main`, to
+    /// the [`Oid`] they pointed to, as listed by [`Repository::references`].
+    /// It is expected to be captured with the same `scope`: any reference
+    /// of `old` that is not currently listed is reported as deleted.
+    pub fn diff_refs(
+        &self,
+        old: &HashMap<String, Oid>,
+        scope: &Glob<Qualified<'_>>,
+    ) -> Result<RefsDiff, Error> {
+        let new = self
+            .references(scope)?
+            .map(|r| r.map(|(name, oid)| (name.to_string(), oid)))
+            .collect::<Result<BTreeMap<_, _>, _>>()?;
+        Ok(RefsDiff::new(old, new))
+    }
+
     /// Returns an iterator of namespaces that match `pattern`.
     pub fn namespaces(&self, pattern: &Glob<Namespace>) -> Result<Namespaces, Error> {
         let mut set = BTreeSet::new();
@@ -181,11 +429,159 @@ impl Repository {
     }
 
     /// Get the [`Diff`] between two commits.
+    ///
+    /// This is the `from..to` diff, i.e. the diff between the trees of
+    /// `from` and `to`. When `from` is an ancestor of `to`, this is the
+    /// combined, or squashed, diff of all the commits in `from..to`.
+    /// Otherwise, it also reverts the changes made on `from` since the
+    /// two diverged, see [`Repository::triple_dot_diff`] to leave those
+    /// out.
     pub fn diff(&self, from: impl Revision, to: impl Revision) -> Result<Diff, Error> {
         let from_commit = self.find_commit(self.object_id(&from)?)?;
         let to_commit = self.find_commit(self.object_id(&to)?)?;
         self.diff_commits(None, Some(&from_commit), &to_commit)
-            .and_then(|diff| Diff::try_from(diff).map_err(Error::from))
+            .and_then(|diff| self.to_diff(diff, &diff::Options::default()))
+    }
+
+    /// Get the [`Diff`] between two commits, computed with the given
+    /// [`diff::Options`].
+    pub fn diff_with_options(
+        &self,
+        from: impl Revision,
+        to: impl Revision,
+        opts: &diff::Options,
+    ) -> Result<Diff, Error> {
+        let from_commit = self.find_commit(self.object_id(&from)?)?;
+        let to_commit = self.find_commit(self.object_id(&to)?)?;
+        self.diff_commits(None, Some(&from_commit), &to_commit)
+            .and_then(|diff| self.to_diff(diff, opts))
+    }
+
+    /// Get the [`Diff`] between the tips of `branch` in the namespaces
+    /// `from` and `to`, e.g. to compare how two peers' branches diverged.
+    ///
+    /// The namespaces are absolute, i.e. the current namespace, see
+    /// [`Repository::switch_namespace`], is not taken into account.
+    pub fn diff_across_namespaces(
+        &self,
+        from: &Namespace,
+        to: &Namespace,
+        branch: &Branch,
+    ) -> Result<Diff, Error> {
+        let from = self.namespaced_tip(from, branch)?;
+        let to = self.namespaced_tip(to, branch)?;
+        self.diff(from, to)
+    }
+
+    /// Get the [`Diff`] of the changes made on `head` since it diverged
+    /// from `base`, i.e. the `base...head` diff.
+    ///
+    /// This is the diff between the merge base of `base` and `head`, and
+    /// `head`, so changes made on `base` after the two diverged are not
+    /// included, unlike [`Repository::diff`].
+    ///
+    /// # Errors
+    ///
+    /// * [`error::Repo::NoMergeBase`] if `base` and `head` do not share any
+    ///   history.
+    pub fn triple_dot_diff(&self, base: impl Revision, head: impl Revision) -> Result<Diff, Error> {
+        let base = self.object_id(&base)?;
+        let head = self.object_id(&head)?;
+        let merge_base = self
+            .merge_base(base, head)?
+            .ok_or(error::Repo::NoMergeBase(base, head))?;
+        self.diff(merge_base, head)
+    }
+
+    /// Get the [`Diff`] of merging `theirs` into `ours`, i.e. the changes
+    /// that the merge would make to `ours`, without creating the merge.
+    ///
+    /// The files that can't be merged cleanly are
+    /// [`crate::diff::FileDiff::Conflicted`], with the sides of the
+    /// conflict from the common ancestor, `ours` and `theirs`.
+    ///
+    /// # Errors
+    ///
+    /// * [`error::Repo::NoMergeBase`] if `ours` and `theirs` do not share
+    ///   any history.
+    pub fn merge_diff(&self, ours: impl Revision, theirs: impl Revision) -> Result<Diff, Error> {
+        let ours = self.object_id(&ours)?;
+        let theirs = self.object_id(&theirs)?;
+        let merge_base = self
+            .merge_base(ours, theirs)?
+            .ok_or(error::Repo::NoMergeBase(ours, theirs))?;
+        let ours = self.find_commit(ours)?.tree()?;
+        let index = self.inner.merge_trees(
+            &self.find_commit(merge_base)?.tree()?,
+            &ours,
+            &self.find_commit(theirs)?.tree()?,
+            None,
+        )?;
+        let diff = self
+            .inner
+            .diff_tree_to_index(Some(&ours), Some(&index), None)?;
+        Ok(Diff::from_git(
+            diff,
+            Some(&self.inner.odb()?),
+            Some(&index),
+            &diff::Options::default(),
+        )?)
+    }
+
+    /// Returns the best common ancestor of `one` and `two`, i.e. the
+    /// commit `git merge-base` finds, or `None` if they do not share any
+    /// history.
+    ///
+    /// See [`crate::CachedRepository::merge_base`] for reusing the merge
+    /// bases of repeated comparisons, e.g. of many branches against the
+    /// same base.
+    pub fn merge_base(&self, one: impl Revision, two: impl Revision) -> Result<Option<Oid>, Error> {
+        let one = self.object_id(&one)?;
+        let two = self.object_id(&two)?;
+        match self.inner.merge_base(one.into(), two.into()) {
+            Ok(oid) => Ok(Some(oid.into())),
+            Err(err) if err.code() == git2::ErrorCode::NotFound => Ok(None),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    /// Get the diff [`diff::Stats`] between two commits.
+    ///
+    /// This is cheaper than calling [`Repository::diff`] and then
+    /// [`Diff::stats`], since the changes of each file are never
+    /// collected.
+    pub fn diff_stats(&self, from: impl Revision, to: impl Revision) -> Result<diff::Stats, Error> {
+        let from_commit = self.find_commit(self.object_id(&from)?)?;
+        let to_commit = self.find_commit(self.object_id(&to)?)?;
+        let diff = self.diff_commits(None, Some(&from_commit), &to_commit)?;
+        Ok(diff.stats()?.into())
+    }
+
+    /// Walk the diff between two commits, file by file, without collecting
+    /// it into a [`Diff`].
+    ///
+    /// `file_cb` is called with the path of each changed file, followed by
+    /// a call to `hunk_cb` for each of the file's [`Hunk`]s. Returning
+    /// `false` from either callback stops the walk.
+    ///
+    /// This is useful for streaming large diffs, since only a single
+    /// file's changes are held in memory at a time. Binary files and
+    /// renames without content changes produce no hunks.
+    pub fn diff_foreach<F, H>(
+        &self,
+        from: impl Revision,
+        to: impl Revision,
+        file_cb: F,
+        hunk_cb: H,
+    ) -> Result<(), Error>
+    where
+        F: FnMut(&Path) -> bool,
+        H: FnMut(&Path, Hunk<Modification>) -> bool,
+    {
+        let from_commit = self.find_commit(self.object_id(&from)?)?;
+        let to_commit = self.find_commit(self.object_id(&to)?)?;
+        let diff = self.diff_commits(None, Some(&from_commit), &to_commit)?;
+        Ok(diff::git::foreach(&diff, file_cb, hunk_cb)?)
     }
 
     /// Get the [`Diff`] of a `commit`.
@@ -203,10 +599,185 @@ impl Repository {
         }
     }
 
+    /// Get the [`Diff`] between two [`Directory`]s.
+    ///
+    /// The directories do not need to be at the same location, nor from
+    /// the same commit. The paths in the resulting [`Diff`] are relative
+    /// to the directories themselves, rather than to the repository root.
+    pub fn diff_directories(&self, from: &Directory, to: &Directory) -> Result<Diff, Error> {
+        let old_tree = self.find_tree(from.id())?;
+        let new_tree = self.find_tree(to.id())?;
+        self.diff_trees(None, Some(&old_tree), &new_tree)
+            .and_then(|diff| self.to_diff(diff, &diff::Options::default()))
+    }
+
+    /// Get a [`Diff`] of `commit` against each of its parents, in the
+    /// order of the parents.
+    ///
+    /// For a merge commit, this shows the changes brought in from each side
+    /// of the merge, as opposed to [`Repository::diff_commit`] which only
+    /// compares against the first parent. If the `commit` has no parents,
+    /// the single [`Diff`] is against nothing. See
+    /// [`Repository::files_changed_from_all_parents`] for the files changed
+    /// from every parent.
+    pub fn diff_commit_parents(&self, commit: impl ToCommit) -> Result<Vec<Diff>, Error> {
+        let commit = commit
+            .to_commit(self)
+            .map_err(|err| Error::ToCommit(err.into()))?;
+        if commit.parents.is_empty() {
+            return Ok(vec![self.initial_diff(commit.id)?]);
+        }
+        commit
+            .parents
+            .iter()
+            .map(|parent| self.diff(*parent, commit.id))
+            .collect()
+    }
+
+    /// Get the [`Diff`] of `commit` against its first parent, keeping only
+    /// the files that differ from every parent.
+    ///
+    /// For a merge commit, these are the files where the merge did more
+    /// than take the version of one side, e.g. to resolve a conflict. Only
+    /// whole files are filtered: their hunks are those of the first-parent
+    /// diff, including hunks that match another parent, so this is not a
+    /// combined diff like `git diff -c`. For a commit with a single parent,
+    /// or none, this is the same as [`Repository::diff_commit`].
+    pub fn files_changed_from_all_parents(&self, commit: impl ToCommit) -> Result<Diff, Error> {
+        let commit = commit
+            .to_commit(self)
+            .map_err(|err| Error::ToCommit(err.into()))?;
+        let diff = self.diff_commit(commit.id)?;
+        if commit.parents.len() < 2 {
+            return Ok(diff);
+        }
+
+        let git_commit = self.find_commit(commit.id)?;
+        let mut changed = BTreeSet::new();
+        for file in diff.files() {
+            let paths = match file {
+                FileDiff::Added(added) => vec![&added.path],
+                FileDiff::Deleted(deleted) => vec![&deleted.path],
+                FileDiff::Modified(modified) => vec![&modified.path],
+                FileDiff::Moved(moved) => vec![&moved.old_path, &moved.new_path],
+                FileDiff::Copied(copied) => vec![&copied.old_path, &copied.new_path],
+                FileDiff::Conflicted(conflicted) => vec![&conflicted.path],
+            };
+            for path in paths {
+                if self.changed_from_all_parents(path, &git_commit)? {
+                    changed.insert(path.clone());
+                }
+            }
+        }
+        Ok(diff.filter_paths(|path| changed.contains(path)))
+    }
+
+    /// Get the [`Diff`] between the `n`th first-parent ancestor of `rev`,
+    /// see [`Repository::ancestor`], and `rev` itself, i.e. the changes
+    /// made by the last `n` commits of `rev`, like `git diff rev~n rev`.
+    ///
+    /// If the history of `rev` is exactly `n` commits long, `rev~n` doesn't
+    /// exist, and the [`Diff`] is against nothing, i.e. the changes of the
+    /// whole history.
+    ///
+    /// # Errors
+    ///
+    /// * [`error::Repo::RevisionNotFound`] if the history of `rev` is
+    ///   fewer than `n` commits long.
+    pub fn diff_ancestors<R: Revision>(&self, rev: R, n: usize) -> Result<Diff, Error> {
+        let to = self.ancestor(rev, 0)?;
+        match self.ancestor(to, n) {
+            Ok(from) => self.diff(from, to),
+            Err(Error::Repo(error::Repo::RevisionNotFound(_)))
+                if n > 0 && self.ancestor(to, n - 1).is_ok() =>
+            {
+                self.initial_diff(to)
+            },
+            Err(err) => Err(err),
+        }
+    }
+
+    /// Get the changed files between two commits, with the status of each
+    /// change, like `git diff --name-status`.
+    ///
+    /// Only the deltas of the diff are looked at, and no patches are
+    /// generated, so this is cheaper than [`Repository::diff`] when only
+    /// the list of changed files is needed. The [`NameStatus::similarity`]
+    /// of a renamed file is the score `libgit2` gave it when detecting the
+    /// rename, which can differ slightly from the score of `git`.
+    pub fn diff_name_status(
+        &self,
+        from: impl Revision,
+        to: impl Revision,
+    ) -> Result<Vec<NameStatus>, Error> {
+        use diff::git::error::Diff::{DeltaUnhandled, PathUnavailable};
+
+        let from_commit = self.find_commit(self.object_id(&from)?)?;
+        let to_commit = self.find_commit(self.object_id(&to)?)?;
+        let diff = self.diff_commits(None, Some(&from_commit), &to_commit)?;
+
+        // `git2` doesn't expose the similarity of a delta, but it is the
+        // score after the status of its raw format, e.g.
+        // `:100644 100644 <old> <new> R087\told\tnew`.
+        let mut similarities = HashMap::new();
+        diff.print(git2::DiffFormat::Raw, |delta, _, line| {
+            let score = line
+                .content()
+                .split(|b| *b == b'\t')
+                .next()
+                .and_then(|header| header.rsplit(|b| *b == b' ').next())
+                .and_then(|status| std::str::from_utf8(status.get(1..)?).ok())
+                .and_then(|score| score.parse::<u8>().ok());
+            if let (Some(path), Some(score)) = (delta.new_file().path(), score) {
+                similarities.insert(path.to_path_buf(), score);
+            }
+            true
+        })?;
+
+        let mut changes = Vec::new();
+        for delta in diff.deltas() {
+            let status = match delta.status() {
+                git2::Delta::Added => 'A',
+                git2::Delta::Deleted => 'D',
+                git2::Delta::Modified => 'M',
+                git2::Delta::Renamed => 'R',
+                git2::Delta::Copied => 'C',
+                git2::Delta::Typechange => 'T',
+                status => return Err(DeltaUnhandled(status).into()),
+            };
+            let old_path = delta.old_file().path().ok_or(PathUnavailable)?;
+            let new_path = delta.new_file().path().ok_or(PathUnavailable)?;
+            let change = match status {
+                'R' | 'C' => NameStatus {
+                    status,
+                    similarity: similarities.get(new_path).copied(),
+                    old_path: Some(old_path.to_path_buf()),
+                    path: new_path.to_path_buf(),
+                },
+                _ => NameStatus {
+                    status,
+                    similarity: None,
+                    old_path: None,
+                    path: if status == 'D' { old_path } else { new_path }.to_path_buf(),
+                },
+            };
+            changes.push(change);
+        }
+        Ok(changes)
+    }
+
     /// Get the [`FileDiff`] between two revisions for a file at `path`.
     ///
     /// If `path` is only a directory name, not a file, returns
     /// a [`FileDiff`] for any file under `path`.
+    ///
+    /// The diff is restricted to `path`, so this is much cheaper than
+    /// [`Repository::diff`] when only a single file is of interest.
+    ///
+    /// # Errors
+    ///
+    /// * [`error::Repo::PathNotFound`] if the file at `path` is unchanged
+    ///   between `from` and `to`, or does not exist in either.
     pub fn diff_file<P: AsRef<Path>, R: Revision>(
         &self,
         path: &P,
@@ -217,7 +788,7 @@ impl Repository {
         let to_commit = self.find_commit(self.object_id(&to)?)?;
         let diff = self
             .diff_commits(Some(path.as_ref()), Some(&from_commit), &to_commit)
-            .and_then(|diff| Diff::try_from(diff).map_err(Error::from))?;
+            .and_then(|diff| self.to_diff(diff, &diff::Options::default()))?;
         let file_diff = diff
             .into_files()
             .pop()
@@ -225,11 +796,201 @@ impl Repository {
         Ok(file_diff)
     }
 
+    /// Compare the file paths in the trees of two revisions.
+    ///
+    /// Returns the paths that only exist in `from`, followed by the paths
+    /// that only exist in `to`. Only the presence of files is compared,
+    /// so changes to their content are ignored. This is cheaper than
+    /// computing a full [`Diff`] when only the structure is of interest.
+    pub fn tree_set_diff(
+        &self,
+        from: impl Revision,
+        to: impl Revision,
+    ) -> Result<(Vec<PathBuf>, Vec<PathBuf>), Error> {
+        let from = self.tree_paths(&self.find_commit(self.object_id(&from)?)?.tree()?)?;
+        let to = self.tree_paths(&self.find_commit(self.object_id(&to)?)?.tree()?)?;
+        Ok((
+            from.difference(&to).cloned().collect(),
+            to.difference(&from).cloned().collect(),
+        ))
+    }
+
     /// Parse an [`Oid`] from the given string.
     pub fn oid(&self, oid: &str) -> Result<Oid, Error> {
         Ok(self.inner.revparse_single(oid)?.id().into())
     }
 
+    /// Resolve `rev` to the [`Oid`] of the commit it points to.
+    ///
+    /// `rev` can be anything understood by `git rev-parse`, e.g. a branch
+    /// name, a tag name or an abbreviated object id. Annotated tags and
+    /// symbolic references are peeled to their commit.
+    ///
+    /// # Errors
+    ///
+    /// * [`error::Repo::RevisionNotFound`] if `rev` does not exist.
+    /// * [`error::Repo::InvalidRevision`] if `rev` is not a valid
+    ///   revision.
+    /// * [`error::Repo::NoReflogEntry`] if `rev` refers to an entry of a
+    ///   reflog, e.g. `main@{2}`, that the reflog does not have.
+    /// * [`error::Repo::NoUpstream`] if `rev` refers to the upstream of a
+    ///   branch, e.g. `main@{upstream}`, that has none configured.
+    pub fn resolve(&self, rev: &str) -> Result<Oid, Error> {
+        let object =
+            self.inner
+                .revparse_single(rev)
+                .map_err(|err| match (err.code(), err.class()) {
+                    // Reading `branch.<name>.remote` or `branch.<name>.merge`
+                    // failed.
+                    (git2::ErrorCode::NotFound, git2::ErrorClass::Config) => {
+                        error::Repo::NoUpstream(rev.to_string()).into()
+                    },
+                    (git2::ErrorCode::NotFound, git2::ErrorClass::Reference)
+                        if self.is_missing_reflog_entry(rev) =>
+                    {
+                        error::Repo::NoReflogEntry(rev.to_string()).into()
+                    },
+                    (git2::ErrorCode::NotFound, _) => {
+                        error::Repo::RevisionNotFound(rev.to_string()).into()
+                    },
+                    (git2::ErrorCode::InvalidSpec, _) => {
+                        error::Repo::InvalidRevision(rev.to_string()).into()
+                    },
+                    _ => Error::Git(err),
+                })?;
+        let commit = object.peel_to_commit()?;
+        Ok(commit.id().into())
+    }
+
+    /// Resolve `input` as any kind of revision, e.g. for a "go to" box
+    /// that accepts object ids, branches, tags and references alike.
+    ///
+    /// The first interpretation that matches wins, in the order:
+    ///
+    /// 1. a full object id,
+    /// 2. an abbreviated object id,
+    /// 3. a local branch name, e.g. `main`,
+    /// 4. a tag name, e.g. `v1.0`,
+    /// 5. a fully qualified reference name, e.g. `refs/notes/commits`,
+    ///    which is never taken as a branch or tag name,
+    /// 6. any revision understood by `git rev-parse`, e.g. `main~2`.
+    ///
+    /// # Errors
+    ///
+    /// * [`error::Repo::AmbiguousOid`] if `input` is an abbreviated object
+    ///   id matching more than one object.
+    /// * [`error::Repo::RevisionNotFound`] or
+    ///   [`error::Repo::InvalidRevision`] if nothing matched.
+    pub fn resolve_any(&self, input: &str) -> Result<ResolvedRev, Error> {
+        let is_hex = !input.is_empty() && input.bytes().all(|b| b.is_ascii_hexdigit());
+        if is_hex && input.len() == 40 {
+            let oid = git2::Oid::from_str(input)?;
+            if self.inner.odb()?.exists(oid) {
+                return Ok(ResolvedRev::Oid(oid.into()));
+            }
+        }
+        if is_hex && (4..40).contains(&input.len()) {
+            let short = git2::Oid::from_str(input)?;
+            match self.inner.odb()?.exists_prefix(short, input.len()) {
+                Ok(oid) => return Ok(ResolvedRev::ShortOid(oid.into())),
+                Err(err) if err.code() == git2::ErrorCode::Ambiguous => {
+                    return Err(error::Repo::AmbiguousOid(input.to_string()).into())
+                },
+                Err(err) if err.code() == git2::ErrorCode::NotFound => {},
+                Err(err) => return Err(err.into()),
+            }
+        }
+        if let Ok(name) = RefString::try_from(input) {
+            match name.qualified() {
+                Some(name) => {
+                    if let Some(reference) = self.find_namespaced_reference(&name)? {
+                        let target = reference
+                            .resolve()?
+                            .target()
+                            .ok_or_else(|| error::Repo::NoTarget(name.to_string()))?;
+                        return Ok(ResolvedRev::Reference(name.to_owned(), target.into()));
+                    }
+                },
+                None => {
+                    let branch = Branch::local(&name);
+                    if let Some(reference) = self.find_namespaced_reference(&branch.refname())? {
+                        let tip = reference.peel_to_commit()?.id().into();
+                        return Ok(ResolvedRev::Branch(branch, tip));
+                    }
+                    let tag = git_ext::ref_format::lit::refs_tags(&name).into();
+                    if let Some(reference) = self.find_namespaced_reference(&tag)? {
+                        let tag = Tag::try_from(&reference).map_err(refs::error::Tag::from)?;
+                        return Ok(ResolvedRev::Tag(tag));
+                    }
+                },
+            }
+        }
+        self.resolve(input).map(ResolvedRev::Revspec)
+    }
+
+    /// Resolve the reference `name`, following any chain of symbolic
+    /// references, e.g. `HEAD` to `refs/heads/main`.
+    ///
+    /// Returns the [`Oid`] the chain ends at, along with the names of the
+    /// references that were traversed, starting with `name`.
+    ///
+    /// When browsing a namespace, see [`Repository::switch_namespace`],
+    /// `name` and the names of the chain are those within the namespace,
+    /// e.g. `HEAD` is resolved as `refs/namespaces/<namespace>/HEAD`.
+    ///
+    /// # Errors
+    ///
+    /// * [`error::Repo::SymrefCycle`] if the symbolic references form a
+    ///   cycle.
+    /// * [`error::Repo::InvalidSymrefTarget`] if a symbolic reference
+    ///   doesn't point to a valid reference name.
+    /// * [`error::Repo::NoTarget`] if the last reference has no target.
+    pub fn resolve_reference(&self, name: &RefStr) -> Result<(Oid, Vec<RefString>), Error> {
+        let namespace = self.which_namespace()?;
+        let find = |name: &RefStr| match &namespace {
+            Some(namespace) => self
+                .inner
+                .find_reference(namespace.to_namespaced_name(name).as_str()),
+            None => self.inner.find_reference(name.as_str()),
+        };
+        // The symbolic references of a namespace point to the full names
+        // of their targets, which include the namespace.
+        let prefix = namespace.as_ref().map(|namespace| {
+            let head = namespace.head();
+            head.as_str().trim_end_matches("HEAD").to_owned()
+        });
+
+        let mut chain = vec![name.to_ref_string()];
+        let mut reference = find(name)?;
+        while let Some(target) = reference.symbolic_target_bytes() {
+            let target = str::from_utf8(target)
+                .ok()
+                .map(|target| match &prefix {
+                    Some(prefix) => target.strip_prefix(prefix.as_str()).unwrap_or(target),
+                    None => target,
+                })
+                .and_then(|target| RefString::try_from(target).ok())
+                .ok_or_else(|| {
+                    error::Repo::InvalidSymrefTarget(String::from_utf8_lossy(target).into_owned())
+                })?;
+            if chain.contains(&target) {
+                chain.push(target);
+                return Err(error::Repo::SymrefCycle(chain).into());
+            }
+            reference = find(&target)?;
+            chain.push(target);
+        }
+        let oid = reference.target().ok_or_else(|| {
+            error::Repo::NoTarget(
+                chain
+                    .last()
+                    .map(|name| name.to_string())
+                    .unwrap_or_default(),
+            )
+        })?;
+        Ok((oid.into(), chain))
+    }
+
     /// Returns a top level `Directory` without nested sub-directories.
     ///
     /// To visit inside any nested sub-directories, call `directory.get(&repo)`
@@ -253,12 +1014,162 @@ impl Repository {
         Ok(root.find_directory(path, self)?)
     }
 
+    /// Returns the entries of the stash, most recent first, like
+    /// `git stash list`.
+    ///
+    /// The entries are read from the reflog of `refs/stash`, so a
+    /// repository without a stash has no entries.
+    pub fn stashes(&self) -> Result<Vec<StashEntry>, Error> {
+        let reflog = self.inner.reflog("refs/stash")?;
+        Ok(reflog
+            .iter()
+            .enumerate()
+            .map(|(index, entry)| StashEntry {
+                index,
+                message: entry.message().unwrap_or_default().to_owned(),
+                id: entry.id_new().into(),
+            })
+            .collect())
+    }
+
+    /// Returns the paths of the files in `commit` for which `predicate`,
+    /// given the path and content of the file, returns `true`, e.g. to
+    /// find the files containing a `TODO` or starting with a shebang.
+    ///
+    /// All files are given to `predicate`, binary or not, and symbolic
+    /// links are given their target as content. Submodules are skipped.
+    pub fn filter_files<C, F>(&self, commit: C, mut predicate: F) -> Result<Vec<PathBuf>, Error>
+    where
+        C: ToCommit,
+        F: FnMut(&Path, &[u8]) -> bool,
+    {
+        let commit = commit
+            .to_commit(self)
+            .map_err(|err| Error::ToCommit(err.into()))?;
+        let tree = self.find_commit(commit.id)?.tree()?;
+        let mut files = Vec::new();
+        tree.walk(git2::TreeWalkMode::PreOrder, |root, entry| {
+            if entry.kind() == Some(git2::ObjectType::Blob) {
+                let name = String::from_utf8_lossy(entry.name_bytes());
+                files.push((Path::new(root).join(name.as_ref()), entry.id()));
+            }
+            git2::TreeWalkResult::Ok
+        })?;
+
+        let mut matches = Vec::new();
+        for (path, oid) in files {
+            let blob = self.find_blob(oid.into())?;
+            if predicate(&path, blob.content()) {
+                matches.push(path);
+            }
+        }
+        Ok(matches)
+    }
+
+    /// Returns the submodules declared in the `.gitmodules` file of
+    /// `commit`, along with the commit each of them is pinned to in its
+    /// tree.
+    ///
+    /// If `commit` has no `.gitmodules` file, there are no submodules. A
+    /// submodule that has no entry at its path in the tree is returned
+    /// with no [`Submodule::oid`].
+    pub fn submodules<C: ToCommit>(&self, commit: C) -> Result<Vec<Submodule>, Error> {
+        let commit = commit
+            .to_commit(self)
+            .map_err(|err| Error::ToCommit(err.into()))?;
+        let tree = self.find_commit(commit.id)?.tree()?;
+        let mut submodules = match tree.get_path(Path::new(submodule::GITMODULES)) {
+            Ok(entry) if entry.kind() == Some(git2::ObjectType::Blob) => {
+                let blob = self.find_blob(entry.id().into())?;
+                submodule::parse(&String::from_utf8_lossy(blob.content()))
+            },
+            Ok(_) => return Ok(vec![]),
+            Err(err) if err.code() == git2::ErrorCode::NotFound => return Ok(vec![]),
+            Err(err) => return Err(err.into()),
+        };
+        for submodule in submodules.iter_mut() {
+            match tree.get_path(&submodule.path) {
+                Ok(entry) if entry.kind() == Some(git2::ObjectType::Commit) => {
+                    submodule.oid = Some(entry.id().into());
+                },
+                Ok(_) => {},
+                Err(err) if err.code() == git2::ErrorCode::NotFound => {},
+                Err(err) => return Err(err.into()),
+            }
+        }
+        Ok(submodules)
+    }
+
+    /// Returns the number of entries of the directory at `path` in
+    /// `commit`, without loading the entries themselves.
+    ///
+    /// This is useful to decide whether to paginate a directory before
+    /// listing it.
+    pub fn entry_count<C: ToCommit, P: AsRef<Path>>(
+        &self,
+        commit: C,
+        path: &P,
+    ) -> Result<usize, Error> {
+        let dir = self.directory(commit, path)?;
+        Ok(self.find_tree(dir.id())?.len())
+    }
+
+    /// Returns a page of the entries of the directory at `path` in
+    /// `commit`, along with the total number of entries, see
+    /// [`Directory::entries_page`].
+    pub fn directory_page<C: ToCommit, P: AsRef<Path>>(
+        &self,
+        commit: C,
+        path: &P,
+        offset: usize,
+        limit: usize,
+    ) -> Result<(Vec<fs::Entry>, usize), Error> {
+        let dir = self.directory(commit, path)?;
+        Ok(dir.entries_page(self, offset, limit)?)
+    }
+
     /// Returns a [`File`] for `path` in `commit`.
     pub fn file<C: ToCommit, P: AsRef<Path>>(&self, commit: C, path: &P) -> Result<File, Error> {
         let root = self.root_dir(commit)?;
         Ok(root.find_file(path, self)?)
     }
 
+    /// Returns the [`fs::Entries`] of the directory at `path` in `commit`,
+    /// omitting the entries that are ignored by the `.gitignore` files of
+    /// `commit`.
+    ///
+    /// The `.gitignore` files of the root directory, and of each directory
+    /// leading to `path`, are respected, with nested files taking
+    /// precedence. If a parent directory is ignored, then all of its
+    /// entries are too. Use [`Directory::entries`] to list every entry
+    /// that was committed.
+    pub fn entries_not_ignored<C: ToCommit, P: AsRef<Path>>(
+        &self,
+        commit: C,
+        path: &P,
+    ) -> Result<fs::Entries, Error> {
+        let root = self.root_dir(commit)?;
+        let dir = root.find_directory(path, self)?;
+        let root_tree = self.find_tree(root.id())?;
+
+        let mut ignore = Ignore::default();
+        let mut current = PathBuf::new();
+        let mut ignored = false;
+        self.add_ignore_rules(&mut ignore, &root_tree, &current)?;
+        for component in dir.path().components() {
+            current.push(component);
+            if ignore.is_ignored(&current, true) {
+                ignored = true;
+                break;
+            }
+            self.add_ignore_rules(&mut ignore, &root_tree, &current)?;
+        }
+
+        let mut entries = dir.entries(self)?;
+        entries.retain(|entry| !ignored && !ignore.is_ignored(&entry.path(), entry.is_directory()));
+        Ok(entries)
+    }
+
     /// Returns a [`Tree`] for `path` in `commit`.
     pub fn tree<C: ToCommit, P: AsRef<Path>>(&self, commit: C, path: &P) -> Result<Tree, Error> {
         let commit = commit
@@ -301,22 +1212,250 @@ impl Repository {
         Ok(Blob::<BlobRef<'a>>::new(file.id(), git2_blob, last_commit))
     }
 
-    /// Returns the last commit, if exists, for a `path` in the history of
-    /// `rev`.
-    pub fn last_commit<P, C>(&self, path: &P, rev: C) -> Result<Option<Commit>, Error>
-    where
-        P: AsRef<Path>,
-        C: ToCommit,
-    {
-        let history = self.history(rev)?;
-        history.by_path(path).next().transpose()
+    /// Returns a [`Blob`] for `path` in `commit`, with the filters of the
+    /// `.gitattributes` files of `commit` applied to its content, i.e. the
+    /// content a checkout of `commit` would produce.
+    ///
+    /// The built-in `text`, `eol` and `ident` attributes are applied, along
+    /// with the `core.autocrlf` and `core.eol` settings of the repository,
+    /// e.g. to convert LF line endings to CRLF. Filter drivers, set with
+    /// the `filter` attribute, are external commands and are not run.
+    ///
+    /// The [`Blob::object_id`] is still the id of the committed blob.
+    pub fn blob_filtered<C: ToCommit, P: AsRef<Path>>(
+        &self,
+        commit: C,
+        path: &P,
+    ) -> Result<Blob<Vec<u8>>, Error> {
+        let commit = commit
+            .to_commit(self)
+            .map_err(|e| Error::ToCommit(e.into()))?;
+        let path = path.as_ref();
+        let blob = self.blob(commit.id, &path)?.to_owned();
+        let root = self.find_commit(commit.id)?.tree()?;
+
+        let mut attributes = Attributes::default();
+        let mut current = PathBuf::new();
+        self.add_attribute_rules(&mut attributes, &root, &current)?;
+        for component in path.parent().into_iter().flat_map(Path::components) {
+            current.push(component);
+            self.add_attribute_rules(&mut attributes, &root, &current)?;
+        }
+
+        let config = EolConfig::new(&self.inner.config()?);
+        let content = attributes
+            .smudge(path, blob.object_id(), blob.content(), config)
+            .into_owned();
+        Ok(blob.with_content(content))
+    }
+
+    /// Returns the tree of `commit` as an archive of the given `format`.
+    ///
+    /// See [`Repository::archive_tar_to`] and [`Repository::archive_zip_to`]
+    /// for writing the archive to a file, rather than keeping it in memory.
+    #[cfg(feature = "archive")]
+    pub fn archive<C: ToCommit>(&self, commit: C, format: ArchiveFormat) -> Result<Vec<u8>, Error> {
+        let mut buf = std::io::Cursor::new(Vec::new());
+        match format {
+            ArchiveFormat::Tar => self.archive_tar_to(commit, &mut buf)?,
+            ArchiveFormat::Zip => self.archive_zip_to(commit, &mut buf)?,
+        }
+        Ok(buf.into_inner())
+    }
+
+    /// Writes the tree of `commit` to `writer` as a `tar` archive.
+    ///
+    /// The file modes of the tree are preserved, i.e. executable files and
+    /// symbolic links, and every entry uses the commit time of `commit` as
+    /// its modification time.
+    #[cfg(feature = "archive")]
+    pub fn archive_tar_to<C, W>(&self, commit: C, writer: W) -> Result<(), Error>
+    where
+        C: ToCommit,
+        W: std::io::Write,
+    {
+        let (tree, mtime) = self.archive_tree(commit)?;
+        archive::write_tar(&self.inner, &tree, mtime, writer)?;
+        Ok(())
+    }
+
+    /// Writes the tree of `commit` to `writer` as a `zip` archive, see
+    /// [`Repository::archive_tar_to`].
+    ///
+    /// Unlike a `tar` archive, writing a `zip` archive requires `writer` to
+    /// be seekable, e.g. a file.
+    #[cfg(feature = "archive")]
+    pub fn archive_zip_to<C, W>(&self, commit: C, writer: W) -> Result<(), Error>
+    where
+        C: ToCommit,
+        W: std::io::Write + std::io::Seek,
+    {
+        let (tree, mtime) = self.archive_tree(commit)?;
+        archive::write_zip(&self.inner, &tree, mtime, writer)?;
+        Ok(())
+    }
+
+    /// Returns the size, in bytes, of the blob `oid`.
+    ///
+    /// Only the object header is read from the object database, so the
+    /// content of the blob is never loaded. This makes it cheap to check
+    /// the size of a file before deciding to fetch it.
+    pub fn blob_size(&self, oid: Oid) -> Result<u64, Error> {
+        let (size, kind) = self.inner.odb()?.read_header(oid.into())?;
+        if kind != git2::ObjectType::Blob {
+            return Err(error::Repo::NotBlob(oid).into());
+        }
+        Ok(size as u64)
+    }
+
+    /// Returns the shortest abbreviation of `oid` that is unambiguous in
+    /// this repository, and at least as long as `core.abbrev`, e.g.
+    /// `27acd68`.
+    pub fn short_id(&self, oid: Oid) -> Result<String, Error> {
+        let short = self.inner.find_object(oid.into(), None)?.short_id()?;
+        Ok(String::from_utf8_lossy(&short).into_owned())
+    }
+
+    /// Returns whether `path`, a file or a directory, exists in `commit`.
+    ///
+    /// Only the trees leading to `path` are read, so this is cheaper than
+    /// finding a [`Directory`] or [`File`] when their content is not
+    /// needed. The empty path is the root directory, so it always exists.
+    pub fn path_exists<C: ToCommit, P: AsRef<Path>>(
+        &self,
+        commit: C,
+        path: &P,
+    ) -> Result<bool, Error> {
+        let commit = commit
+            .to_commit(self)
+            .map_err(|err| Error::ToCommit(err.into()))?;
+        let path = path.as_ref();
+        if path.as_os_str().is_empty() {
+            return Ok(true);
+        }
+        let tree = self.find_commit(commit.id)?.tree()?;
+        match tree.get_path(path) {
+            Ok(_) => Ok(true),
+            Err(err) if err.code() == git2::ErrorCode::NotFound => Ok(false),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    /// Returns the size, in bytes, of the file at `path` in `commit`, or
+    /// `None` if the `path` does not exist.
+    ///
+    /// See [`Repository::blob_size`].
+    pub fn file_size<C: ToCommit, P: AsRef<Path>>(
+        &self,
+        commit: C,
+        path: &P,
+    ) -> Result<Option<u64>, Error> {
+        let root = self.root_dir(commit)?;
+        match root.find_file(path, self) {
+            Ok(file) => self.blob_size(file.id()).map(Some),
+            Err(fs::error::Directory::PathNotFound(_)) => Ok(None),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    /// Returns up to `max_bytes` of the content of the file at `path` in
+    /// `commit`, and whether the content was truncated.
+    ///
+    /// This is useful for previewing the start of large files. Loose
+    /// objects are streamed from the object database, so only `max_bytes`
+    /// are read. Packed objects cannot be streamed, and are loaded in full
+    /// before being truncated, but the full content is never returned.
+    pub fn file_prefix<C: ToCommit, P: AsRef<Path>>(
+        &self,
+        commit: C,
+        path: &P,
+        max_bytes: usize,
+    ) -> Result<(Vec<u8>, bool), Error> {
+        let file = self.file(commit, path)?;
+        match self.inner.odb()?.reader(file.id().into()) {
+            Ok((reader, size, _)) => {
+                let mut prefix = Vec::with_capacity(max_bytes.min(size));
+                reader
+                    .take(max_bytes as u64)
+                    .read_to_end(&mut prefix)
+                    .map_err(error::Repo::Io)?;
+                Ok((prefix, size > max_bytes))
+            },
+            // Only loose objects can be streamed, so the object is not
+            // found if it is packed, or streaming is unsupported if there
+            // is no loose object backend.
+            Err(err)
+                if err.code() == git2::ErrorCode::NotFound
+                    || (err.class() == git2::ErrorClass::Odb
+                        && err.code() == git2::ErrorCode::GenericError) =>
+            {
+                let blob = self.find_blob(file.id())?;
+                let content = blob.content();
+                let prefix = content[..max_bytes.min(content.len())].to_vec();
+                Ok((prefix, content.len() > max_bytes))
+            },
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    /// Returns the last commit, if exists, for a `path` in the history of
+    /// `rev`.
+    pub fn last_commit<P, C>(&self, path: &P, rev: C) -> Result<Option<Commit>, Error>
+    where
+        P: AsRef<Path>,
+        C: ToCommit,
+    {
+        let history = self.history(rev)?;
+        history.by_path(path).next().transpose()
     }
 
     /// Returns a commit for `rev`, if it exists.
+    ///
+    /// `rev` can be any [`Revision`], e.g. an [`Oid`], a [`Branch`] or a
+    /// [`Tag`], and is resolved once, when this is called. The returned
+    /// [`Commit`] is a [`ToCommit`], so it can be passed to other methods,
+    /// e.g. [`Repository::history`], to pin them to that commit even if
+    /// the branch moves on afterwards.
     pub fn commit<R: Revision>(&self, rev: R) -> Result<Commit, Error> {
         rev.to_commit(self)
     }
 
+    /// Returns the commits for `oids`, in the same order, e.g. to render a
+    /// list of commits whose ids are already known.
+    ///
+    /// # Errors
+    ///
+    /// Fails on the first of `oids` that is not a commit of this
+    /// repository, see [`Repository::commit`].
+    pub fn commits(&self, oids: &[Oid]) -> Result<Vec<Commit>, Error> {
+        oids.iter().map(|oid| self.commit(*oid)).collect()
+    }
+
+    /// Returns the [`Oid`] of the `n`th ancestor of `rev`, following only
+    /// the first parent of merge commits, i.e. `rev~n`.
+    ///
+    /// Use [`Repository::resolve`] for any other relative revision, e.g.
+    /// `HEAD~3` or `main^2`.
+    ///
+    /// # Errors
+    ///
+    /// * [`error::Repo::RevisionNotFound`] if the history of `rev` has
+    ///   fewer than `n` commits before it.
+    pub fn ancestor<R: Revision>(&self, rev: R, n: usize) -> Result<Oid, Error> {
+        let oid = self.object_id(&rev)?;
+        let mut commit = self.inner.find_object(oid.into(), None)?.peel_to_commit()?;
+        for _ in 0..n {
+            commit = match commit.parent(0) {
+                Ok(parent) => parent,
+                Err(err) if err.code() == git2::ErrorCode::NotFound => {
+                    return Err(error::Repo::RevisionNotFound(format!("{oid}~{n}")).into())
+                },
+                Err(err) => return Err(err.into()),
+            };
+        }
+        Ok(commit.id().into())
+    }
+
     /// Gets the [`Stats`] of this repository starting from the
     /// `HEAD` (see [`Repository::head`]) of the repository.
     pub fn stats(&self) -> Result<Stats, Error> {
@@ -330,6 +1469,8 @@ impl Repository {
         R: Revision,
     {
         let branches = self.branches(Glob::all_heads())?.count();
+        let tags = self.tags(&Glob::all_tags())?.count();
+        let namespaces = self.namespaces(&Glob::all_namespaces())?.count();
         let mut history = self.history(rev)?;
         let (commits, contributors) = history.try_fold(
             (0, BTreeSet::new()),
@@ -343,9 +1484,21 @@ impl Repository {
             branches,
             commits,
             contributors: contributors.len(),
+            tags,
+            namespaces,
         })
     }
 
+    /// Gets the [`OdbStats`] of this repository, i.e. the number and size
+    /// of its loose objects and packfiles.
+    ///
+    /// The stats are gathered by inspecting the `objects` directory of the
+    /// repository, so objects borrowed from alternates are not included.
+    pub fn odb_stats(&self) -> Result<OdbStats, Error> {
+        Self::objects_dir_stats(&self.inner.path().join("objects"))
+            .map_err(|err| error::Repo::Io(err).into())
+    }
+
     // TODO(finto): I think this can be removed in favour of using
     // `source::Blob::new`
     /// Retrieves the file with `path` in this commit.
@@ -366,13 +1519,56 @@ impl Repository {
         Ok(FileContent::new(blob))
     }
 
-    /// Returns the [`Oid`] of the current `HEAD`.
+    /// Returns `true` if the repository has no references, i.e. it was
+    /// freshly initialised and nothing was committed yet.
+    pub fn is_empty(&self) -> Result<bool, Error> {
+        Ok(self.inner.is_empty()?)
+    }
+
+    /// Returns the [`Oid`] of the current `HEAD`, or of the `HEAD` of the
+    /// namespace being browsed, see [`Repository::resolve_reference`].
+    ///
+    /// # Errors
+    ///
+    /// * [`error::Repo::EmptyRepository`] if the repository has no
+    ///   commits, see [`Repository::is_empty`].
     pub fn head(&self) -> Result<Oid, Error> {
-        let head = self.inner.head()?;
-        let head_commit = head.peel_to_commit()?;
+        let oid = match self.resolve_reference(&refname!("HEAD")) {
+            Ok((oid, _)) => oid,
+            Err(Error::Git(err))
+                if err.code() == git2::ErrorCode::NotFound && self.is_empty()? =>
+            {
+                return Err(error::Repo::EmptyRepository.into())
+            },
+            Err(err) => return Err(err),
+        };
+        let head_commit = self.inner.find_object(oid.into(), None)?.peel_to_commit()?;
         Ok(head_commit.id().into())
     }
 
+    /// Returns what the `HEAD` reference points to, i.e. either the
+    /// [`Branch`] it is attached to, or the commit it is detached at.
+    ///
+    /// Unlike [`Repository::head`], this succeeds for an empty repository,
+    /// since `HEAD` is still attached to the branch that the first commit
+    /// will be made on.
+    pub fn head_ref(&self) -> Result<HeadRef, Error> {
+        let head = self.inner.find_reference("HEAD")?;
+        match head.symbolic_target_bytes() {
+            Some(target) => {
+                let branch = str::from_utf8(target)
+                    .map_err(branch::error::Branch::from)
+                    .and_then(Branch::from_str)
+                    .map_err(refs::error::Branch::from)?;
+                Ok(HeadRef::Branch(branch))
+            },
+            None => {
+                let oid = head.target().ok_or(error::Repo::InvalidHead)?;
+                Ok(HeadRef::Detached(oid.into()))
+            },
+        }
+    }
+
     /// Extract the signature from a commit
     ///
     /// # Arguments
@@ -384,31 +1580,413 @@ impl Repository {
         commit: impl ToCommit,
         field: Option<&str>,
     ) -> Result<Option<Signature>, Error> {
-        // Match is necessary here because according to the documentation for
-        // git_commit_extract_signature at
-        // https://libgit2.org/libgit2/#HEAD/group/commit/git_commit_extract_signature
-        // the return value for a commit without a signature will be GIT_ENOTFOUND
+        let commit = commit
+            .to_commit(self)
+            .map_err(|e| Error::ToCommit(e.into()))?;
+        self.signature(commit.id, field)
+    }
+
+    /// Extract the payload covered by the signature of a commit, i.e. the
+    /// commit object without the signature header field, to verify the
+    /// signature returned by [`Repository::extract_signature`] against.
+    ///
+    /// Returns `None` if the commit is not signed.
+    ///
+    /// # Arguments
+    ///
+    /// `field` - the name of the header field containing the signature block;
+    ///           pass `None` to extract the default 'gpgsig'
+    pub fn signing_payload(
+        &self,
+        commit: impl ToCommit,
+        field: Option<&str>,
+    ) -> Result<Option<Vec<u8>>, Error> {
         let commit = commit
             .to_commit(self)
             .map_err(|e| Error::ToCommit(e.into()))?;
 
         match self.inner.extract_signature(&commit.id, field) {
-            Err(error) => {
-                if error.code() == git2::ErrorCode::NotFound {
-                    Ok(None)
-                } else {
-                    Err(error.into())
-                }
-            },
-            Ok(sig) => Ok(Some(Signature::from(sig.0))),
+            Err(error) if error.code() == git2::ErrorCode::NotFound => Ok(None),
+            Err(error) => Err(error.into()),
+            Ok((_, payload)) => Ok(Some(payload.to_vec())),
         }
     }
 
+    /// Returns the commits in the history of `head`, most recent first,
+    /// each paired with its signature, if it is signed.
+    ///
+    /// The signatures are extracted from the default `gpgsig` header field,
+    /// as with [`Repository::extract_signature`], while walking the history.
+    pub fn history_with_signatures<C: ToCommit>(
+        &self,
+        head: C,
+    ) -> Result<Vec<(Commit, Option<Signature>)>, Error> {
+        self.history(head)?
+            .map(|commit| {
+                let commit = commit?;
+                let signature = self.signature(commit.id, None)?;
+                Ok((commit, signature))
+            })
+            .collect()
+    }
+
     /// Returns the history with the `head` commit.
+    ///
+    /// `head` is resolved to a commit when this is called, and the
+    /// [`History`] starts from exactly that commit: it does not follow a
+    /// branch that is updated while it is being walked.
     pub fn history<C: ToCommit>(&self, head: C) -> Result<History, Error> {
         History::new(self, head)
     }
 
+    /// Returns the commits in the history of `head` that changed the file
+    /// at `path`, along with the changes they made to it, most recent
+    /// first.
+    ///
+    /// Renames are followed, like [`History::follow_renames`], and each
+    /// [`FileRevision`] has the path the file had in its commit, so that
+    /// renames can be shown along the history of the file.
+    pub fn file_evolution<C: ToCommit, P: AsRef<Path>>(
+        &self,
+        head: C,
+        path: &P,
+    ) -> Result<Vec<FileRevision>, Error> {
+        let head = head
+            .to_commit(self)
+            .map_err(|err| Error::ToCommit(err.into()))?;
+        let mut revwalk = self.revwalk()?;
+        revwalk.push(head.id.into())?;
+
+        let mut path = path.as_ref().to_path_buf();
+        let mut revisions = Vec::new();
+        for oid in revwalk {
+            let commit = self.find_commit(oid?.into())?;
+            // Only compute the full diff, with its patches, for the commits
+            // that touch `path`.
+            if self.diff_commit_and_parents(&path, &commit)?.is_none() {
+                continue;
+            }
+            let parent = commit.parents().next();
+            let diff = self.to_diff(
+                self.diff_commits(None, parent.as_ref(), &commit)?,
+                &diff::Options::default(),
+            )?;
+            let file = diff.into_files().into_iter().find(|file| match file {
+                FileDiff::Added(added) => added.path == path,
+                FileDiff::Deleted(deleted) => deleted.path == path,
+                FileDiff::Modified(modified) => modified.path == path,
+                FileDiff::Moved(moved) => moved.new_path == path,
+                FileDiff::Copied(copied) => copied.new_path == path,
+                FileDiff::Conflicted(_) => false,
+            });
+            let (diff, old_path) = match file {
+                Some(FileDiff::Added(added)) => (added.diff, None),
+                Some(FileDiff::Deleted(deleted)) => (deleted.diff, None),
+                Some(FileDiff::Modified(modified)) => (modified.diff, None),
+                Some(FileDiff::Moved(moved)) => (moved.diff, Some(moved.old_path)),
+                Some(FileDiff::Copied(copied)) => (copied.diff, Some(copied.old_path)),
+                // `path` is a directory, or only matched as a prefix.
+                Some(FileDiff::Conflicted(_)) | None => continue,
+            };
+            revisions.push(FileRevision {
+                commit: Commit::try_from(commit)?,
+                path: path.clone(),
+                old_path: old_path.clone(),
+                diff,
+            });
+            // Continue the history of the file under its old path.
+            if let Some(old_path) = old_path {
+                path = old_path;
+            }
+        }
+        Ok(revisions)
+    }
+
+    /// Returns the commits in the history of `head` that changed the lines
+    /// `start` to `end` of the file at `path`, along with the changes they
+    /// made to them, most recent first, like `git log -L start,end:path`.
+    ///
+    /// The line numbers are 1-based and inclusive, and refer to the file
+    /// in `head`. Going back in history, the range follows the lines as
+    /// they shift with the changes made before them, and the file is
+    /// followed across renames. The history of the range stops at the
+    /// commits that added all of its lines.
+    ///
+    /// The [`FileRevision::diff`] of each commit only has the hunks that
+    /// touch the range, as it was in that commit.
+    ///
+    /// Merge commits are only returned if they changed the file in
+    /// respect to all of their parents, and the range in respect to their
+    /// first parent. The range is followed into each parent it came from.
+    ///
+    /// # Errors
+    ///
+    /// * [`error::Repo::InvalidLineRange`] if `start` is `0`, greater than
+    ///   `end`, or greater than the number of lines of the file.
+    pub fn line_history<C: ToCommit, P: AsRef<Path>>(
+        &self,
+        head: C,
+        path: &P,
+        start: u32,
+        end: u32,
+    ) -> Result<Vec<FileRevision>, Error> {
+        let head = head
+            .to_commit(self)
+            .map_err(|err| Error::ToCommit(err.into()))?;
+        // Like `git`, the range is clamped to the lines of the file.
+        let file = self.file(head.id, path)?;
+        let blob = self.find_blob(file.id())?;
+        let content = blob.content();
+        let line_count = content.split(|b| *b == b'\n').count()
+            - usize::from(content.is_empty() || content.ends_with(b"\n"));
+        let line_count = u32::try_from(line_count).unwrap_or(u32::MAX);
+        if start == 0 || start > end || start > line_count {
+            return Err(error::Repo::InvalidLineRange(start, end).into());
+        }
+        let end = end.min(line_count);
+        let mut revwalk = self.revwalk()?;
+        revwalk.set_sorting(git2::Sort::TOPOLOGICAL)?;
+        revwalk.push(head.id.into())?;
+
+        // The path and lines tracked in each commit still to be visited.
+        let mut tracked = HashMap::new();
+        tracked.insert(head.id, (path.as_ref().to_path_buf(), start..=end));
+        let mut revisions = Vec::new();
+        for oid in revwalk {
+            let oid = Oid::from(oid?);
+            let (path, lines) = match tracked.remove(&oid) {
+                Some(tracked) => tracked,
+                None => continue,
+            };
+            let commit = self.find_commit(oid)?;
+            let parents = commit.parents().collect::<Vec<_>>();
+
+            let mut changes = Vec::new();
+            let mut unchanged = None;
+            for parent in &parents {
+                match self.file_change(&path, Some(parent), &commit)? {
+                    Some(change) => changes.push((parent, change)),
+                    None => {
+                        unchanged = Some(parent);
+                        break;
+                    },
+                }
+            }
+            // The lines come from a parent with the same file, like `git`
+            // simplifies the history of a path.
+            if let Some(parent) = unchanged {
+                track_lines(&mut tracked, parent.id().into(), path, lines);
+                continue;
+            }
+            let change = match changes.first() {
+                Some((_, change)) => change.clone(),
+                // A root commit adds all of its files.
+                None => match self.file_change(&path, None, &commit)? {
+                    Some(change) => change,
+                    None => continue,
+                },
+            };
+            for (parent, (content, old_path)) in changes {
+                if let Some(old_lines) = content.map_lines_to_old(&lines) {
+                    let old_path = old_path.unwrap_or_else(|| path.clone());
+                    track_lines(&mut tracked, parent.id().into(), old_path, old_lines);
+                }
+            }
+            let (content, old_path) = change;
+            if content.touches(&lines) {
+                revisions.push(FileRevision {
+                    commit: Commit::try_from(commit)?,
+                    path,
+                    old_path,
+                    diff: content.restrict_to(&lines),
+                });
+            }
+        }
+        Ok(revisions)
+    }
+
+    /// Returns up to `limit` commits in the history of `head`, most recent
+    /// first, each paired with its [`Diff`] against its first parent.
+    ///
+    /// The result is the same as [`History::with_diffs`], but each commit's
+    /// parent is loaded only once: it is reused as the next commit of the
+    /// walk, rather than being looked up again. This makes it the better
+    /// choice for diffing a long, mostly linear, history.
+    pub fn sequential_diffs<C: ToCommit>(
+        &self,
+        head: C,
+        limit: usize,
+    ) -> Result<Vec<(Commit, Diff)>, Error> {
+        let head = head
+            .to_commit(self)
+            .map_err(|err| Error::ToCommit(err.into()))?;
+        let mut revwalk = self.revwalk()?;
+        revwalk.push(head.id.into())?;
+
+        let mut diffs = Vec::new();
+        let mut parent: Option<git2::Commit> = None;
+        for oid in revwalk.take(limit) {
+            let oid = oid?;
+            let commit = match parent.take() {
+                Some(parent) if parent.id() == oid => parent,
+                _ => self.find_commit(oid.into())?,
+            };
+            parent = commit.parents().next();
+            let diff = self.to_diff(
+                self.diff_commits(None, parent.as_ref(), &commit)?,
+                &diff::Options::default(),
+            )?;
+            diffs.push((Commit::try_from(commit)?, diff));
+        }
+        Ok(diffs)
+    }
+
+    /// Returns the ids of all the objects reachable from `rev`, i.e. the
+    /// commits in its history, along with their trees and blobs, as well
+    /// as the tag object if `rev` is an annotated tag.
+    ///
+    /// This is the set of objects that would survive pruning if `rev`
+    /// were the only reference. Note that this walks the entire history
+    /// and every tree in it, so it is expensive for large repositories.
+    pub fn reachable_objects<R: Revision>(&self, rev: R) -> Result<BTreeSet<Oid>, Error> {
+        let oid = self.object_id(&rev)?;
+        let object = self.inner.find_object(oid.into(), None)?;
+        let mut objects = BTreeSet::new();
+        if object.kind() == Some(git2::ObjectType::Tag) {
+            objects.insert(oid);
+        }
+
+        let mut revwalk = self.revwalk()?;
+        revwalk.push(object.peel_to_commit()?.id())?;
+        for commit in revwalk {
+            let commit = self.inner.find_commit(commit?)?;
+            objects.insert(commit.id().into());
+            self.insert_tree_objects(&commit.tree()?, &mut objects)?;
+        }
+        Ok(objects)
+    }
+
+    /// Returns the commits in the history of `head` in which the person
+    /// with `email` had `role`, e.g. to tell the changes they wrote apart
+    /// from the ones they merged.
+    ///
+    /// The email is compared exactly, as it is recorded in the commits.
+    pub fn commits_by<C: ToCommit>(
+        &self,
+        head: C,
+        email: &str,
+        role: AuthorRole,
+    ) -> Result<Vec<Commit>, Error> {
+        self.history(head)?
+            .filter(|commit| {
+                commit
+                    .as_ref()
+                    .map_or(true, |commit| role.matches(commit, email))
+            })
+            .collect()
+    }
+
+    /// Returns the merge commits, i.e. the commits with more than one
+    /// parent, in the history of `head`.
+    pub fn merge_commits<C: ToCommit>(&self, head: C) -> Result<Vec<Commit>, Error> {
+        self.history(head)?
+            .filter(|commit| {
+                commit
+                    .as_ref()
+                    .map_or(true, |commit| commit.parents.len() > 1)
+            })
+            .collect()
+    }
+
+    /// Returns the [`Churn`] of the commits reachable from `to` but not
+    /// from `from`, i.e. the lines added and deleted by each of them, in
+    /// respect to its parent.
+    ///
+    /// Like `git log --numstat`, merge commits are skipped, so that the
+    /// changes they bring in are not counted twice, and binary files have
+    /// no lines. Files are identified by their path in each commit, so a
+    /// renamed file is counted under each of its paths.
+    pub fn churn(&self, from: impl Revision, to: impl Revision) -> Result<Churn, Error> {
+        let from = self.object_id(&from)?;
+        let to = self.object_id(&to)?;
+
+        let mut churn = Churn::default();
+        let mut files = BTreeMap::<PathBuf, (usize, usize)>::new();
+        for commit in self.commits_between(from, to)? {
+            churn.commits += 1;
+            let parent = commit.parents().next();
+            let diff = self.diff_commits(None, parent.as_ref(), &commit)?;
+            for (idx, delta) in diff.deltas().enumerate() {
+                let path = delta
+                    .new_file()
+                    .path()
+                    .or_else(|| delta.old_file().path())
+                    .ok_or(diff::git::error::Diff::PathUnavailable)?;
+                let (_, insertions, deletions) = match git2::Patch::from_diff(&diff, idx)? {
+                    Some(patch) => patch.line_stats()?,
+                    None => (0, 0, 0),
+                };
+                churn.insertions += insertions;
+                churn.deletions += deletions;
+                let file = files.entry(path.to_path_buf()).or_default();
+                file.0 += insertions;
+                file.1 += deletions;
+            }
+        }
+
+        churn.files = files
+            .into_iter()
+            .map(|(path, (insertions, deletions))| FileChurn {
+                path,
+                insertions,
+                deletions,
+            })
+            .collect();
+        // The sort is stable, so equally churned files stay sorted by path.
+        churn
+            .files
+            .sort_by_key(|file| std::cmp::Reverse(file.total()));
+        Ok(churn)
+    }
+
+    /// Returns the commits reachable from `head` that were committed at, or
+    /// after, `since`, newest first.
+    ///
+    /// The history is walked in commit time order and the walk stops once
+    /// it only finds older commits. Since commit times are not guaranteed
+    /// to be monotonic, e.g. due to clock skew, an older commit may sit
+    /// above newer ones. To account for this, the walk only stops after
+    /// seeing a number of consecutive commits older than `since`, similar
+    /// to `git log --since`, and any older commits seen on the way are
+    /// skipped.
+    pub fn commits_since<C: ToCommit>(&self, head: C, since: Time) -> Result<Vec<Commit>, Error> {
+        /// The number of consecutive older commits to walk before stopping.
+        const SLOP: usize = 5;
+
+        let head = head
+            .to_commit(self)
+            .map_err(|err| Error::ToCommit(err.into()))?;
+        let mut revwalk = self.revwalk()?;
+        revwalk.set_sorting(git2::Sort::TIME)?;
+        revwalk.push(head.id.into())?;
+
+        let mut commits = vec![];
+        let mut older = 0;
+        for oid in revwalk {
+            let commit = self.find_commit(oid?.into())?;
+            if commit.time().seconds() >= since.seconds() {
+                commits.push(Commit::try_from(commit)?);
+                older = 0;
+            } else {
+                older += 1;
+                if older >= SLOP {
+                    break;
+                }
+            }
+        }
+        Ok(commits)
+    }
+
     /// Lists branches that are reachable from `rev`.
     pub fn revision_branches(
         &self,
@@ -428,6 +2006,86 @@ impl Repository {
 
         Ok(contained_branches)
     }
+
+    /// Finds the commits of `head` that are not in `upstream`, and whether
+    /// an equivalent change was already applied to `upstream`, similar to
+    /// `git cherry`.
+    ///
+    /// Two commits are considered equivalent if they have the same
+    /// patch-id, i.e. they introduce the same changes regardless of their
+    /// commit metadata. Merge commits are skipped.
+    ///
+    /// The commits are returned in the order they were applied, oldest
+    /// first.
+    pub fn cherry(
+        &self,
+        upstream: impl Revision,
+        head: impl Revision,
+    ) -> Result<Vec<(Oid, CherryStatus)>, Error> {
+        let upstream = self.object_id(&upstream)?;
+        let head = self.object_id(&head)?;
+
+        let mut upstream_ids = BTreeSet::new();
+        for commit in self.commits_between(head, upstream)? {
+            upstream_ids.insert(self.patch_id(&commit)?);
+        }
+
+        self.commits_between(upstream, head)?
+            .iter()
+            .rev()
+            .map(|commit| {
+                let status = if upstream_ids.contains(&self.patch_id(commit)?) {
+                    CherryStatus::Equivalent
+                } else {
+                    CherryStatus::Unique
+                };
+                Ok((commit.id().into(), status))
+            })
+            .collect()
+    }
+
+    /// Lists branches, matching `glob`, whose tip commit has the root tree
+    /// `tree`.
+    ///
+    /// Different commits can share the same tree, so this can be used to
+    /// find branches that are content-identical.
+    pub fn tree_branches(&self, tree: Oid, glob: Glob<Branch>) -> Result<Vec<Branch>, Error> {
+        let mut matching = vec![];
+        for branch in self.branches(glob)? {
+            let branch = branch?;
+            let commit = self.find_commit(self.object_id(&branch)?)?;
+            if Oid::from(commit.tree_id()) == tree {
+                matching.push(branch);
+            }
+        }
+        Ok(matching)
+    }
+
+    /// Lists tags, matching `glob`, whose tagged commit has the root tree
+    /// `tree`.
+    ///
+    /// See [`Repository::tree_branches`].
+    pub fn tree_tags(&self, tree: Oid, glob: &Glob<Tag>) -> Result<Vec<Tag>, Error> {
+        let mut matching = vec![];
+        for tag in self.tags(glob)? {
+            let tag = tag?;
+            let object = self.inner.find_object(tag.id().into(), None)?;
+            let target = match object.kind() {
+                Some(git2::ObjectType::Tag) => object.peel(git2::ObjectType::Any)?,
+                _ => object,
+            };
+            // Tags of blobs have no tree, and are skipped.
+            let tagged = match target.kind() {
+                Some(git2::ObjectType::Commit) => target.peel_to_tree()?.id(),
+                Some(git2::ObjectType::Tree) => target.id(),
+                _ => continue,
+            };
+            if Oid::from(tagged) == tree {
+                matching.push(tag);
+            }
+        }
+        Ok(matching)
+    }
 }
 
 ////////////////////////////////////////////////////////////
@@ -459,15 +2117,222 @@ impl Repository {
         self.inner.revwalk()
     }
 
+    fn signature(&self, oid: Oid, field: Option<&str>) -> Result<Option<Signature>, Error> {
+        // Match is necessary here because according to the documentation for
+        // git_commit_extract_signature at
+        // https://libgit2.org/libgit2/#HEAD/group/commit/git_commit_extract_signature
+        // the return value for a commit without a signature will be GIT_ENOTFOUND
+        match self.inner.extract_signature(&oid, field) {
+            Err(error) => {
+                if error.code() == git2::ErrorCode::NotFound {
+                    Ok(None)
+                } else {
+                    Err(error.into())
+                }
+            },
+            Ok(sig) => Ok(Some(Signature::from(sig.0))),
+        }
+    }
+
     pub(super) fn object_id<R: Revision>(&self, r: &R) -> Result<Oid, Error> {
         r.object_id(self).map_err(|err| Error::Revision(err.into()))
     }
 
+    /// Inserts the ids of `tree`, and of all the trees and blobs reachable
+    /// from it, into `objects`. Trees that are already in `objects` are not
+    /// walked again.
+    fn insert_tree_objects(
+        &self,
+        tree: &git2::Tree,
+        objects: &mut BTreeSet<Oid>,
+    ) -> Result<(), git2::Error> {
+        if !objects.insert(tree.id().into()) {
+            return Ok(());
+        }
+        for entry in tree.iter() {
+            match entry.kind() {
+                Some(git2::ObjectType::Tree) => {
+                    self.insert_tree_objects(&self.inner.find_tree(entry.id())?, objects)?
+                },
+                Some(git2::ObjectType::Blob) => {
+                    objects.insert(entry.id().into());
+                },
+                // Submodule commits are objects of another repository.
+                _ => {},
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns the paths of all files in `tree`, including the files of
+    /// its sub-trees.
+    fn tree_paths(&self, tree: &git2::Tree) -> Result<BTreeSet<PathBuf>, Error> {
+        let mut paths = BTreeSet::new();
+        tree.walk(git2::TreeWalkMode::PreOrder, |root, entry| {
+            if entry.kind() != Some(git2::ObjectType::Tree) {
+                let name = String::from_utf8_lossy(entry.name_bytes());
+                paths.insert(Path::new(root).join(name.as_ref()));
+            }
+            git2::TreeWalkResult::Ok
+        })?;
+        Ok(paths)
+    }
+
+    /// Adds the rules of the `.gitignore` file in the directory `dir` of
+    /// `root`, if there is one.
+    fn add_ignore_rules(
+        &self,
+        ignore: &mut Ignore,
+        root: &git2::Tree,
+        dir: &Path,
+    ) -> Result<(), Error> {
+        if let Some(blob) = self.find_dir_blob(root, dir, ignore::GITIGNORE)? {
+            ignore.add(dir, blob.content());
+        }
+        Ok(())
+    }
+
+    /// Adds the rules of the `.gitattributes` file in the directory `dir`
+    /// of `root`, if there is one.
+    fn add_attribute_rules(
+        &self,
+        attributes: &mut Attributes,
+        root: &git2::Tree,
+        dir: &Path,
+    ) -> Result<(), Error> {
+        if let Some(blob) = self.find_dir_blob(root, dir, attributes::GITATTRIBUTES)? {
+            attributes.add(dir, blob.content());
+        }
+        Ok(())
+    }
+
+    /// Returns the blob of the file `name` in the directory `dir` of
+    /// `root`, if there is one.
+    fn find_dir_blob(
+        &self,
+        root: &git2::Tree,
+        dir: &Path,
+        name: &str,
+    ) -> Result<Option<git2::Blob<'_>>, Error> {
+        match root.get_path(&dir.join(name)) {
+            Ok(entry) if entry.kind() == Some(git2::ObjectType::Blob) => {
+                Ok(Some(self.find_blob(entry.id().into())?))
+            },
+            Ok(_) => Ok(None),
+            Err(err) if err.code() == git2::ErrorCode::NotFound => Ok(None),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    /// Gathers the [`OdbStats`] for the loose objects and packfiles found
+    /// in `objects`. Missing directories are treated as empty.
+    fn objects_dir_stats(objects: &Path) -> Result<OdbStats, std::io::Error> {
+        let read_dir = |path: &Path| match std::fs::read_dir(path) {
+            Ok(entries) => entries.collect::<Result<Vec<_>, _>>(),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(vec![]),
+            Err(err) => Err(err),
+        };
+
+        let mut stats = OdbStats::default();
+        for dir in read_dir(objects)? {
+            let name = dir.file_name();
+            let is_fanout = name.len() == 2
+                && name
+                    .to_str()
+                    .is_some_and(|name| name.bytes().all(|b| b.is_ascii_hexdigit()));
+            if is_fanout && dir.file_type()?.is_dir() {
+                for object in read_dir(&dir.path())? {
+                    stats.loose_objects += 1;
+                    stats.loose_size += object.metadata()?.len();
+                }
+            }
+        }
+        for file in read_dir(&objects.join("pack"))? {
+            if file.path().extension().is_some_and(|ext| ext == "pack") {
+                stats.packs += 1;
+            }
+            stats.pack_size += file.metadata()?.len();
+        }
+        Ok(stats)
+    }
+
+    /// Returns the reference `name` in the current namespace, or `None`
+    /// if it does not exist.
+    fn find_namespaced_reference(
+        &self,
+        name: &Qualified<'_>,
+    ) -> Result<Option<git2::Reference<'_>>, Error> {
+        let name = self.namespaced_refname(name)?;
+        match self.inner.find_reference(name.as_str()) {
+            Ok(reference) => Ok(Some(reference)),
+            Err(err) if err.code() == git2::ErrorCode::NotFound => Ok(None),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    /// Returns the [`Oid`] of the tip commit of `branch` in `namespace`.
+    fn namespaced_tip(&self, namespace: &Namespace, branch: &Branch) -> Result<Oid, Error> {
+        let name = namespace.to_namespaced(&branch.refname()).into_qualified();
+        let reference = self.inner.find_reference(name.as_str())?;
+        Ok(reference.peel_to_commit()?.id().into())
+    }
+
+    /// Returns the non-merge commits reachable from `head` but not from
+    /// `hide`, in topological order starting from `head`.
+    fn commits_between(&self, hide: Oid, head: Oid) -> Result<Vec<git2::Commit<'_>>, Error> {
+        let mut revwalk = self.revwalk()?;
+        revwalk.set_sorting(git2::Sort::TOPOLOGICAL)?;
+        revwalk.push(head.into())?;
+        revwalk.hide(hide.into())?;
+
+        let mut commits = vec![];
+        for oid in revwalk {
+            let commit = self.find_commit(oid?.into())?;
+            if commit.parent_count() <= 1 {
+                commits.push(commit);
+            }
+        }
+        Ok(commits)
+    }
+
+    /// Computes the patch-id of the changes introduced by `commit`, in
+    /// respect to its first parent.
+    fn patch_id(&self, commit: &git2::Commit) -> Result<Oid, Error> {
+        let parent = commit.parents().next();
+        let diff = self.diff_commits(None, parent.as_ref(), commit)?;
+        Ok(diff.patchid(None)?.into())
+    }
+
     /// Get the [`Diff`] of a commit with no parents.
     fn initial_diff<R: Revision>(&self, rev: R) -> Result<Diff, Error> {
         let commit = self.find_commit(self.object_id(&rev)?)?;
         self.diff_commits(None, None, &commit)
-            .and_then(|diff| Diff::try_from(diff).map_err(Error::from))
+            .and_then(|diff| self.to_diff(diff, &diff::Options::default()))
+    }
+
+    /// Lists the branches with `filter` whose tip is reachable from
+    /// `target` if `merged`, and whose tip is not otherwise.
+    fn branches_by_merged<R, G>(
+        &self,
+        target: &R,
+        filter: G,
+        merged: bool,
+    ) -> Result<Vec<Branch>, Error>
+    where
+        R: Revision,
+        G: Into<Glob<Branch>>,
+    {
+        let target = git2::Oid::from(self.object_id(target)?);
+        let mut branches = Vec::new();
+        for tip in self.branch_tips(filter)? {
+            let (branch, tip) = tip?;
+            let tip = git2::Oid::from(tip);
+            let reachable = tip == target || self.inner.graph_descendant_of(target, tip)?;
+            if reachable == merged {
+                branches.push(branch);
+            }
+        }
+        Ok(branches)
     }
 
     fn reachable_from(&self, reference: &git2::Reference, oid: &Oid) -> Result<bool, Error> {
@@ -488,14 +2353,128 @@ impl Repository {
     {
         let mut parents = commit.parents();
 
-        let diff = self.diff_commits(Some(path.as_ref()), parents.next().as_ref(), commit)?;
-        if let Some(_delta) = diff.deltas().next() {
+        if self.changes_path(path.as_ref(), parents.next().as_ref(), commit)? {
             Ok(Some(path.as_ref().to_path_buf()))
         } else {
             Ok(None)
         }
     }
 
+    /// Returns the changes `commit` made to the file at `path` in respect
+    /// to `parent`, along with the path the file had in `parent` if
+    /// `commit` renamed it, or `None` if the file is unchanged.
+    fn file_change(
+        &self,
+        path: &Path,
+        parent: Option<&git2::Commit>,
+        commit: &git2::Commit,
+    ) -> Result<Option<(DiffContent, Option<PathBuf>)>, Error> {
+        let diff = self.diff_commits(Some(path), parent, commit)?;
+        let renamed = match diff.deltas().next() {
+            None => return Ok(None),
+            // The file may have been renamed, which is only detected when
+            // diffing all the files.
+            Some(delta) => delta.status() == git2::Delta::Added && parent.is_some(),
+        };
+        let diff = if renamed {
+            self.to_diff(
+                self.diff_commits(None, parent, commit)?,
+                &diff::Options::default(),
+            )?
+        } else {
+            self.to_diff(diff, &diff::Options::default())?
+        };
+        Ok(diff.into_files().into_iter().find_map(|file| match file {
+            FileDiff::Added(added) if added.path == path => Some((added.diff, None)),
+            FileDiff::Deleted(deleted) if deleted.path == path => Some((deleted.diff, None)),
+            FileDiff::Modified(modified) if modified.path == path => Some((modified.diff, None)),
+            FileDiff::Moved(moved) if moved.new_path == path => {
+                Some((moved.diff, Some(moved.old_path)))
+            },
+            FileDiff::Copied(copied) if copied.new_path == path => {
+                Some((copied.diff, Some(copied.old_path)))
+            },
+            _ => None,
+        }))
+    }
+
+    /// Returns whether `commit` changed `path` in respect to each of its
+    /// parents.
+    pub(crate) fn changed_from_all_parents<P>(
+        &self,
+        path: &P,
+        commit: &git2::Commit,
+    ) -> Result<bool, Error>
+    where
+        P: AsRef<Path>,
+    {
+        for parent in commit.parents() {
+            if !self.changes_path(path.as_ref(), Some(&parent), commit)? {
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    }
+
+    /// Returns the old path of `path` if it was renamed by `commit`, in
+    /// respect to its first parent.
+    pub(crate) fn renamed_from<P>(
+        &self,
+        path: &P,
+        commit: &git2::Commit,
+    ) -> Result<Option<PathBuf>, Error>
+    where
+        P: AsRef<Path>,
+    {
+        let parent = commit.parents().next();
+        let diff = self.diff_commits(None, parent.as_ref(), commit)?;
+        let renamed = diff
+            .deltas()
+            .filter(|delta| delta.status() == git2::Delta::Renamed)
+            .find(|delta| delta.new_file().path() == Some(path.as_ref()))
+            .and_then(|delta| delta.old_file().path().map(Path::to_path_buf));
+        Ok(renamed)
+    }
+
+    /// Returns whether `rev` refers to an entry of a reflog, e.g.
+    /// `main@{2}`, that the reflog does not have.
+    fn is_missing_reflog_entry(&self, rev: &str) -> bool {
+        let (name, rest) = match rev.split_once("@{") {
+            Some(split) => split,
+            None => return false,
+        };
+        let n = match rest.split_once('}').map(|(n, _)| n.parse::<usize>()) {
+            Some(Ok(n)) => n,
+            _ => return false,
+        };
+        // `@{n}` is an entry of the reflog of `HEAD`.
+        let name = if name.is_empty() { "HEAD" } else { name };
+        let reference = match self.inner.resolve_reference_from_short_name(name) {
+            Ok(reference) => reference,
+            Err(_) => return false,
+        };
+        match reference.name().map(|name| self.inner.reflog(name)) {
+            Some(Ok(reflog)) => n >= reflog.len(),
+            _ => false,
+        }
+    }
+
+    /// Returns the tree of `commit` and its commit time, in seconds since
+    /// the UNIX epoch.
+    #[cfg(feature = "archive")]
+    fn archive_tree<C: ToCommit>(&self, commit: C) -> Result<(git2::Tree<'_>, i64), Error> {
+        let commit = commit
+            .to_commit(self)
+            .map_err(|err| Error::ToCommit(err.into()))?;
+        let commit = self.find_commit(commit.id)?;
+        Ok((commit.tree()?, commit.time().seconds()))
+    }
+
+    /// Converts `diff` into a [`Diff`], respecting the given `opts`.
+    fn to_diff(&self, diff: git2::Diff<'_>, opts: &diff::Options) -> Result<Diff, Error> {
+        Ok(Diff::from_git(diff, Some(&self.inner.odb()?), None, opts)?)
+    }
+
     fn diff_commits(
         &self,
         path: Option<&Path>,
@@ -504,17 +2483,46 @@ impl Repository {
     ) -> Result<git2::Diff, Error> {
         let new_tree = to.tree()?;
         let old_tree = from.map_or(Ok(None), |c| c.tree().map(Some))?;
+        self.diff_trees(path, old_tree.as_ref(), &new_tree)
+    }
 
+    /// Returns whether `to` changed the file at `path` in respect to
+    /// `from`.
+    ///
+    /// Unlike [`Self::diff_commits`], the binary check of the file is
+    /// skipped, since no patch is made from the diff.
+    fn changes_path(
+        &self,
+        path: &Path,
+        from: Option<&git2::Commit>,
+        to: &git2::Commit,
+    ) -> Result<bool, Error> {
+        let mut opts = git2::DiffOptions::new();
+        opts.pathspec(path.to_string_lossy().to_string());
+        opts.skip_binary_check(true);
+
+        let new_tree = to.tree()?;
+        let old_tree = from.map_or(Ok(None), |c| c.tree().map(Some))?;
+        let diff =
+            self.inner
+                .diff_tree_to_tree(old_tree.as_ref(), Some(&new_tree), Some(&mut opts))?;
+        Ok(diff.deltas().next().is_some())
+    }
+
+    fn diff_trees(
+        &self,
+        path: Option<&Path>,
+        old_tree: Option<&git2::Tree>,
+        new_tree: &git2::Tree,
+    ) -> Result<git2::Diff<'_>, Error> {
         let mut opts = git2::DiffOptions::new();
         if let Some(path) = path {
             opts.pathspec(path.to_string_lossy().to_string());
-            // We're skipping the binary pass because we won't be inspecting deltas.
-            opts.skip_binary_check(true);
         }
 
-        let mut diff =
-            self.inner
-                .diff_tree_to_tree(old_tree.as_ref(), Some(&new_tree), Some(&mut opts))?;
+        let mut diff = self
+            .inner
+            .diff_tree_to_tree(old_tree, Some(new_tree), Some(&mut opts))?;
 
         // Detect renames by default.
         let mut find_opts = git2::DiffFindOptions::new();
@@ -560,3 +2568,20 @@ impl std::fmt::Debug for Repository {
         write!(f, ".git")
     }
 }
+
+/// Tracks `lines` of the file at `path` in the commit `oid`, merging them
+/// with the lines already tracked there, e.g. when the commit is the
+/// parent of several commits that were visited.
+fn track_lines(
+    tracked: &mut HashMap<Oid, (PathBuf, RangeInclusive<u32>)>,
+    oid: Oid,
+    path: PathBuf,
+    lines: RangeInclusive<u32>,
+) {
+    tracked
+        .entry(oid)
+        .and_modify(|(_, tracked)| {
+            *tracked = *tracked.start().min(lines.start())..=*tracked.end().max(lines.end())
+        })
+        .or_insert((path, lines));
+}
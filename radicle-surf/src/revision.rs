@@ -34,6 +34,83 @@ impl From<git2::Buf> for Signature {
     }
 }
 
+impl Signature {
+    /// Returns the raw bytes of the signature.
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.0
+    }
+
+    /// Returns the [`SignatureFormat`] of the signature, detected from its
+    /// armor header, e.g. `-----BEGIN PGP SIGNATURE-----`.
+    pub fn format(&self) -> SignatureFormat {
+        let start = self
+            .0
+            .iter()
+            .position(|b| !b.is_ascii_whitespace())
+            .unwrap_or(self.0.len());
+        let sig = &self.0[start..];
+        let is = |headers: &[&str]| headers.iter().any(|h| sig.starts_with(h.as_bytes()));
+
+        if is(&[
+            "-----BEGIN PGP SIGNATURE-----",
+            "-----BEGIN PGP MESSAGE-----",
+        ]) {
+            SignatureFormat::Pgp
+        } else if is(&["-----BEGIN SSH SIGNATURE-----"]) {
+            SignatureFormat::Ssh
+        } else if is(&["-----BEGIN SIGNED MESSAGE-----"]) {
+            SignatureFormat::X509
+        } else {
+            SignatureFormat::Unknown
+        }
+    }
+}
+
+/// The format of a [`Signature`], following the formats supported by
+/// `git`'s `gpg.format` option.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SignatureFormat {
+    /// An OpenPGP signature.
+    Pgp,
+    /// An SSH signature, i.e. `SSHSIG`.
+    Ssh,
+    /// An X.509 signature, e.g. made by `gpgsm`.
+    X509,
+    /// The armor header was not recognised.
+    Unknown,
+}
+
+/// What an input was resolved as by [`Repository::resolve_any`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ResolvedRev {
+    /// A full object id.
+    Oid(Oid),
+    /// An abbreviated object id, expanded to the full object id.
+    ShortOid(Oid),
+    /// A local branch, along with the commit it points to.
+    Branch(Branch, Oid),
+    /// A light-weight or annotated tag.
+    Tag(Tag),
+    /// A fully qualified reference, along with its target.
+    Reference(Qualified<'static>, Oid),
+    /// A revision understood by `git rev-parse`, resolved to a commit.
+    Revspec(Oid),
+}
+
+impl ResolvedRev {
+    /// Returns the object id that the input resolved to.
+    ///
+    /// For annotated tags this is the id of the tag object, not of the
+    /// object it tags.
+    pub fn id(&self) -> Oid {
+        match self {
+            Self::Oid(oid) | Self::ShortOid(oid) | Self::Revspec(oid) => *oid,
+            Self::Branch(_, oid) | Self::Reference(_, oid) => *oid,
+            Self::Tag(tag) => tag.id(),
+        }
+    }
+}
+
 /// Supports various ways to specify a revision used in Git.
 pub trait Revision {
     type Error: std::error::Error + Send + Sync + 'static;
@@ -78,8 +155,8 @@ impl Revision for Branch {
     type Error = Error;
 
     fn object_id(&self, repo: &Repository) -> Result<Oid, Self::Error> {
-        let refname = repo.namespaced_refname(&self.refname())?;
-        Ok(repo.refname_to_id(&refname)?)
+        let (oid, _) = repo.resolve_reference(&self.refname())?;
+        Ok(oid)
     }
 }
 
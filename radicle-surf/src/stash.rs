@@ -0,0 +1,24 @@
+// Copyright © 2022 The Radicle Git Contributors
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use git_ext::Oid;
+
+#[cfg(feature = "serde")]
+use serde::Serialize;
+
+/// An entry of the stash of a repository, see
+/// [`crate::Repository::stashes`].
+///
+/// A stash entry is a commit, so its `id` can be browsed like any other
+/// revision, e.g. with [`crate::Repository::root_dir`].
+#[cfg_attr(feature = "serde", derive(Serialize), serde(rename_all = "camelCase"))]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct StashEntry {
+    /// The index of the entry, where `0` is the most recent one, as in
+    /// `stash@{0}`.
+    pub index: usize,
+    /// The message of the entry, e.g. `WIP on main: 1a2b3c4 Subject`.
+    pub message: String,
+    /// The id of the stash commit.
+    pub id: Oid,
+}
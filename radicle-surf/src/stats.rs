@@ -15,6 +15,8 @@
 // You should have received a copy of the GNU General Public License
 // along with this program. If not, see <https://www.gnu.org/licenses/>.
 
+use std::path::PathBuf;
+
 pub use radicle_git_ext::Oid;
 
 #[cfg(feature = "serde")]
@@ -30,4 +32,72 @@ pub struct Stats {
     pub branches: usize,
     /// Number of contributors
     pub contributors: usize,
+    /// Number of tags
+    pub tags: usize,
+    /// Number of namespaces
+    pub namespaces: usize,
+}
+
+/// Stats for the object database of a repository, see
+/// [`crate::Repository::odb_stats`].
+#[cfg_attr(feature = "serde", derive(Serialize), serde(rename_all = "camelCase"))]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct OdbStats {
+    /// Number of loose objects
+    pub loose_objects: usize,
+    /// Size, in bytes, of the loose objects on disk
+    pub loose_size: u64,
+    /// Number of packfiles
+    pub packs: usize,
+    /// Size, in bytes, of the packfiles and their indexes on disk
+    pub pack_size: u64,
+}
+
+impl OdbStats {
+    /// The total size, in bytes, of the object database on disk.
+    pub fn size(&self) -> u64 {
+        self.loose_size + self.pack_size
+    }
+}
+
+/// The churn of a range of commits, i.e. the lines they added and deleted,
+/// see [`crate::Repository::churn`].
+#[cfg_attr(feature = "serde", derive(Serialize), serde(rename_all = "camelCase"))]
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Churn {
+    /// Number of commits in the range
+    pub commits: usize,
+    /// Number of lines added by the commits
+    pub insertions: usize,
+    /// Number of lines deleted by the commits
+    pub deletions: usize,
+    /// The churn of each changed file, most churned first
+    pub files: Vec<FileChurn>,
+}
+
+/// The churn of a single file, see [`Churn`].
+#[cfg_attr(feature = "serde", derive(Serialize), serde(rename_all = "camelCase"))]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FileChurn {
+    /// The path of the file
+    pub path: PathBuf,
+    /// Number of lines added to the file
+    pub insertions: usize,
+    /// Number of lines deleted from the file
+    pub deletions: usize,
+}
+
+impl Churn {
+    /// The total number of lines changed, i.e. added or deleted.
+    pub fn total(&self) -> usize {
+        self.insertions + self.deletions
+    }
+}
+
+impl FileChurn {
+    /// The total number of lines of the file changed, i.e. added or
+    /// deleted.
+    pub fn total(&self) -> usize {
+        self.insertions + self.deletions
+    }
 }
@@ -0,0 +1,136 @@
+// Copyright © 2022 The Radicle Git Contributors
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! The submodules declared in a commit's [`.gitmodules`][gitmodules] file.
+//!
+//! `libgit2` only loads the `.gitmodules` file of a working directory, so
+//! this parses the subset of the `git config` format it is written in.
+//!
+//! [gitmodules]: https://git-scm.com/docs/gitmodules
+
+use std::{collections::BTreeMap, path::PathBuf};
+
+use git_ext::Oid;
+
+#[cfg(feature = "serde")]
+use serde::Serialize;
+
+/// The name of the file declaring the submodules of a repository.
+pub(crate) const GITMODULES: &str = ".gitmodules";
+
+/// A submodule declared in `.gitmodules`, see
+/// [`crate::Repository::submodules`].
+#[cfg_attr(feature = "serde", derive(Serialize), serde(rename_all = "camelCase"))]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Submodule {
+    /// The name of the submodule.
+    pub name: String,
+    /// The path of the submodule, relative to the repository root.
+    pub path: PathBuf,
+    /// The URL the submodule is cloned from.
+    pub url: String,
+    /// The branch of the submodule to track, if any.
+    pub branch: Option<String>,
+    /// The commit the submodule is pinned to in the tree, if it is
+    /// present at `path`.
+    pub oid: Option<Oid>,
+}
+
+/// Parses the submodules declared in the content of a `.gitmodules` file,
+/// in the order of their names.
+///
+/// Submodules without a `path` or `url` are skipped, as `git` does, and
+/// their `oid` is left unset.
+pub(crate) fn parse(content: &str) -> Vec<Submodule> {
+    #[derive(Default)]
+    struct Fields {
+        path: Option<String>,
+        url: Option<String>,
+        branch: Option<String>,
+    }
+
+    let mut submodules = BTreeMap::<String, Fields>::new();
+    let mut current = None;
+    for line in content.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') || line.starts_with(';') {
+            continue;
+        }
+        if let Some(section) = line.strip_prefix('[') {
+            current = section_name(section);
+            continue;
+        }
+        let name = match &current {
+            Some(name) => name,
+            None => continue,
+        };
+        let (key, value) = line.split_once('=').unwrap_or((line, ""));
+        let value = Some(unquote(value.trim()));
+        let fields = submodules.entry(name.clone()).or_default();
+        match key.trim().to_ascii_lowercase().as_str() {
+            "path" => fields.path = value,
+            "url" => fields.url = value,
+            "branch" => fields.branch = value,
+            _ => {},
+        }
+    }
+
+    submodules
+        .into_iter()
+        .filter_map(|(name, fields)| {
+            Some(Submodule {
+                name,
+                path: fields.path?.into(),
+                url: fields.url?,
+                branch: fields.branch,
+                oid: None,
+            })
+        })
+        .collect()
+}
+
+/// Returns the name of a `[submodule "<name>"]` section, given the line
+/// after its opening bracket.
+fn section_name(section: &str) -> Option<String> {
+    let (section, rest) = section.split_once(char::is_whitespace)?;
+    if !section.eq_ignore_ascii_case("submodule") {
+        return None;
+    }
+    let rest = rest.trim_start().strip_prefix('"')?;
+    let end = rest.rfind("\"]")?;
+    Some(unescape(&rest[..end]))
+}
+
+/// Strips the quotes and inline comment of a value.
+fn unquote(value: &str) -> String {
+    let mut unquoted = String::new();
+    let mut quoted = false;
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' => quoted = !quoted,
+            '\\' => match chars.next() {
+                Some('n') => unquoted.push('\n'),
+                Some('t') => unquoted.push('\t'),
+                Some(c) => unquoted.push(c),
+                None => {},
+            },
+            '#' | ';' if !quoted => break,
+            c => unquoted.push(c),
+        }
+    }
+    unquoted.trim_end().to_owned()
+}
+
+/// Unescapes the `\"` and `\\` sequences of a subsection name.
+fn unescape(name: &str) -> String {
+    let mut unescaped = String::new();
+    let mut chars = name.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => unescaped.extend(chars.next()),
+            c => unescaped.push(c),
+        }
+    }
+    unescaped
+}
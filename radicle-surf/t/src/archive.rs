@@ -0,0 +1,108 @@
+use std::{
+    io::{self, Cursor, Read},
+    path::Path,
+};
+
+use radicle_git_ext::{ref_format::refname, Oid};
+use radicle_surf::{archive::ArchiveFormat, Branch, Repository};
+use test_helpers::tempdir::WithTmpDir;
+
+use super::GIT_PLATINUM;
+
+#[test]
+fn archive_tar() {
+    let repo = Repository::open(GIT_PLATINUM).unwrap();
+    let bytes = repo
+        .archive(Branch::local(refname!("master")), ArchiveFormat::Tar)
+        .unwrap();
+
+    let mut archive = tar::Archive::new(bytes.as_slice());
+    let mut paths = vec![];
+    for entry in archive.entries().unwrap() {
+        let mut entry = entry.unwrap();
+        let path = entry.path().unwrap().to_string_lossy().into_owned();
+        let header = entry.header();
+        assert_eq!(header.mtime().unwrap(), 1602778504);
+        match path.as_str() {
+            "bin/ls" => assert_eq!(header.mode().unwrap(), 0o755),
+            "src/memory.rs" => {
+                assert_eq!(header.mode().unwrap(), 0o644);
+                let mut content = String::new();
+                entry.read_to_string(&mut content).unwrap();
+                assert!(content.starts_with("//! Provides [MemoryClient]"));
+            },
+            _ => {},
+        }
+        paths.push(path);
+    }
+    assert_eq!(paths.len(), 24);
+    assert!(paths.contains(&"this/is/a/really/deeply/nested/directory/tree/.gitkeep".to_string()));
+}
+
+#[test]
+fn archive_zip() {
+    let repo = Repository::open(GIT_PLATINUM).unwrap();
+    let bytes = repo
+        .archive(Branch::local(refname!("master")), ArchiveFormat::Zip)
+        .unwrap();
+
+    let mut archive = zip::ZipArchive::new(Cursor::new(bytes)).unwrap();
+    assert_eq!(archive.len(), 24);
+
+    let ls = archive.by_name("bin/ls").unwrap();
+    assert_eq!(ls.unix_mode().map(|mode| mode & 0o777), Some(0o755));
+    let modified = ls.last_modified();
+    assert_eq!(
+        (modified.year(), modified.month(), modified.day()),
+        (2020, 10, 15)
+    );
+    drop(ls);
+
+    let mut memory = archive.by_name("src/memory.rs").unwrap();
+    assert_eq!(memory.unix_mode().map(|mode| mode & 0o777), Some(0o644));
+    let mut content = String::new();
+    memory.read_to_string(&mut content).unwrap();
+    assert!(content.starts_with("//! Provides [MemoryClient]"));
+}
+
+#[test]
+fn archive_symlink() {
+    let tmp = WithTmpDir::new(|path| git2::Repository::init_bare(path).map_err(io::Error::other))
+        .unwrap();
+    let mut builder = tmp.treebuilder(None).unwrap();
+    builder
+        .insert("target.txt", tmp.blob(b"content").unwrap(), 0o100644)
+        .unwrap();
+    builder
+        .insert("link", tmp.blob(b"target.txt").unwrap(), 0o120000)
+        .unwrap();
+    let tree = tmp.find_tree(builder.write().unwrap()).unwrap();
+    let sig = git2::Signature::now("Linker", "linker@example.com").unwrap();
+    let oid = Oid::from(tmp.commit(None, &sig, &sig, "link", &tree, &[]).unwrap());
+    let repo = Repository::open(tmp.path()).unwrap();
+
+    // A `tar` archive can be written to a writer that can't seek.
+    let mut bytes = Vec::new();
+    repo.archive_tar_to(oid, &mut bytes).unwrap();
+    let mut archive = tar::Archive::new(bytes.as_slice());
+    let link = archive
+        .entries()
+        .unwrap()
+        .map(|entry| entry.unwrap())
+        .find(|entry| entry.path().unwrap().as_ref() == Path::new("link"))
+        .unwrap();
+    assert_eq!(link.header().entry_type(), tar::EntryType::Symlink);
+    assert_eq!(
+        link.link_name().unwrap().unwrap().as_ref(),
+        Path::new("target.txt")
+    );
+
+    let mut bytes = Cursor::new(Vec::new());
+    repo.archive_zip_to(oid, &mut bytes).unwrap();
+    let mut archive = zip::ZipArchive::new(bytes).unwrap();
+    let mut link = archive.by_name("link").unwrap();
+    assert_eq!(link.unix_mode().map(|mode| mode & 0o170000), Some(0o120000));
+    let mut target = String::new();
+    link.read_to_string(&mut target).unwrap();
+    assert_eq!(target, "target.txt");
+}
@@ -1,9 +1,11 @@
 use proptest::prelude::*;
-use radicle_git_ext::ref_format::{RefStr, RefString};
+use radicle_git_ext::ref_format::{name::component, refname, RefStr, RefString};
 use radicle_git_ext_test::git_ref_format::gen;
-use radicle_surf::Branch;
+use radicle_surf::{Branch, Glob, RemoteConfig, Repository};
 use test_helpers::roundtrip;
 
+use super::GIT_PLATINUM;
+
 proptest! {
     #[test]
     fn prop_test_branch(branch in gen_branch()) {
@@ -22,3 +24,69 @@ fn gen_branch() -> impl Strategy<Value = Branch> {
         })
     ]
 }
+
+#[test]
+fn branches_merged_into() {
+    let repo = Repository::open(GIT_PLATINUM).unwrap();
+    let all = || Glob::all_heads().branches().and(Glob::all_remotes());
+    let origin = component!("origin");
+    let banana = component!("banana");
+
+    let mut merged = repo
+        .branches_merged_into(Branch::local(refname!("master")), all())
+        .unwrap();
+    merged.sort();
+    assert_eq!(
+        merged,
+        vec![
+            Branch::local(refname!("master")),
+            Branch::remote(banana.clone(), refname!("orange/pineapple")),
+            Branch::remote(banana, refname!("pineapple")),
+            Branch::remote(origin.clone(), refname!("HEAD")),
+            Branch::remote(origin.clone(), refname!("master")),
+        ]
+    );
+
+    let mut not_merged = repo
+        .branches_not_merged_into(Branch::local(refname!("master")), all())
+        .unwrap();
+    not_merged.sort();
+    assert_eq!(
+        not_merged,
+        vec![
+            Branch::local(refname!("dev")),
+            Branch::local(refname!("diff-test")),
+            Branch::local(refname!("empty-branch")),
+            Branch::remote(origin.clone(), refname!("dev")),
+            Branch::remote(origin.clone(), refname!("diff-test")),
+            Branch::remote(origin, refname!("empty-branch")),
+        ]
+    );
+
+    let merged = repo
+        .branches_merged_into(Branch::local(refname!("dev")), Glob::all_heads())
+        .unwrap();
+    assert_eq!(merged, vec![Branch::local(refname!("dev"))]);
+}
+
+#[test]
+fn branch_remote() {
+    let repo = Repository::open(GIT_PLATINUM).unwrap();
+    let origin = RemoteConfig {
+        name: "origin".to_string(),
+        url: Some("https://github.com/radicle-dev/git-platinum.git".to_string()),
+        push_url: None,
+    };
+
+    let remote = Branch::remote(component!("origin"), refname!("dev"));
+    assert_eq!(repo.branch_remote(&remote).unwrap(), Some(origin.clone()));
+    // `master` tracks `origin/master`.
+    let local = Branch::local(refname!("master"));
+    assert_eq!(repo.branch_remote(&local).unwrap(), Some(origin));
+
+    // There are `banana` branches, but no `banana` remote is configured.
+    let banana = Branch::remote(component!("banana"), refname!("pineapple"));
+    assert_eq!(repo.branch_remote(&banana).unwrap(), None);
+    let untracked = Branch::local(refname!("does-not-exist"));
+    assert_eq!(repo.branch_remote(&untracked).unwrap(), None);
+}
@@ -0,0 +1,54 @@
+use std::path::Path;
+
+use radicle_git_ext::ref_format::refname;
+use radicle_surf::{Branch, CachedRepository, Repository};
+
+use super::GIT_PLATINUM;
+
+#[test]
+fn cached_queries() {
+    let repo = CachedRepository::new(Repository::open(GIT_PLATINUM).unwrap(), 1);
+    let master = Branch::local(refname!("master"));
+    let dev = Branch::local(refname!("dev"));
+
+    let src = repo.directory(&master, &Path::new("src")).unwrap();
+    assert_eq!(src, repo.directory(&master, &Path::new("src")).unwrap());
+    assert_eq!(src, (*repo).directory(&master, &Path::new("src")).unwrap());
+
+    let diff = repo.diff(&master, &dev).unwrap();
+    assert_eq!(diff, repo.diff(&master, &dev).unwrap());
+    assert_eq!(diff, (*repo).diff(&master, &dev).unwrap());
+
+    let last = repo.last_commit(&Path::new("README.md"), &dev).unwrap();
+    assert_eq!(
+        last,
+        repo.last_commit(&Path::new("README.md"), &dev).unwrap()
+    );
+
+    // Evicts the `master` entry, as the capacity is 1.
+    let dev_src = repo.directory(&dev, &Path::new("src")).unwrap();
+    assert_eq!(dev_src, repo.directory(&dev, &Path::new("src")).unwrap());
+    assert_eq!(src, repo.directory(&master, &Path::new("src")).unwrap());
+
+    repo.clear();
+    assert_eq!(diff, repo.diff(&master, &dev).unwrap());
+}
+
+#[test]
+fn cached_merge_bases() {
+    let repo = CachedRepository::new(Repository::open(GIT_PLATINUM).unwrap(), 2);
+    let master = Branch::local(refname!("master"));
+    let dev = Branch::local(refname!("dev"));
+
+    let merge_base = repo.merge_base(&master, &dev).unwrap();
+    assert_eq!(
+        merge_base.map(|oid| oid.to_string()).as_deref(),
+        Some("1820cb07c1a890016ca5578aa652fd4d4c38967e")
+    );
+    assert_eq!(merge_base, (*repo).merge_base(&master, &dev).unwrap());
+    assert_eq!(merge_base, repo.merge_base(&dev, &master).unwrap());
+
+    let diff = repo.triple_dot_diff(&master, &dev).unwrap();
+    assert_eq!(diff, (*repo).triple_dot_diff(&master, &dev).unwrap());
+    assert_eq!(diff, repo.diff(merge_base.unwrap(), &dev).unwrap());
+}
@@ -0,0 +1,50 @@
+use std::io;
+
+use radicle_surf::{CherryStatus, Repository};
+use test_helpers::tempdir::WithTmpDir;
+
+/// Commits `content` to `path` on top of `parent`, returning the new commit.
+fn commit(
+    repo: &git2::Repository,
+    parent: Option<git2::Oid>,
+    path: &str,
+    content: &str,
+    message: &str,
+) -> git2::Oid {
+    let sig = git2::Signature::now("Cherry Picker", "cherry@picker.com").unwrap();
+    let parent = parent.map(|oid| repo.find_commit(oid).unwrap());
+    let base = parent.as_ref().map(|commit| commit.tree().unwrap());
+    let mut builder = repo.treebuilder(base.as_ref()).unwrap();
+    let blob = repo.blob(content.as_bytes()).unwrap();
+    builder.insert(path, blob, 0o100644).unwrap();
+    let tree = repo.find_tree(builder.write().unwrap()).unwrap();
+    let parents = parent.iter().collect::<Vec<_>>();
+    repo.commit(None, &sig, &sig, message, &tree, &parents)
+        .unwrap()
+}
+
+#[test]
+fn cherry() {
+    let tmp = WithTmpDir::new(|path| git2::Repository::init_bare(path).map_err(io::Error::other))
+        .unwrap();
+    let base = commit(&tmp, None, "README", "base", "Initial commit");
+
+    // `upstream` has a cherry-picked version of `picked`.
+    let upstream = commit(&tmp, Some(base), "a.txt", "a", "Add a (cherry-picked)");
+    let upstream = commit(&tmp, Some(upstream), "c.txt", "c", "Add c");
+
+    let picked = commit(&tmp, Some(base), "a.txt", "a", "Add a");
+    let unique = commit(&tmp, Some(picked), "b.txt", "b", "Add b");
+
+    let repo = Repository::open(tmp.path()).unwrap();
+    let cherry = repo
+        .cherry(upstream.to_string(), unique.to_string())
+        .unwrap();
+    assert_eq!(
+        cherry,
+        vec![
+            (picked.into(), CherryStatus::Equivalent),
+            (unique.into(), CherryStatus::Unique),
+        ]
+    );
+}
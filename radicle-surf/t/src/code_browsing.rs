@@ -1,11 +1,23 @@
-use std::path::Path;
+use std::{
+    io::{self, Write as _},
+    path::Path,
+};
 
 use radicle_git_ext::ref_format::refname;
 use radicle_surf::{
+    diff,
     fs::{self, Directory},
+    repo,
     Branch,
+    Churn,
+    Error,
+    FileChurn,
+    HeadRef,
+    OdbStats,
     Repository,
+    StashEntry,
 };
+use test_helpers::tempdir::WithTmpDir;
 
 use super::GIT_PLATINUM;
 
@@ -66,6 +78,261 @@ fn test_file_history() {
     assert_eq!(file.size(), 67);
 }
 
+#[test]
+fn test_file_history_follow_renames() {
+    let repo = Repository::open(GIT_PLATINUM).unwrap();
+    let branch = Branch::local(refname!("diff-test"));
+    // `emoji.txt` was moved from `text/emoji.txt` on `diff-test`.
+    let path = Path::new("emoji.txt");
+
+    let history = repo.history(&branch).unwrap().by_path(&path);
+    let commits = history
+        .map(|c| c.unwrap().id.to_string())
+        .collect::<Vec<_>>();
+    assert_eq!(commits, vec!["f4ee21b82639f78387aa81510cddab7bcf80554d"]);
+
+    let history = repo
+        .history(&branch)
+        .unwrap()
+        .by_path(&path)
+        .follow_renames();
+    let commits = history
+        .map(|c| c.unwrap().id.to_string())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        commits,
+        vec![
+            "f4ee21b82639f78387aa81510cddab7bcf80554d",
+            "1e0206da8571ca71c51c91154e2fee376e09b4e7"
+        ]
+    );
+}
+
+#[test]
+fn test_file_evolution() {
+    let repo = Repository::open(GIT_PLATINUM).unwrap();
+    let branch = Branch::local(refname!("diff-test"));
+    let evolution = repo
+        .file_evolution(&branch, &Path::new("emoji.txt"))
+        .unwrap();
+
+    let revisions = evolution
+        .iter()
+        .map(|revision| {
+            (
+                revision.commit.id.to_string(),
+                revision.path.as_path(),
+                revision.old_path.as_deref(),
+            )
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(
+        revisions,
+        vec![
+            (
+                "f4ee21b82639f78387aa81510cddab7bcf80554d".to_string(),
+                Path::new("emoji.txt"),
+                Some(Path::new("text/emoji.txt")),
+            ),
+            (
+                "1e0206da8571ca71c51c91154e2fee376e09b4e7".to_string(),
+                Path::new("text/emoji.txt"),
+                None,
+            ),
+        ]
+    );
+    // The file was added by the last commit.
+    assert!(matches!(
+        &evolution[1].diff,
+        diff::DiffContent::Plain { hunks, .. } if hunks.iter().count() == 1
+    ));
+}
+
+#[test]
+fn test_file_history_combined_merges() {
+    let repo = Repository::open(GIT_PLATINUM).unwrap();
+    let master = Branch::local(refname!("master"));
+    let path = Path::new("README.md");
+    let ids = |history: radicle_surf::History| {
+        history
+            .map(|c| c.unwrap().id.to_string())
+            .collect::<Vec<_>>()
+    };
+
+    // The merge brings in the change made to `README.md` on its second
+    // parent, so it differs from its first parent only.
+    let history = repo.history(&master).unwrap().by_path(&path);
+    assert_eq!(
+        ids(history),
+        vec![
+            "223aaf87d6ea62eef0014857640fd7c8dd0f80b5",
+            "80bacafba303bf0cdf6142921f430ff265f25095",
+            "d3464e33d75c75c99bfb90fa2e9d16efc0b7d0e3",
+        ]
+    );
+
+    let history = repo
+        .history(&master)
+        .unwrap()
+        .by_path(&path)
+        .combined_merges();
+    assert_eq!(
+        ids(history),
+        vec![
+            "80bacafba303bf0cdf6142921f430ff265f25095",
+            "d3464e33d75c75c99bfb90fa2e9d16efc0b7d0e3",
+        ]
+    );
+}
+
+#[test]
+fn test_history_with_diffs() {
+    let repo = Repository::open(GIT_PLATINUM).unwrap();
+    let dev = Branch::local(refname!("dev"));
+    let pairs = repo
+        .history(&dev)
+        .unwrap()
+        .with_diffs()
+        .take(2)
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+    assert_eq!(pairs.len(), 2);
+    for (commit, diff) in pairs {
+        assert_eq!(diff, repo.diff_commit(commit.id).unwrap());
+    }
+
+    // The root commit is diffed against the empty tree.
+    let (root, diff) = repo
+        .history(&dev)
+        .unwrap()
+        .with_diffs()
+        .last()
+        .unwrap()
+        .unwrap();
+    assert!(root.parents.is_empty());
+    assert_eq!(diff.added().count(), diff.files().count());
+}
+
+#[test]
+fn test_line_history() {
+    let tmp = WithTmpDir::new(|path| git2::Repository::init_bare(path).map_err(io::Error::other))
+        .unwrap();
+    let sig = git2::Signature::now("Lines", "lines@example.com").unwrap();
+    let mut lines = (1..=10).map(|i| format!("l{i}")).collect::<Vec<_>>();
+    let mut commits = Vec::<git2::Oid>::new();
+    let mut commit = |name: &str, lines: &[String]| {
+        let mut builder = tmp.treebuilder(None).unwrap();
+        let content = lines
+            .iter()
+            .map(|line| format!("{line}\n"))
+            .collect::<String>();
+        let blob = tmp.blob(content.as_bytes()).unwrap();
+        builder.insert(name, blob, 0o100644).unwrap();
+        let tree = tmp.find_tree(builder.write().unwrap()).unwrap();
+        let parents = commits
+            .last()
+            .map(|oid| tmp.find_commit(*oid).unwrap())
+            .into_iter()
+            .collect::<Vec<_>>();
+        let parents = parents.iter().collect::<Vec<_>>();
+        let oid = tmp
+            .commit(None, &sig, &sig, "lines", &tree, &parents)
+            .unwrap();
+        commits.push(oid);
+        oid.to_string()
+    };
+
+    let c1 = commit("file.txt", &lines);
+    // Shifts the tracked lines down.
+    lines.splice(0..0, ["new1".to_string(), "new2".to_string()]);
+    commit("file.txt", &lines);
+    lines[7] = "L6".to_string();
+    let c3 = commit("file.txt", &lines);
+    lines[2] = "L1".to_string();
+    commit("file.txt", &lines);
+    commit("code.txt", &lines);
+    lines[8] = "L7".to_string();
+    let c6 = commit("code.txt", &lines);
+
+    // The lines `l5` to `l7` in the last commit, as `git log -L
+    // 7,9:code.txt` reports them.
+    let repo = Repository::open(tmp.path()).unwrap();
+    let head = commits
+        .last()
+        .copied()
+        .map(radicle_git_ext::Oid::from)
+        .unwrap();
+    let history = repo
+        .line_history(head, &Path::new("code.txt"), 7, 9)
+        .unwrap();
+    let revisions = history
+        .iter()
+        .map(|revision| (revision.commit.id.to_string(), revision.path.as_path()))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        revisions,
+        vec![
+            (c6, Path::new("code.txt")),
+            (c3, Path::new("file.txt")),
+            (c1, Path::new("file.txt")),
+        ]
+    );
+    // Only the hunks touching the lines are kept.
+    for revision in &history {
+        match &revision.diff {
+            diff::DiffContent::Plain { hunks, .. } => assert_eq!(hunks.iter().count(), 1),
+            content => panic!("unexpected content: {content:?}"),
+        }
+    }
+
+    let err = repo
+        .line_history(head, &Path::new("code.txt"), 0, 9)
+        .unwrap_err();
+    assert_eq!(err.to_string(), "invalid line range: 0,9");
+
+    // The range is clamped to the file, and follows the lines that a
+    // commit rewrote into the lines they replaced.
+    let repo = Repository::open(GIT_PLATINUM).unwrap();
+    let history = repo
+        .line_history(
+            Branch::local(refname!("master")),
+            &Path::new("README.md"),
+            1,
+            3,
+        )
+        .unwrap();
+    assert_eq!(
+        history
+            .iter()
+            .map(|revision| revision.commit.id.to_string())
+            .collect::<Vec<_>>(),
+        vec![
+            "80bacafba303bf0cdf6142921f430ff265f25095",
+            "d3464e33d75c75c99bfb90fa2e9d16efc0b7d0e3",
+        ]
+    );
+}
+
+#[test]
+fn test_sequential_diffs() {
+    let repo = Repository::open(GIT_PLATINUM).unwrap();
+    let master = Branch::local(refname!("master"));
+    let expected = repo
+        .history(&master)
+        .unwrap()
+        .with_diffs()
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+    assert_eq!(
+        repo.sequential_diffs(&master, usize::MAX).unwrap(),
+        expected
+    );
+
+    let limited = repo.sequential_diffs(&master, 3).unwrap();
+    assert_eq!(limited, expected[..3]);
+    assert!(repo.sequential_diffs(&master, 0).unwrap().is_empty());
+}
+
 #[test]
 fn test_commit_history() {
     let repo = Repository::open(GIT_PLATINUM).unwrap();
@@ -98,3 +365,343 @@ fn test_commit_signature() {
     let signature = repo.extract_signature(commit_nonexist, None);
     assert!(signature.is_err());
 }
+
+#[test]
+fn test_history_with_signatures() {
+    let repo = Repository::open(GIT_PLATINUM).unwrap();
+    let master = Branch::local(refname!("master"));
+    let history = repo.history_with_signatures(&master).unwrap();
+    assert_eq!(history.len(), repo.history(&master).unwrap().count());
+    for (commit, signature) in &history {
+        assert_eq!(signature, &repo.extract_signature(commit.id, None).unwrap());
+    }
+
+    let signature_of = |oid: &str| {
+        history
+            .iter()
+            .find(|(commit, _)| commit.id.to_string() == oid)
+            .map(|(_, signature)| signature.is_some())
+    };
+    assert_eq!(
+        signature_of("e24124b7538658220b5aaf3b6ef53758f0a106dc"),
+        Some(true)
+    );
+    assert_eq!(
+        signature_of("80bacafba303bf0cdf6142921f430ff265f25095"),
+        Some(false)
+    );
+}
+
+#[test]
+fn test_file_prefix() {
+    let repo = Repository::open(GIT_PLATINUM).unwrap();
+    let master = Branch::local(refname!("master"));
+
+    let (prefix, truncated) = repo.file_prefix(&master, &"src/memory.rs", 10).unwrap();
+    assert_eq!(prefix, b"//! Provid");
+    assert!(truncated);
+
+    let (content, truncated) = repo.file_prefix(&master, &"src/memory.rs", 10_000).unwrap();
+    assert_eq!(content.len(), 6253);
+    assert!(!truncated);
+
+    // Loose objects are streamed.
+    let tmp = WithTmpDir::new(|path| git2::Repository::init_bare(path).map_err(io::Error::other))
+        .unwrap();
+    let mut builder = tmp.treebuilder(None).unwrap();
+    builder
+        .insert("hello.txt", tmp.blob(b"hello world").unwrap(), 0o100644)
+        .unwrap();
+    let tree = tmp.find_tree(builder.write().unwrap()).unwrap();
+    let sig = git2::Signature::now("Loose", "loose@example.com").unwrap();
+    let oid =
+        radicle_git_ext::Oid::from(tmp.commit(None, &sig, &sig, "loose", &tree, &[]).unwrap());
+    let repo = Repository::open(tmp.path()).unwrap();
+    let (prefix, truncated) = repo.file_prefix(oid, &"hello.txt", 5).unwrap();
+    assert_eq!(prefix, b"hello");
+    assert!(truncated);
+}
+
+#[test]
+fn test_repository_paths() {
+    let repo = Repository::open(GIT_PLATINUM).unwrap();
+    assert!(!repo.is_bare());
+    assert!(repo.path().ends_with("git-platinum/.git"));
+    assert!(repo.workdir().unwrap().ends_with("git-platinum"));
+
+    let bare = WithTmpDir::new(|path| {
+        git2::Repository::init_bare(path)
+            .map(Repository::from)
+            .map_err(io::Error::other)
+    })
+    .unwrap();
+    assert!(bare.is_bare());
+    assert!(bare.workdir().is_none());
+}
+
+#[test]
+fn test_add_alternate() {
+    let init = || {
+        WithTmpDir::new(|path| git2::Repository::init_bare(path).map_err(io::Error::other)).unwrap()
+    };
+    let objects = init();
+    let sig = git2::Signature::now("Alternate", "alternate@example.com").unwrap();
+    let tree = objects
+        .find_tree(objects.treebuilder(None).unwrap().write().unwrap())
+        .unwrap();
+    let oid = objects
+        .commit(None, &sig, &sig, "Stored elsewhere", &tree, &[])
+        .unwrap()
+        .to_string();
+
+    let tmp = init();
+    let repo = Repository::open(tmp.path()).unwrap();
+    assert!(repo.commit(oid.as_str()).is_err());
+
+    repo.add_alternate(objects.path().join("objects")).unwrap();
+    let commit = repo.commit(oid.as_str()).unwrap();
+    assert_eq!(commit.summary, "Stored elsewhere");
+}
+
+#[test]
+fn test_empty_repository() {
+    let repo = Repository::open(GIT_PLATINUM).unwrap();
+    assert!(!repo.is_empty().unwrap());
+
+    let tmp = WithTmpDir::new(|path| git2::Repository::init_bare(path).map_err(io::Error::other))
+        .unwrap();
+    let repo = Repository::open(tmp.path()).unwrap();
+    assert!(repo.is_empty().unwrap());
+    let err = repo.head().unwrap_err();
+    assert!(
+        matches!(err, Error::Repo(repo::error::Repo::EmptyRepository)),
+        "{err:?}"
+    );
+    assert!(repo.stats().is_err());
+}
+
+#[test]
+fn test_stats() {
+    let repo = Repository::open(GIT_PLATINUM).unwrap();
+    let stats = repo.stats().unwrap();
+    assert_eq!(stats.branches, 4);
+    assert_eq!(stats.tags, 6);
+    // `golden`, `golden/silver` and `me`.
+    assert_eq!(stats.namespaces, 3);
+}
+
+#[test]
+fn test_config_get() {
+    let tmp = WithTmpDir::new(|path| git2::Repository::init_bare(path).map_err(io::Error::other))
+        .unwrap();
+    let mut config = tmp.config().unwrap();
+    config.set_str("radicle.project", "heartwood").unwrap();
+    config.set_bool("radicle.enabled", true).unwrap();
+
+    let repo = Repository::open(tmp.path()).unwrap();
+    assert_eq!(
+        repo.config_get("radicle.project").unwrap().as_deref(),
+        Some("heartwood")
+    );
+    assert_eq!(
+        repo.config_get("radicle.enabled").unwrap().as_deref(),
+        Some("true")
+    );
+    assert_eq!(repo.config_get("radicle.missing").unwrap(), None);
+}
+
+#[test]
+fn test_odb_stats() {
+    let tmp = WithTmpDir::new(|path| git2::Repository::init_bare(path).map_err(io::Error::other))
+        .unwrap();
+    let repo = Repository::open(tmp.path()).unwrap();
+    assert_eq!(repo.odb_stats().unwrap(), OdbStats::default());
+
+    let loose = tmp.blob(b"loose").unwrap();
+    let stats = repo.odb_stats().unwrap();
+    assert_eq!(stats.loose_objects, 1);
+    assert!(stats.loose_size > 0);
+    assert_eq!(stats.packs, 0);
+
+    let mut pack = git2::Buf::new();
+    let mut builder = tmp.packbuilder().unwrap();
+    builder.insert_object(loose, None).unwrap();
+    builder.write_buf(&mut pack).unwrap();
+    let odb = tmp.odb().unwrap();
+    let mut writer = odb.packwriter().unwrap();
+    writer.write_all(&pack).unwrap();
+    writer.commit().unwrap();
+    let stats = repo.odb_stats().unwrap();
+    assert_eq!(stats.loose_objects, 1);
+    assert_eq!(stats.packs, 1);
+    assert!(stats.pack_size > 0);
+    assert_eq!(stats.size(), stats.loose_size + stats.pack_size);
+}
+
+#[test]
+fn test_head_ref() {
+    let repo = Repository::open(GIT_PLATINUM).unwrap();
+    assert_eq!(
+        repo.head_ref().unwrap(),
+        HeadRef::Branch(Branch::local(refname!("dev")))
+    );
+
+    let tmp = WithTmpDir::new(|path| git2::Repository::init_bare(path).map_err(io::Error::other))
+        .unwrap();
+    tmp.set_head("refs/heads/main").unwrap();
+    let repo = Repository::open(tmp.path()).unwrap();
+
+    // HEAD of an empty repository is still attached to a branch.
+    assert_eq!(
+        repo.head_ref().unwrap(),
+        HeadRef::Branch(Branch::local(refname!("main")))
+    );
+
+    let sig = git2::Signature::now("Head Hunter", "head@hunter.com").unwrap();
+    let tree = tmp
+        .find_tree(tmp.treebuilder(None).unwrap().write().unwrap())
+        .unwrap();
+    let oid = tmp.commit(None, &sig, &sig, "initial", &tree, &[]).unwrap();
+    tmp.set_head_detached(oid).unwrap();
+    assert_eq!(repo.head_ref().unwrap(), HeadRef::Detached(oid.into()));
+}
+
+#[test]
+fn test_file_size() {
+    let repo = Repository::open(GIT_PLATINUM).unwrap();
+    let master = Branch::local(refname!("master"));
+
+    let size = repo.file_size(&master, &"src/memory.rs").unwrap();
+    assert_eq!(size, Some(6253));
+
+    let missing = repo.file_size(&master, &"src/missing.rs").unwrap();
+    assert_eq!(missing, None);
+
+    let file = repo.file(&master, &"src/memory.rs").unwrap();
+    assert_eq!(repo.blob_size(file.id()).unwrap(), 6253);
+
+    // A tree is not a blob.
+    let dir = repo.directory(&master, &"src").unwrap();
+    assert!(repo.blob_size(dir.id()).is_err());
+}
+
+#[test]
+fn stashes() {
+    let mut tmp =
+        WithTmpDir::new(|path| git2::Repository::init(path).map_err(io::Error::other)).unwrap();
+    let workdir = tmp.workdir().unwrap().to_path_buf();
+    let sig = git2::Signature::now("Stasher", "stasher@example.com").unwrap();
+
+    let repo = Repository::open(tmp.path()).unwrap();
+    assert!(repo.stashes().unwrap().is_empty());
+
+    std::fs::write(workdir.join("file.txt"), "committed\n").unwrap();
+    let mut index = tmp.index().unwrap();
+    index.add_path(Path::new("file.txt")).unwrap();
+    index.write().unwrap();
+    let tree = tmp.find_tree(index.write_tree().unwrap()).unwrap();
+    tmp.set_head("refs/heads/main").unwrap();
+    tmp.commit(Some("HEAD"), &sig, &sig, "Initial commit", &tree, &[])
+        .unwrap();
+    drop(tree);
+
+    let mut stash = |content: &str, message: &str| {
+        std::fs::write(workdir.join("file.txt"), content).unwrap();
+        tmp.stash_save(&sig, message, None).unwrap()
+    };
+    let first = stash("first\n", "first");
+    let second = stash("second\n", "second");
+
+    let stashes = repo.stashes().unwrap();
+    assert_eq!(
+        stashes,
+        vec![
+            StashEntry {
+                index: 0,
+                message: "On main: second".to_string(),
+                id: second.into(),
+            },
+            StashEntry {
+                index: 1,
+                message: "On main: first".to_string(),
+                id: first.into(),
+            },
+        ]
+    );
+
+    // The stash commits can be browsed like any other commit.
+    let file = repo.blob(stashes[1].id, &"file.txt").unwrap();
+    assert_eq!(file.content(), b"first\n");
+}
+
+#[test]
+fn churn() {
+    let repo = Repository::open(GIT_PLATINUM).unwrap();
+    let from = repo.resolve("dev~3").unwrap();
+    let to = repo.resolve("dev").unwrap();
+    let file = |path: &str, insertions| FileChurn {
+        path: path.into(),
+        insertions,
+        deletions: 0,
+    };
+
+    let churn = repo.churn(from, to).unwrap();
+    assert_eq!(
+        churn,
+        Churn {
+            commits: 3,
+            insertions: 23,
+            deletions: 0,
+            files: vec![
+                file("text/emoji.txt", 8),
+                file("text/garden.txt", 8),
+                file("text/arrows.txt", 7),
+                file(".i-am-well-hidden", 0),
+                file(".i-too-am-hidden", 0),
+                file("here-we-are-on-a-dev-branch.lol", 0),
+            ],
+        }
+    );
+    assert_eq!(churn.total(), 23);
+
+    assert_eq!(repo.churn(to, to).unwrap(), Churn::default());
+}
+
+#[test]
+fn filter_files() {
+    let repo = Repository::open(GIT_PLATINUM).unwrap();
+    let master = Branch::local(refname!("master"));
+
+    let binaries = repo
+        .filter_files(&master, |_, content| content.contains(&0))
+        .unwrap();
+    assert_eq!(
+        binaries,
+        vec![
+            Path::new("bin/cat"),
+            Path::new("bin/ls"),
+            Path::new("bin/test")
+        ]
+    );
+
+    let rust = repo
+        .filter_files(&master, |path, content| {
+            path.extension() == Some("rs".as_ref()) && content.starts_with(b"//!")
+        })
+        .unwrap();
+    assert_eq!(rust, vec![Path::new("src/memory.rs")]);
+}
+
+#[test]
+fn path_exists() {
+    let repo = Repository::open(GIT_PLATINUM).unwrap();
+    let master = Branch::local(refname!("master"));
+    assert!(repo.path_exists(&master, &"").unwrap());
+    assert!(repo.path_exists(&master, &"src").unwrap());
+    assert!(repo.path_exists(&master, &"src/memory.rs").unwrap());
+    assert!(repo
+        .path_exists(&master, &"this/is/a/really/deeply/nested/directory/tree")
+        .unwrap());
+    assert!(!repo.path_exists(&master, &"src/missing.rs").unwrap());
+    assert!(!repo.path_exists(&master, &"src/memory.rs/nested").unwrap());
+}
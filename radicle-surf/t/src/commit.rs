@@ -1,9 +1,22 @@
-use std::str::FromStr;
+use std::{io, str::FromStr};
 
 use proptest::prelude::*;
-use radicle_git_ext::Oid;
-use radicle_surf::{Author, Commit, Time};
-use test_helpers::roundtrip;
+use radicle_git_ext::{ref_format::refname, Oid};
+use radicle_surf::{
+    Author,
+    AuthorRole,
+    Branch,
+    Commit,
+    MessageReference,
+    PatternError,
+    ReferencePattern,
+    Repository,
+    SignatureFormat,
+    Time,
+};
+use test_helpers::{roundtrip, tempdir::WithTmpDir};
+
+use super::GIT_PLATINUM;
 
 proptest! {
     #[test]
@@ -30,3 +43,279 @@ fn commits_strategy() -> impl Strategy<Value = Commit> {
         parents: vec![Oid::from_str(&id).unwrap(), Oid::from_str(&id).unwrap()],
     })
 }
+
+#[test]
+fn test_commits_since() {
+    let repo = Repository::open(GIT_PLATINUM).unwrap();
+    let commits = repo
+        .commits_since(Branch::local(refname!("master")), Time::new(1584362600, 0))
+        .unwrap();
+    let ids = commits.iter().map(|c| c.id.to_string()).collect::<Vec<_>>();
+    assert_eq!(
+        ids,
+        vec![
+            "a0dd9122d33dff2a35f564d564db127152c88e02",
+            "223aaf87d6ea62eef0014857640fd7c8dd0f80b5",
+            "80bacafba303bf0cdf6142921f430ff265f25095",
+        ]
+    );
+}
+
+#[test]
+fn test_commits_since_clock_skew() {
+    let tmp = WithTmpDir::new(|path| git2::Repository::init_bare(path).map_err(io::Error::other))
+        .unwrap();
+    let tree = tmp
+        .find_tree(tmp.treebuilder(None).unwrap().write().unwrap())
+        .unwrap();
+    let mut parent = None;
+    let mut ids = vec![];
+    // The middle commit was made with a clock that was behind.
+    for seconds in [100, 50, 200] {
+        let sig = git2::Signature::new("Skewed", "skewed@clock.com", &git2::Time::new(seconds, 0))
+            .unwrap();
+        let parents = parent.iter().collect::<Vec<_>>();
+        let oid = tmp
+            .commit(None, &sig, &sig, "tick", &tree, &parents)
+            .unwrap();
+        ids.push(Oid::from(oid));
+        parent = Some(tmp.find_commit(oid).unwrap());
+    }
+
+    let repo = Repository::open(tmp.path()).unwrap();
+    let commits = repo.commits_since(ids[2], Time::new(80, 0)).unwrap();
+    let found = commits.iter().map(|c| c.id).collect::<Vec<_>>();
+    assert_eq!(found, vec![ids[2], ids[0]]);
+}
+
+#[test]
+fn test_short_id() {
+    let repo = Repository::open(GIT_PLATINUM).unwrap();
+    let master = repo.commit(Branch::local(refname!("master"))).unwrap();
+    assert_eq!(master.short_id(&repo).unwrap(), "a0dd912");
+    assert_eq!(repo.short_id(master.id).unwrap(), "a0dd912");
+
+    let missing = Oid::from_str("0000000000000000000000000000000000000000").unwrap();
+    assert!(repo.short_id(missing).is_err());
+}
+
+#[test]
+fn test_commit_references() {
+    let repo = Repository::open(GIT_PLATINUM).unwrap();
+    let mut commit = repo.commit(Branch::local(refname!("master"))).unwrap();
+    commit.message = "Fix #12 and rad:z3gqc#4\n\nSee issue#5 and #7.\n".to_owned();
+
+    let reference = |pattern: &str, text: &str, id: &str, start: usize| MessageReference {
+        pattern: pattern.to_owned(),
+        text: text.to_owned(),
+        id: id.to_owned(),
+        range: start..start + text.len(),
+    };
+    assert_eq!(
+        commit.references(),
+        vec![
+            reference("issue", "#12", "12", 4),
+            reference("issue", "#7", "7", 41),
+        ]
+    );
+    assert_eq!(&commit.message[4..7], "#12");
+
+    let urn = ReferencePattern::new("urn", r"rad:[[:alnum:]]+(?:#[0-9]+)?").unwrap();
+    assert_eq!(
+        commit.references_with(&[ReferencePattern::issue(), urn]),
+        vec![
+            reference("issue", "#12", "12", 4),
+            reference("urn", "rad:z3gqc#4", "rad:z3gqc#4", 12),
+            reference("issue", "#7", "7", 41),
+        ]
+    );
+    assert!(matches!(
+        ReferencePattern::new("invalid", "("),
+        Err(PatternError::Regex(_))
+    ));
+}
+
+#[test]
+fn test_commits() {
+    let repo = Repository::open(GIT_PLATINUM).unwrap();
+    let master = Oid::from_str("a0dd9122d33dff2a35f564d564db127152c88e02").unwrap();
+    let dev = Oid::from_str("27acd68c7504755aa11023300890bb85bbd69d45").unwrap();
+    let commits = repo.commits(&[dev, master]).unwrap();
+    assert_eq!(
+        commits.iter().map(|commit| commit.id).collect::<Vec<_>>(),
+        vec![dev, master]
+    );
+    assert!(repo.commits(&[]).unwrap().is_empty());
+
+    let missing = Oid::from_str("0000000000000000000000000000000000000000").unwrap();
+    assert!(repo.commits(&[master, missing]).is_err());
+}
+
+#[test]
+fn test_commits_by() {
+    let repo = Repository::open(GIT_PLATINUM).unwrap();
+    let master = Branch::local(refname!("master"));
+    let count = |email, role| repo.commits_by(&master, email, role).unwrap().len();
+
+    let fintan = "fintan.halpenny@gmail.com";
+    assert_eq!(count(fintan, AuthorRole::Author), 2);
+    assert_eq!(count(fintan, AuthorRole::Committer), 1);
+    assert_eq!(count(fintan, AuthorRole::Either), 2);
+
+    // Changes merged on GitHub are committed by GitHub.
+    let github = "noreply@github.com";
+    assert_eq!(count(github, AuthorRole::Author), 0);
+    assert_eq!(count(github, AuthorRole::Committer), 5);
+
+    let commits = repo
+        .commits_by(&master, fintan, AuthorRole::Committer)
+        .unwrap();
+    assert!(commits
+        .iter()
+        .all(|commit| commit.committer.email == fintan));
+}
+
+#[test]
+fn test_commit_times() {
+    let repo = Repository::open(GIT_PLATINUM).unwrap();
+
+    // Authored and committed at different times.
+    let commit = repo
+        .commit("80bacafba303bf0cdf6142921f430ff265f25095")
+        .unwrap();
+    assert_eq!(commit.author_time().seconds(), 1584362521);
+    assert_eq!(commit.commit_time().seconds(), 1584362684);
+
+    // Committed with a timezone offset of -0800.
+    let commit = repo
+        .commit("f4ee21b82639f78387aa81510cddab7bcf80554d")
+        .unwrap();
+    let time = commit.commit_time();
+    assert_eq!(time.offset_minutes(), -480);
+    assert_eq!(time.to_utc().offset_minutes(), 0);
+    assert_eq!(time.to_utc().seconds(), time.seconds());
+}
+
+#[test]
+fn test_signature_format() {
+    let tmp = WithTmpDir::new(|path| git2::Repository::init_bare(path).map_err(io::Error::other))
+        .unwrap();
+    let sig = git2::Signature::now("Signer", "signer@example.com").unwrap();
+    let tree = tmp
+        .find_tree(tmp.treebuilder(None).unwrap().write().unwrap())
+        .unwrap();
+    let content = tmp
+        .commit_create_buffer(&sig, &sig, "signed", &tree, &[])
+        .unwrap();
+    let content = content.as_str().unwrap();
+    let repo = Repository::open(tmp.path()).unwrap();
+
+    for (armor, format) in [
+        ("PGP SIGNATURE", SignatureFormat::Pgp),
+        ("SSH SIGNATURE", SignatureFormat::Ssh),
+        ("SIGNED MESSAGE", SignatureFormat::X509),
+        ("SOMETHING ELSE", SignatureFormat::Unknown),
+    ] {
+        let signature = format!("-----BEGIN {armor}-----\nc2lnbmF0dXJl\n-----END {armor}-----");
+        let oid = tmp.commit_signed(content, &signature, None).unwrap();
+        let extracted = repo
+            .extract_signature(Oid::from(oid), None)
+            .unwrap()
+            .unwrap();
+        assert_eq!(extracted.format(), format);
+        assert_eq!(extracted.as_bytes(), signature.as_bytes());
+
+        // The signature covers the commit as it was before signing.
+        let payload = repo.signing_payload(Oid::from(oid), None).unwrap().unwrap();
+        assert_eq!(payload, content.as_bytes());
+    }
+
+    // Unsigned commits have no signature.
+    let oid = tmp
+        .commit(None, &sig, &sig, "unsigned", &tree, &[])
+        .unwrap();
+    assert!(repo
+        .extract_signature(Oid::from(oid), None)
+        .unwrap()
+        .is_none());
+    assert!(repo
+        .signing_payload(Oid::from(oid), None)
+        .unwrap()
+        .is_none());
+}
+
+#[test]
+fn test_commit_message_encoding() {
+    let tmp = WithTmpDir::new(|path| git2::Repository::init_bare(path).map_err(io::Error::other))
+        .unwrap();
+    let tree = tmp.treebuilder(None).unwrap().write().unwrap();
+    let repo = Repository::open(tmp.path()).unwrap();
+
+    for (encoding, raw, expected) in [
+        (
+            "ISO-8859-1",
+            &b"caf\xe9\n\nd\xe9j\xe0 vu\n"[..],
+            "café\n\ndéjà vu\n",
+        ),
+        ("Shift_JIS", &b"\x93\xfa\x96{\x8c\xea\n"[..], "日本語\n"),
+    ] {
+        let mut object = format!(
+            "tree {tree}\n\
+             author Encoder <encoder@example.com> 0 +0000\n\
+             committer Encoder <encoder@example.com> 0 +0000\n\
+             encoding {encoding}\n\n"
+        )
+        .into_bytes();
+        object.extend_from_slice(raw);
+        let oid = tmp
+            .odb()
+            .unwrap()
+            .write(git2::ObjectType::Commit, &object)
+            .unwrap();
+
+        let commit = repo.commit(Oid::from(oid)).unwrap();
+        assert_eq!(commit.message, expected);
+        assert_eq!(commit.summary, expected.lines().next().unwrap());
+    }
+}
+
+#[test]
+fn test_reachable_objects() {
+    let repo = Repository::open(GIT_PLATINUM).unwrap();
+
+    // Compare with `git rev-list --objects <rev> | wc -l`.
+    let master = repo
+        .reachable_objects(Branch::local(refname!("master")))
+        .unwrap();
+    assert_eq!(master.len(), 55);
+
+    let initial = Oid::from_str("d3464e33d75c75c99bfb90fa2e9d16efc0b7d0e3").unwrap();
+    let objects = repo.reachable_objects(initial).unwrap();
+    assert_eq!(objects.len(), 3);
+    assert!(objects.contains(&initial));
+    assert!(objects.is_subset(&master));
+
+    // The annotated tag object itself is reachable too.
+    let tag = Oid::from_str("4d1f4af2703074d37cb877f4fdbe36322c8e541d").unwrap();
+    let objects = repo.reachable_objects(tag).unwrap();
+    assert_eq!(objects.len(), 41);
+    assert!(objects.contains(&tag));
+}
+
+#[test]
+fn test_merge_commits() {
+    let repo = Repository::open(GIT_PLATINUM).unwrap();
+    let merges = repo
+        .merge_commits(Branch::local(refname!("master")))
+        .unwrap();
+    assert_eq!(
+        merges.iter().map(|commit| commit.id).collect::<Vec<_>>(),
+        vec![Oid::from_str("223aaf87d6ea62eef0014857640fd7c8dd0f80b5").unwrap()]
+    );
+    assert!(merges.iter().all(|commit| commit.parents.len() > 1));
+
+    assert!(repo
+        .merge_commits(Branch::local(refname!("dev")))
+        .unwrap()
+        .is_empty());
+}
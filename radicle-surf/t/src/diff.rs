@@ -1,8 +1,10 @@
-use pretty_assertions::assert_eq;
+use pretty_assertions::{assert_eq, assert_ne};
 use radicle_git_ext::{ref_format::refname, Oid};
 use radicle_surf::{
     diff::{
+        self,
         Added,
+        Conflicted,
         Diff,
         DiffContent,
         DiffFile,
@@ -11,17 +13,24 @@ use radicle_surf::{
         FileMode,
         Hunk,
         Line,
+        LineMapping,
         Modification,
         Modified,
+        MovedLines,
+        PathChange,
+        Side,
         Stats,
+        Whitespace,
     },
+    repo,
     Branch,
     Error,
     Repository,
 };
-use std::{path::Path, str::FromStr};
+use std::{io, path::Path, str::FromStr};
+use test_helpers::tempdir::WithTmpDir;
 
-use super::GIT_PLATINUM;
+use super::{commit_files, tmp_repo, GIT_PLATINUM};
 
 #[test]
 fn test_initial_diff() -> Result<(), Error> {
@@ -76,6 +85,482 @@ fn test_diff_of_rev() -> Result<(), Error> {
     Ok(())
 }
 
+#[test]
+fn test_triple_dot_diff() -> Result<(), Error> {
+    let repo = Repository::open(GIT_PLATINUM)?;
+    let master = Branch::local(refname!("master"));
+    let dev = Branch::local(refname!("dev"));
+
+    // dev diverged from master at 1820cb0, so the changes made on master
+    // since then are not part of the diff.
+    let merge_base = Oid::from_str("1820cb07c1a890016ca5578aa652fd4d4c38967e")?;
+    let diff = repo.triple_dot_diff(&master, &dev)?;
+    assert_eq!(diff, repo.diff(merge_base, &dev)?);
+    assert_ne!(diff, repo.diff(&master, &dev)?);
+
+    Ok(())
+}
+
+#[test]
+fn test_triple_dot_diff_unrelated() -> Result<(), Error> {
+    let tmp = WithTmpDir::new(|path| git2::Repository::init_bare(path).map_err(io::Error::other))
+        .unwrap();
+    let sig = git2::Signature::now("Root", "root@example.com")?;
+    let tree = tmp.find_tree(tmp.treebuilder(None)?.write()?)?;
+    let one = tmp.commit(None, &sig, &sig, "one", &tree, &[])?;
+    let two = tmp.commit(None, &sig, &sig, "two", &tree, &[])?;
+
+    let repo = Repository::open(tmp.path())?;
+    let err = repo
+        .triple_dot_diff(Oid::from(one), Oid::from(two))
+        .unwrap_err();
+    assert!(
+        matches!(err, Error::Repo(repo::error::Repo::NoMergeBase(base, head)) if *base == one && *head == two),
+        "{err:?}"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_diff_binary_sizes() -> Result<(), Error> {
+    let tmp = WithTmpDir::new(|path| git2::Repository::init_bare(path).map_err(io::Error::other))
+        .unwrap();
+    let sig = git2::Signature::now("Binary", "binary@example.com")?;
+    let commit = |files: &[(&str, &[u8])]| -> Result<Oid, git2::Error> {
+        let mut builder = tmp.treebuilder(None)?;
+        for (name, content) in files {
+            builder.insert(name, tmp.blob(content)?, 0o100644)?;
+        }
+        let tree = tmp.find_tree(builder.write()?)?;
+        Ok(tmp.commit(None, &sig, &sig, "binary", &tree, &[])?.into())
+    };
+    let one = commit(&[("changed.bin", b"\0\x01\x02"), ("deleted.bin", b"\0\x01")])?;
+    let two = commit(&[("changed.bin", b"\0\x01\x02\x03\x04"), ("added.bin", b"\0")])?;
+
+    let repo = Repository::open(tmp.path())?;
+    let diff = repo.diff(one, two)?;
+    assert_eq!(
+        diff.added().next().unwrap().diff,
+        DiffContent::Binary {
+            old_size: None,
+            new_size: Some(1),
+        }
+    );
+    assert_eq!(
+        diff.deleted().next().unwrap().diff,
+        DiffContent::Binary {
+            old_size: Some(2),
+            new_size: None,
+        }
+    );
+    assert_eq!(
+        diff.modified().next().unwrap().diff,
+        DiffContent::Binary {
+            old_size: Some(3),
+            new_size: Some(5),
+        }
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_diff_symlink_target() -> Result<(), Error> {
+    let tmp = WithTmpDir::new(|path| git2::Repository::init_bare(path).map_err(io::Error::other))
+        .unwrap();
+    let sig = git2::Signature::now("Linker", "linker@example.com")?;
+    let commit = |links: &[(&str, &str)]| -> Result<Oid, git2::Error> {
+        let mut builder = tmp.treebuilder(None)?;
+        for (name, target) in links {
+            builder.insert(name, tmp.blob(target.as_bytes())?, 0o120000)?;
+        }
+        let tree = tmp.find_tree(builder.write()?)?;
+        Ok(tmp.commit(None, &sig, &sig, "link", &tree, &[])?.into())
+    };
+    let one = commit(&[("current", "v1"), ("deleted", "gone")])?;
+    let two = commit(&[("current", "v2"), ("added", "../new")])?;
+
+    let repo = Repository::open(tmp.path())?;
+    let diff = repo.diff(one, two)?;
+    assert_eq!(
+        diff.modified().next().unwrap().diff,
+        DiffContent::SymlinkTarget {
+            old: Some("v1".into()),
+            new: Some("v2".into()),
+        }
+    );
+    assert_eq!(
+        diff.added().next().unwrap().diff,
+        DiffContent::SymlinkTarget {
+            old: None,
+            new: Some("../new".into()),
+        }
+    );
+    assert_eq!(
+        diff.deleted().next().unwrap().diff,
+        DiffContent::SymlinkTarget {
+            old: Some("gone".into()),
+            new: None,
+        }
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_hunk_function_context() -> Result<(), Error> {
+    let tmp = WithTmpDir::new(|path| git2::Repository::init_bare(path).map_err(io::Error::other))
+        .unwrap();
+    let sig = git2::Signature::now("Context", "context@example.com")?;
+    let commit = |content: &str| -> Result<Oid, git2::Error> {
+        let mut builder = tmp.treebuilder(None)?;
+        builder.insert("main.rs", tmp.blob(content.as_bytes())?, 0o100644)?;
+        let tree = tmp.find_tree(builder.write()?)?;
+        Ok(tmp.commit(None, &sig, &sig, "context", &tree, &[])?.into())
+    };
+    let one = commit("fn main() {\n    1;\n    2;\n    3;\n    4;\n    5;\n}\n")?;
+    let two = commit("fn main() {\n    1;\n    2;\n    3;\n    4;\n    6;\n}\n")?;
+
+    let repo = Repository::open(tmp.path())?;
+    let diff = repo.diff(one, two)?;
+    let modified = diff.modified().next().unwrap();
+    let hunks = match &modified.diff {
+        DiffContent::Plain { hunks, .. } => hunks,
+        content => panic!("unexpected content: {content:?}"),
+    };
+    let hunk = hunks.iter().next().unwrap();
+    assert_eq!(hunk.function_context(), Some("fn main() {"));
+
+    let hunk = Hunk::<Modification> {
+        header: Line::from(b"@@ -1 +1,2 @@\n".to_vec()),
+        lines: vec![],
+        old: 1..2,
+        new: 1..3,
+    };
+    assert_eq!(hunk.function_context(), None);
+
+    Ok(())
+}
+
+#[test]
+fn test_diff_filter_paths() -> Result<(), Error> {
+    let repo = Repository::open(GIT_PLATINUM)?;
+    let diff = repo.diff(
+        Oid::from_str("d3464e33d75c75c99bfb90fa2e9d16efc0b7d0e3")?,
+        Branch::local(refname!("master")),
+    )?;
+
+    let src = diff.filter_paths(|path| path.starts_with("src"));
+    let paths = src
+        .added()
+        .map(|added| added.path.as_path())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        paths,
+        vec![Path::new("src/Eval.hs"), Path::new("src/memory.rs")]
+    );
+    assert_eq!(
+        *src.stats(),
+        Stats {
+            files_changed: 2,
+            insertions: 425,
+            deletions: 0,
+        }
+    );
+    assert_eq!(diff.filter_paths(|_| true), diff);
+
+    let tmp = WithTmpDir::new(|path| git2::Repository::init_bare(path).map_err(io::Error::other))
+        .unwrap();
+    let sig = git2::Signature::now("Filter", "filter@example.com")?;
+    let commit = |name: &str| -> Result<Oid, git2::Error> {
+        let mut builder = tmp.treebuilder(None)?;
+        builder.insert(name, tmp.blob(b"moved\n")?, 0o100644)?;
+        let tree = tmp.find_tree(builder.write()?)?;
+        Ok(tmp.commit(None, &sig, &sig, "filter", &tree, &[])?.into())
+    };
+    let one = commit("old.txt")?;
+    let two = commit("new.txt")?;
+
+    let repo = Repository::open(tmp.path())?;
+    let diff = repo.diff(one, two)?;
+    assert_eq!(diff.moved().count(), 1);
+    assert_eq!(diff.filter_paths(|path| path == Path::new("old.txt")), diff);
+    assert_eq!(diff.filter_paths(|path| path == Path::new("new.txt")), diff);
+    assert_eq!(
+        diff.filter_paths(|path| path == Path::new("other.txt")),
+        Diff::default()
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_diff_moved_lines() -> Result<(), Error> {
+    let tmp = tmp_repo();
+    let one = commit_files(
+        &tmp,
+        "move",
+        &[
+            (
+                "a.rs",
+                b"fn main() {}\n\nfn helper() {\n    1 + 1\n}\n\nfn stays() {}\n",
+                0o100644,
+            ),
+            ("b.rs", b"// b\n", 0o100644),
+        ],
+        &[],
+    );
+    let two = commit_files(
+        &tmp,
+        "move",
+        &[
+            ("a.rs", b"fn main() {}\n\nfn stays() {}\n", 0o100644),
+            (
+                "b.rs",
+                b"// b\n\nfn helper() {\n        1 + 1\n}\n",
+                0o100644,
+            ),
+        ],
+        &[],
+    );
+
+    let repo = Repository::open(tmp.path())?;
+    let diff = repo.diff(one, two)?;
+    // The re-indented `helper` moved from `a.rs` to `b.rs`.
+    assert_eq!(
+        diff.moved_lines(3),
+        vec![MovedLines {
+            old_path: "a.rs".into(),
+            old_lines: 3..6,
+            new_path: "b.rs".into(),
+            new_lines: 3..6,
+        }]
+    );
+    assert!(diff.moved_lines(4).is_empty());
+
+    // Lines repeated many times do not start blocks, which keeps large
+    // diffs tractable, but they still extend them.
+    let n = 20_000;
+    let braces = "}\n".repeat(n);
+    let buf = format!(
+        "diff --git a/a.rs b/a.rs\n\
+         index f89e4c0..7c56eb7 100644\n\
+         --- a/a.rs\n\
+         +++ b/a.rs\n\
+         @@ -1,{} +0,0 @@\n\
+         {}-fn f() {{\n-1\n-}}\n\
+         diff --git a/b.rs b/b.rs\n\
+         index f89e4c0..7c56eb7 100644\n\
+         --- a/b.rs\n\
+         +++ b/b.rs\n\
+         @@ -0,0 +1,{} @@\n\
+         +fn f() {{\n+1\n+}}\n{}",
+        n + 3,
+        braces.replace('}', "-}"),
+        n + 3,
+        braces.replace('}', "+}"),
+    );
+    let diff = Diff::try_from(git2::Diff::from_buffer(buf.as_bytes())?)?;
+    let n = n as u32;
+    assert_eq!(
+        diff.moved_lines(1),
+        vec![MovedLines {
+            old_path: "a.rs".into(),
+            old_lines: n + 1..n + 4,
+            new_path: "b.rs".into(),
+            new_lines: 1..4,
+        }]
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_diff_compose() -> Result<(), Error> {
+    let tmp = WithTmpDir::new(|path| git2::Repository::init_bare(path).map_err(io::Error::other))
+        .unwrap();
+    let sig = git2::Signature::now("Composer", "composer@example.com")?;
+    let commit = |files: &[(&str, &str)]| -> Result<Oid, git2::Error> {
+        let mut builder = tmp.treebuilder(None)?;
+        for (name, content) in files {
+            builder.insert(name, tmp.blob(content.as_bytes())?, 0o100644)?;
+        }
+        let tree = tmp.find_tree(builder.write()?)?;
+        Ok(tmp.commit(None, &sig, &sig, "compose", &tree, &[])?.into())
+    };
+    let moving = "a file that is moved\nacross several lines\nof content\n";
+    let a = commit(&[
+        ("keep.txt", "kept\n"),
+        ("del.txt", "deleted\n"),
+        ("mod.txt", "modified\n"),
+        ("move.txt", moving),
+    ])?;
+    let b = commit(&[
+        ("keep.txt", "kept\n"),
+        ("mod.txt", "modified once\n"),
+        ("moved.txt", moving),
+        ("tmp.txt", "temporary\n"),
+        ("new.txt", "new\n"),
+    ])?;
+    let c = commit(&[
+        ("keep.txt", "kept\n"),
+        ("del.txt", "deleted, then re-added\n"),
+        ("mod.txt", "modified once\n"),
+        ("moved.txt", &format!("{moving}and modified\n")),
+        ("new.txt", "new\n"),
+    ])?;
+
+    let repo = Repository::open(tmp.path())?;
+    let ab = repo.diff(a, b)?;
+    let bc = repo.diff(b, c)?;
+    assert_eq!(
+        ab.compose(&bc).unwrap(),
+        vec![
+            PathChange::Modified {
+                path: "del.txt".into()
+            },
+            PathChange::Modified {
+                path: "mod.txt".into()
+            },
+            PathChange::Moved {
+                old_path: "move.txt".into(),
+                new_path: "moved.txt".into()
+            },
+            PathChange::Added {
+                path: "new.txt".into()
+            },
+        ]
+    );
+
+    // Composing with an empty diff keeps the path-level changes.
+    assert_eq!(ab.compose(&repo.diff(b, b)?).unwrap().len(), 5);
+
+    // `ab` deletes `del.txt`, so it cannot be deleted again.
+    assert!(matches!(
+        ab.compose(&ab),
+        Err(diff::error::Compose::NotConsecutive(_))
+    ));
+
+    Ok(())
+}
+
+#[test]
+fn test_diff_max_files() -> Result<(), Error> {
+    let repo = Repository::open(GIT_PLATINUM)?;
+    let from = Oid::from_str("d3464e33d75c75c99bfb90fa2e9d16efc0b7d0e3")?;
+    let to = Branch::local(refname!("master"));
+    let full = repo.diff(from, &to)?;
+    assert!(!full.is_truncated());
+
+    let opts = diff::Options::default().max_files(2);
+    let diff = repo.diff_with_options(from, &to, &opts)?;
+    assert!(diff.is_truncated());
+    assert_eq!(diff.files().count(), 2);
+    assert_eq!(
+        diff.files().collect::<Vec<_>>(),
+        full.files().take(2).collect::<Vec<_>>()
+    );
+    // The stats still count all the files, but only the lines of the
+    // files kept.
+    assert_eq!(diff.stats().files_changed, 24);
+    assert_eq!(diff.stats().files_changed, full.stats().files_changed);
+    assert!(diff.stats().insertions < full.stats().insertions);
+
+    let opts = diff::Options::default().max_files(24);
+    let diff = repo.diff_with_options(from, &to, &opts)?;
+    assert!(!diff.is_truncated());
+    assert_eq!(diff, full);
+
+    Ok(())
+}
+
+#[test]
+fn test_diff_max_files_not_patched() -> Result<(), Error> {
+    let tmp = WithTmpDir::new(|path| git2::Repository::init_bare(path).map_err(io::Error::other))
+        .unwrap();
+    let sig = git2::Signature::now("Truncator", "truncator@example.com")?;
+    let commit = |files: &[(&str, &[u8])]| -> Result<Oid, git2::Error> {
+        let mut builder = tmp.treebuilder(None)?;
+        for (name, content) in files {
+            builder.insert(name, tmp.blob(content)?, 0o100644)?;
+        }
+        let tree = tmp.find_tree(builder.write()?)?;
+        Ok(tmp.commit(None, &sig, &sig, "truncate", &tree, &[])?.into())
+    };
+    let one = commit(&[("a.txt", b"a\n"), ("b.txt", b"b\n")])?;
+    let two = commit(&[("a.txt", b"a\nA\n"), ("b.txt", b"b\nB\n")])?;
+    // Patching `b.txt` would fail once its new content is gone.
+    let blob = tmp.blob(b"b\nB\n")?.to_string();
+    std::fs::remove_file(tmp.path().join("objects").join(&blob[..2]).join(&blob[2..])).unwrap();
+
+    let repo = Repository::open(tmp.path())?;
+    assert!(repo.diff(one, two).is_err());
+
+    let opts = diff::Options::default().max_files(1);
+    let diff = repo.diff_with_options(one, two, &opts)?;
+    assert!(diff.is_truncated());
+    assert_eq!(diff.modified().next().unwrap().path, Path::new("a.txt"));
+    assert_eq!(
+        diff.stats(),
+        &Stats {
+            files_changed: 2,
+            insertions: 1,
+            deletions: 0,
+        }
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_diff_binary_extensions() -> Result<(), Error> {
+    let repo = Repository::open(GIT_PLATINUM)?;
+    let from = Branch::local(refname!("master"));
+    let to = Branch::local(refname!("diff-test"));
+
+    let full = repo.diff_with_options(&from, &to, &diff::Options::default())?;
+    assert_eq!(full, repo.diff(&from, &to)?);
+
+    let opts = diff::Options::default().binary_extension("md");
+    let diff = repo.diff_with_options(&from, &to, &opts)?;
+    let readme = diff.modified().next().unwrap();
+    assert_eq!(readme.path, Path::new("README.md"));
+    assert_eq!(
+        readme.diff,
+        DiffContent::Binary {
+            old_size: Some(148),
+            new_size: Some(148),
+        }
+    );
+    // Other files are still diffed as usual.
+    let license = diff.added().next().unwrap();
+    assert!(matches!(license.diff, DiffContent::Plain { .. }));
+    // The lines of README.md are left out of the stats.
+    let (insertions, deletions) = match &full.modified().next().unwrap().diff {
+        DiffContent::Plain { hunks, .. } => hunks.iter().flat_map(|hunk| &hunk.lines).fold(
+            (0, 0),
+            |(insertions, deletions), line| match line {
+                Modification::Addition(_) => (insertions + 1, deletions),
+                Modification::Deletion(_) => (insertions, deletions + 1),
+                Modification::Context { .. } => (insertions, deletions),
+            },
+        ),
+        content => panic!("unexpected content {content:?}"),
+    };
+    assert!(insertions > 0 && deletions > 0);
+    assert_eq!(
+        diff.stats(),
+        &Stats {
+            files_changed: full.stats().files_changed,
+            insertions: full.stats().insertions - insertions,
+            deletions: full.stats().deletions - deletions,
+        }
+    );
+
+    Ok(())
+}
+
 #[test]
 fn test_diff_file() -> Result<(), Error> {
     let repo = Repository::open(GIT_PLATINUM)?;
@@ -115,6 +600,40 @@ fn test_diff_file() -> Result<(), Error> {
     Ok(())
 }
 
+#[test]
+fn test_diff_file_binary() -> Result<(), Error> {
+    let tmp = WithTmpDir::new(|path| git2::Repository::init_bare(path).map_err(io::Error::other))
+        .unwrap();
+    let sig = git2::Signature::now("Binary", "binary@example.com")?;
+    let commit = |content: &[u8], parent: Option<Oid>| -> Result<Oid, git2::Error> {
+        let mut builder = tmp.treebuilder(None)?;
+        builder.insert("data.bin", tmp.blob(content)?, 0o100644)?;
+        let tree = tmp.find_tree(builder.write()?)?;
+        let parents = parent.map(|oid| tmp.find_commit(*oid)).transpose()?;
+        let parents = parents.iter().collect::<Vec<_>>();
+        Ok(tmp
+            .commit(None, &sig, &sig, "binary", &tree, &parents)?
+            .into())
+    };
+    let one = commit(b"a\0\nb\n", None)?;
+    let two = commit(b"a\0\nc\n", Some(one))?;
+    let binary = DiffContent::Binary {
+        old_size: Some(5),
+        new_size: Some(5),
+    };
+
+    // Binary files under a pathspec are not diffed as text.
+    let repo = Repository::open(tmp.path())?;
+    match repo.diff_file(&"data.bin", one, two)? {
+        FileDiff::Modified(modified) => assert_eq!(modified.diff, binary),
+        diff => panic!("unexpected diff {diff:?}"),
+    }
+    let history = repo.line_history(two, &"data.bin", 1, 1)?;
+    assert_eq!(history[0].diff, binary);
+
+    Ok(())
+}
+
 #[test]
 fn test_diff() -> Result<(), Error> {
     let repo = Repository::open(GIT_PLATINUM)?;
@@ -161,6 +680,169 @@ fn test_diff() -> Result<(), Error> {
     Ok(())
 }
 
+#[test]
+fn test_diff_commit_parents() -> Result<(), Error> {
+    let repo = Repository::open(GIT_PLATINUM)?;
+
+    // A merge commit is diffed against both of its parents.
+    let merge = "223aaf87d6ea62eef0014857640fd7c8dd0f80b5";
+    let diffs = repo.diff_commit_parents(merge)?;
+    assert_eq!(diffs.len(), 2);
+    assert_eq!(diffs[0], repo.diff_commit(merge)?);
+    let files = diffs[1]
+        .files()
+        .map(|file| match file {
+            FileDiff::Modified(m) => m.path.clone(),
+            FileDiff::Deleted(d) => d.path.clone(),
+            _ => panic!("unexpected file diff: {file:?}"),
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(
+        files,
+        vec![Path::new("src/Eval.hs"), Path::new("src/Folder.svelte")]
+    );
+
+    // The initial commit has a single diff against nothing.
+    let initial = "d3464e33d75c75c99bfb90fa2e9d16efc0b7d0e3";
+    assert_eq!(
+        repo.diff_commit_parents(initial)?,
+        vec![repo.diff_commit(initial)?]
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_files_changed_from_all_parents() -> Result<(), Error> {
+    let tmp = WithTmpDir::new(|path| git2::Repository::init_bare(path).map_err(io::Error::other))
+        .unwrap();
+    let sig = git2::Signature::now("Merger", "merger@example.com")?;
+    let commit = |contents: [&str; 3], parents: &[Oid]| -> Result<Oid, git2::Error> {
+        let mut builder = tmp.treebuilder(None)?;
+        for (name, content) in ["a", "b", "c"].into_iter().zip(contents) {
+            builder.insert(name, tmp.blob(content.as_bytes())?, 0o100644)?;
+        }
+        let tree = tmp.find_tree(builder.write()?)?;
+        let parents = parents
+            .iter()
+            .map(|oid| tmp.find_commit(**oid))
+            .collect::<Result<Vec<_>, _>>()?;
+        let parents = parents.iter().collect::<Vec<_>>();
+        Ok(tmp
+            .commit(None, &sig, &sig, "merge", &tree, &parents)?
+            .into())
+    };
+    let base = commit(["1\n", "1\n", "1\n"], &[])?;
+    let ours = commit(["2\n", "1\n", "1\n"], &[base])?;
+    let theirs = commit(["1\n", "2\n", "1\n"], &[base])?;
+    let merge = commit(["2\n", "2\n", "3\n"], &[ours, theirs])?;
+    let repo = Repository::open(tmp.path())?;
+
+    // `a` is taken from `ours`, and `b` from `theirs`, but `c` differs
+    // from both.
+    let changed = repo.files_changed_from_all_parents(merge)?;
+    assert_eq!(
+        changed
+            .modified()
+            .map(|m| m.path.as_path())
+            .collect::<Vec<_>>(),
+        vec![Path::new("c")]
+    );
+    assert_eq!(changed.files().count(), 1);
+    assert_eq!(changed.stats().files_changed, 1);
+
+    // Other commits have the same diff as `diff_commit`.
+    assert_eq!(
+        repo.files_changed_from_all_parents(ours)?,
+        repo.diff_commit(ours)?
+    );
+    assert_eq!(
+        repo.files_changed_from_all_parents(base)?,
+        repo.diff_commit(base)?
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_diff_ancestors() -> Result<(), Error> {
+    let repo = Repository::open(GIT_PLATINUM)?;
+    let dev = Branch::local(refname!("dev"));
+
+    assert_eq!(repo.diff_ancestors(&dev, 1)?, repo.diff_commit(&dev)?);
+    assert_eq!(
+        repo.diff_ancestors(&dev, 3)?,
+        repo.diff(repo.resolve("dev~3")?, repo.resolve("dev")?)?
+    );
+    assert_eq!(repo.diff_ancestors(&dev, 0)?.files().count(), 0);
+
+    // Annotated tags are peeled to their commit.
+    let tag = refname!("refs/tags/v0.6.0");
+    assert_eq!(
+        repo.diff_ancestors(&tag, 1)?,
+        repo.diff_commit(repo.resolve("v0.6.0")?)?
+    );
+
+    // `dev` has 8 commits along its first parents, so the changes of all
+    // of them are the files of `dev`, added.
+    assert!(repo.diff_ancestors(&dev, 7).is_ok());
+    let all = repo.diff_ancestors(&dev, 8)?;
+    assert_eq!(all.files().count(), all.added().count());
+    assert!(all
+        .added()
+        .any(|added| added.path == Path::new("src/memory.rs")));
+    let err = repo.diff_ancestors(&dev, 9).unwrap_err();
+    assert!(err.to_string().starts_with("revision not found"), "{err}");
+
+    Ok(())
+}
+
+#[test]
+fn test_diff_name_status() -> Result<(), Error> {
+    let repo = Repository::open(GIT_PLATINUM)?;
+    let from = "d3464e33d75c75c99bfb90fa2e9d16efc0b7d0e3";
+    let to = Branch::local(refname!("master"));
+    let changes = repo.diff_name_status(from, &to)?;
+    assert_eq!(changes.len(), repo.diff(from, &to)?.files().count());
+    let readme = changes
+        .iter()
+        .find(|change| change.path == Path::new("README.md"))
+        .unwrap();
+    assert_eq!(readme.to_string(), "M\tREADME.md");
+
+    let tmp = WithTmpDir::new(|path| git2::Repository::init_bare(path).map_err(io::Error::other))
+        .unwrap();
+    let lines = (0..10).map(|i| format!("line {i}\n")).collect::<String>();
+    let commit = |files: &[(&str, &str)], parents: &[&git2::Commit]| {
+        let mut builder = tmp.treebuilder(None)?;
+        for (name, content) in files {
+            builder.insert(name, tmp.blob(content.as_bytes())?, 0o100644)?;
+        }
+        let tree = tmp.find_tree(builder.write()?)?;
+        let sig = git2::Signature::now("Status", "status@example.com")?;
+        let oid = tmp.commit(None, &sig, &sig, "status", &tree, parents)?;
+        tmp.find_commit(oid)
+    };
+    let base = commit(&[("old.txt", &lines), ("gone.txt", "gone\n")], &[])?;
+    let renamed = lines.replace("line 9", "line nine");
+    let head = commit(&[("new.txt", &renamed), ("added.txt", "added\n")], &[&base])?;
+
+    // The similarity is the one found when detecting the rename, which
+    // `git diff --name-status` reports as `R086`.
+    let repo = Repository::open(tmp.path())?;
+    let changes = repo
+        .diff_name_status(Oid::from(base.id()), Oid::from(head.id()))?
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>();
+    assert_eq!(
+        changes,
+        vec!["A\tadded.txt", "D\tgone.txt", "R090\told.txt\tnew.txt"]
+    );
+
+    Ok(())
+}
+
 #[test]
 fn test_branch_diff() -> Result<(), Error> {
     let repo = Repository::open(GIT_PLATINUM)?;
@@ -196,6 +878,12 @@ fn test_branch_diff() -> Result<(), Error> {
     // Verify moved.
     let diff_moved = diff.moved().next().unwrap();
 
+    // The blob ids of both sides of the move are available.
+    let old_file = repo.file(&rev_from, &diff_moved.old_path)?;
+    let new_file = repo.file(&rev_to, &diff_moved.new_path)?;
+    assert_eq!(diff_moved.old.oid, old_file.id());
+    assert_eq!(diff_moved.new.oid, new_file.id());
+
     // We can find a `FileDiff` for the old_path in a move.
     let file_diff = repo.diff_file(&diff_moved.old_path, &rev_from, &rev_to)?;
     println!("old path file diff: {:?}", &file_diff);
@@ -211,6 +899,76 @@ fn test_branch_diff() -> Result<(), Error> {
     Ok(())
 }
 
+#[test]
+fn test_diff_conflicted() -> Result<(), Error> {
+    let tmp = WithTmpDir::new(|path| git2::Repository::init_bare(path).map_err(io::Error::other))
+        .unwrap();
+    let sig = git2::Signature::now("Conflicter", "conflicter@example.com")?;
+    let commit = |files: &[(&str, &str)], parent: Option<Oid>| -> Result<Oid, git2::Error> {
+        let mut builder = tmp.treebuilder(None)?;
+        for (name, content) in files {
+            builder.insert(name, tmp.blob(content.as_bytes())?, 0o100644)?;
+        }
+        let tree = tmp.find_tree(builder.write()?)?;
+        let parents = parent.map(|oid| tmp.find_commit(*oid)).transpose()?;
+        let parents = parents.iter().collect::<Vec<_>>();
+        Ok(tmp
+            .commit(None, &sig, &sig, "conflict", &tree, &parents)?
+            .into())
+    };
+    let base = commit(&[("file.txt", "base\n")], None)?;
+    let ours = commit(&[("file.txt", "ours\n")], Some(base))?;
+    let theirs = commit(
+        &[("file.txt", "theirs\n"), ("new.txt", "new\n")],
+        Some(base),
+    )?;
+    let blob = |content: &str| -> Result<DiffFile, git2::Error> {
+        Ok(DiffFile {
+            oid: tmp.blob(content.as_bytes())?.into(),
+            mode: FileMode::Blob,
+        })
+    };
+
+    let repo = Repository::open(tmp.path())?;
+    let diff = repo.merge_diff(ours, theirs)?;
+    assert_eq!(
+        diff.added().map(|a| a.path.as_path()).collect::<Vec<_>>(),
+        vec![Path::new("new.txt")]
+    );
+    assert_eq!(
+        diff.conflicted().collect::<Vec<_>>(),
+        vec![&Conflicted {
+            path: Path::new("file.txt").to_path_buf(),
+            ancestor: Some(blob("base\n")?),
+            ours: Some(blob("ours\n")?),
+            theirs: Some(blob("theirs\n")?),
+        }]
+    );
+    assert_eq!(
+        serde_json::to_value(&diff).unwrap()["conflicted"],
+        serde_json::json!([{
+            "path": "file.txt",
+            "ancestor": { "mode": "blob", "oid": blob("base\n")?.oid.to_string() },
+            "ours": { "mode": "blob", "oid": blob("ours\n")?.oid.to_string() },
+            "theirs": { "mode": "blob", "oid": blob("theirs\n")?.oid.to_string() },
+        }])
+    );
+    assert_eq!(
+        Diff::from_json_v1(&diff.to_json_v1().unwrap()).unwrap(),
+        diff
+    );
+
+    // A file deleted on one side and modified on the other has no side
+    // for the deletion.
+    let deleted = commit(&[], Some(base))?;
+    let diff = repo.merge_diff(deleted, theirs)?;
+    let conflicted = diff.conflicted().next().unwrap();
+    assert_eq!(conflicted.ours, None);
+    assert_eq!(conflicted.theirs, Some(blob("theirs\n")?));
+
+    Ok(())
+}
+
 #[test]
 fn test_diff_serde() -> Result<(), Error> {
     let repo = Repository::open(GIT_PLATINUM)?;
@@ -301,6 +1059,14 @@ fn test_diff_serde() -> Result<(), Error> {
         "moved": [{
             "oldPath": "text/emoji.txt",
             "newPath": "emoji.txt",
+            "old": {
+                "mode": "blob",
+                "oid": "1570277532948712fea9029d100a4208f9e34241",
+            },
+            "new": {
+                "mode": "blob",
+                "oid": "1570277532948712fea9029d100a4208f9e34241",
+            },
         }],
         "copied": [],
         "modified": [{
@@ -341,17 +1107,310 @@ fn test_diff_serde() -> Result<(), Error> {
                 "oid": "5e07534cd74a6a9b2ccd2729b181c4ef26173a5e",
             },
         }],
+        "conflicted": [],
         "stats": {
             "deletions": 9,
             "filesChanged": 4,
             "insertions": 4,
-        }
+        },
+        "truncated": false,
     });
     assert_eq!(serde_json::to_value(diff).unwrap(), json);
 
     Ok(())
 }
 
+#[test]
+fn test_diff_json_v1() -> Result<(), Error> {
+    let repo = Repository::open(GIT_PLATINUM)?;
+    let diff = repo.diff(
+        Branch::local(refname!("master")),
+        Branch::local(refname!("diff-test")),
+    )?;
+    let json = diff.to_json_v1().unwrap();
+    assert_eq!(Diff::from_json_v1(&json).unwrap(), diff);
+
+    let value = serde_json::from_str::<serde_json::Value>(&json).unwrap();
+    assert_eq!(value["version"], 1);
+    assert_eq!(value["truncated"], false);
+    let readme = value["files"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|file| file["path"] == "README.md")
+        .unwrap();
+    assert_eq!(readme["change"], "modified");
+    assert_eq!(readme["content"]["type"], "plain");
+    assert_eq!(readme["new"]["mode"], "blob");
+    let hunk = &readme["content"]["hunks"][0];
+    assert_eq!(hunk["old"], serde_json::json!({ "start": 1, "end": 3 }));
+
+    // Lines that are not valid UTF-8 are kept as base64.
+    let tmp = WithTmpDir::new(|path| git2::Repository::init_bare(path).map_err(io::Error::other))
+        .unwrap();
+    let sig = git2::Signature::now("Json", "json@example.com")?;
+    let mut oids = Vec::new();
+    for content in [&b"text\n"[..], &b"\xff\xfe\n"[..]] {
+        let mut builder = tmp.treebuilder(None)?;
+        builder.insert("latin1.txt", tmp.blob(content)?, 0o100644)?;
+        let tree = tmp.find_tree(builder.write()?)?;
+        let parents = oids.last().map(|oid| tmp.find_commit(*oid)).transpose()?;
+        let oid = tmp.commit(
+            None,
+            &sig,
+            &sig,
+            "json",
+            &tree,
+            &parents.iter().collect::<Vec<_>>(),
+        )?;
+        oids.push(oid);
+    }
+    let repo = Repository::open(tmp.path())?;
+    let diff = repo.diff(Oid::from(oids[0]), Oid::from(oids[1]))?;
+    let json = diff.to_json_v1().unwrap();
+    assert!(json.contains(r#"{"base64":"//4K"}"#), "{json}");
+    assert_eq!(Diff::from_json_v1(&json).unwrap(), diff);
+
+    let err =
+        Diff::from_json_v1(&json.replacen(r#""version":1"#, r#""version":2"#, 1)).unwrap_err();
+    assert_eq!(err.to_string(), "unsupported diff format version 2");
+
+    Ok(())
+}
+
+#[test]
+fn test_diff_stats() -> Result<(), Error> {
+    let repo = Repository::open(GIT_PLATINUM)?;
+    let rev_from = Branch::local(refname!("master"));
+    let rev_to = Branch::local(refname!("diff-test"));
+    let stats = repo.diff_stats(&rev_from, &rev_to)?;
+    assert_eq!(stats, *repo.diff(&rev_from, &rev_to)?.stats());
+    assert_eq!(
+        stats,
+        Stats {
+            files_changed: 4,
+            insertions: 4,
+            deletions: 9,
+        }
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_diff_foreach() -> Result<(), Error> {
+    let repo = Repository::open(GIT_PLATINUM)?;
+    let rev_from = Branch::local(refname!("master"));
+    let rev_to = Branch::local(refname!("diff-test"));
+    let diff = repo.diff(&rev_from, &rev_to)?;
+
+    let mut files = Vec::new();
+    let mut hunks = Vec::new();
+    repo.diff_foreach(
+        &rev_from,
+        &rev_to,
+        |path| {
+            files.push(path.to_path_buf());
+            true
+        },
+        |path, hunk| {
+            hunks.push((path.to_path_buf(), hunk));
+            true
+        },
+    )?;
+    assert_eq!(files.len(), diff.files().count());
+
+    let modified = diff.modified().next().unwrap();
+    let streamed = hunks
+        .iter()
+        .filter(|(path, _)| *path == modified.path)
+        .map(|(_, hunk)| hunk.clone())
+        .collect::<Vec<_>>();
+    match &modified.diff {
+        DiffContent::Plain { hunks, .. } => {
+            assert_eq!(hunks.iter().cloned().collect::<Vec<_>>(), streamed)
+        },
+        _ => panic!("expected a plain diff"),
+    }
+
+    // Stopping early visits a single file.
+    let mut visited = 0;
+    repo.diff_foreach(
+        &rev_from,
+        &rev_to,
+        |_| {
+            visited += 1;
+            false
+        },
+        |_, _| true,
+    )?;
+    assert_eq!(visited, 1);
+
+    Ok(())
+}
+
+#[test]
+fn test_diff_directories() -> Result<(), Error> {
+    let repo = Repository::open(GIT_PLATINUM)?;
+    let rev_from = Branch::local(refname!("master"));
+    let rev_to = Branch::local(refname!("diff-test"));
+
+    // The root directories produce the same diff as the commits.
+    let from = repo.root_dir(&rev_from)?;
+    let to = repo.root_dir(&rev_to)?;
+    assert_eq!(
+        repo.diff_directories(&from, &to)?,
+        repo.diff(&rev_from, &rev_to)?
+    );
+
+    // Sub-directories are diffed relative to themselves, so a file moved
+    // out of the directory shows as deleted.
+    let from = repo.directory(&rev_from, &"text")?;
+    let to = repo.directory(&rev_to, &"text")?;
+    let diff = repo.diff_directories(&from, &to)?;
+    let deleted = diff.deleted().map(|d| d.path.clone()).collect::<Vec<_>>();
+    assert_eq!(
+        deleted,
+        vec![
+            Path::new("arrows.txt").to_path_buf(),
+            Path::new("emoji.txt").to_path_buf()
+        ]
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_tree_set_diff() -> Result<(), Error> {
+    let repo = Repository::open(GIT_PLATINUM)?;
+    let (only_master, only_diff_test) = repo.tree_set_diff(
+        Branch::local(refname!("master")),
+        Branch::local(refname!("diff-test")),
+    )?;
+
+    // README.md is modified, but exists on both sides, so it is ignored.
+    assert_eq!(
+        only_master,
+        vec![
+            Path::new("text/arrows.txt").to_path_buf(),
+            Path::new("text/emoji.txt").to_path_buf()
+        ]
+    );
+    assert_eq!(
+        only_diff_test,
+        vec![
+            Path::new("LICENSE").to_path_buf(),
+            Path::new("emoji.txt").to_path_buf()
+        ]
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_whitespace() {
+    assert_eq!(
+        Line::from(b"\tfoo(bar); \t\r\n".to_vec()).whitespace(),
+        Whitespace {
+            trailing: Some(10..12),
+            tabs: vec![0, 11],
+        }
+    );
+    assert_eq!(
+        Line::from(b"clean\n".to_vec()).whitespace(),
+        Whitespace::default()
+    );
+    assert_eq!(
+        Line::from(b"   ".to_vec()).whitespace().trailing,
+        Some(0..3)
+    );
+
+    assert_eq!(
+        Modification::addition(b"added \n".to_vec(), 1).whitespace(),
+        Some(Whitespace {
+            trailing: Some(5..6),
+            tabs: vec![],
+        })
+    );
+    assert_eq!(
+        Modification::deletion(b"deleted \n".to_vec(), 1).whitespace(),
+        None
+    );
+}
+
+#[test]
+fn test_map_line() {
+    let buf = r#"
+diff --git a/file.txt b/file.txt
+index f89e4c0..7c56eb7 100644
+--- a/file.txt
++++ b/file.txt
+@@ -2,3 +2,4 @@
+ two
+-three
++three and a half
++three and three quarters
+ four
+@@ -15,2 +15,0 @@
+-fifteen
+-sixteen
+"#;
+    let diff = git2::Diff::from_buffer(buf.as_bytes()).unwrap();
+    let diff = Diff::try_from(diff).unwrap();
+    let content = &diff.modified().next().unwrap().diff;
+
+    // Lines before, within, and after the first hunk.
+    assert_eq!(
+        content.map_line(Side::Old, 1),
+        Some((1, LineMapping::Context))
+    );
+    assert_eq!(
+        content.map_line(Side::Old, 2),
+        Some((2, LineMapping::Context))
+    );
+    assert_eq!(
+        content.map_line(Side::Old, 3),
+        Some((3, LineMapping::Deleted))
+    );
+    assert_eq!(
+        content.map_line(Side::New, 4),
+        Some((4, LineMapping::Added))
+    );
+    assert_eq!(
+        content.map_line(Side::New, 5),
+        Some((4, LineMapping::Context))
+    );
+    assert_eq!(
+        content.map_line(Side::Old, 10),
+        Some((11, LineMapping::Context))
+    );
+
+    // Lines around the second, deletion only, hunk.
+    assert_eq!(
+        content.map_line(Side::Old, 15),
+        Some((16, LineMapping::Deleted))
+    );
+    assert_eq!(
+        content.map_line(Side::Old, 17),
+        Some((16, LineMapping::Context))
+    );
+    assert_eq!(
+        content.map_line(Side::New, 15),
+        Some((14, LineMapping::Context))
+    );
+    assert_eq!(
+        content.map_line(Side::New, 16),
+        Some((17, LineMapping::Context))
+    );
+
+    assert_eq!(content.map_line(Side::New, 0), None);
+    let binary = DiffContent::Binary {
+        old_size: None,
+        new_size: Some(1),
+    };
+    assert_eq!(binary.map_line(Side::Old, 1), None);
+}
+
 #[test]
 fn test_both_missing_eof_newline() {
     let buf = r#"
@@ -373,6 +1432,39 @@ index f89e4c0..7c56eb7 100644
     );
 }
 
+#[test]
+fn test_whitespace_only() {
+    let buf = r#"
+diff --git a/indent.rs b/indent.rs
+index f89e4c0..7c56eb7 100644
+--- a/indent.rs
++++ b/indent.rs
+@@ -1,3 +1,4 @@
+ fn main() {
+-println!("hello");
++    println!(
++        "hello");
+ }
+diff --git a/.env b/.env
+index f89e4c0..7c56eb7 100644
+--- a/.env
++++ b/.env
+@@ -1 +1 @@
+-hello = 123
++hello = 1234
+"#;
+    let diff = git2::Diff::from_buffer(buf.as_bytes()).unwrap();
+    let diff = Diff::try_from(diff).unwrap();
+    let whitespace_only = diff
+        .modified()
+        .map(|file| (file.path.as_path(), file.is_whitespace_only()))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        whitespace_only,
+        vec![(Path::new("indent.rs"), true), (Path::new(".env"), false)]
+    );
+}
+
 #[test]
 fn test_none_missing_eof_newline() {
     let buf = r#"
@@ -10,7 +10,8 @@ mod directory {
         Branch,
         Repository,
     };
-    use std::path::Path;
+    use std::{fs as stdfs, io, path::Path};
+    use test_helpers::tempdir::WithTmpDir;
 
     const GIT_PLATINUM: &str = "../data/git-platinum";
 
@@ -55,6 +56,23 @@ mod directory {
         assert!(result.is_err());
     }
 
+    #[test]
+    fn directory_find_entry_normalized() {
+        let repo = Repository::open(GIT_PLATINUM).unwrap();
+        let root = repo.root_dir(Branch::local(refname!("master"))).unwrap();
+
+        let path = Path::new("./src/../text/./garden.txt");
+        let entry = root.find_entry(&path, &repo).unwrap();
+        assert_eq!(entry.path(), Path::new("text/garden.txt"));
+
+        let dir = root.find_directory(&"src/..", &repo).unwrap();
+        assert_eq!(dir, root);
+
+        // Escaping the root is invalid.
+        let result = root.find_entry(&"../src/memory.rs", &repo);
+        assert!(matches!(result, Err(fs::error::Directory::InvalidPath(_))));
+    }
+
     #[test]
     fn directory_find_file_and_directory() {
         let repo = Repository::open(GIT_PLATINUM).unwrap();
@@ -93,6 +111,302 @@ mod directory {
         assert_eq!(src_contents[2].name(), "memory.rs");
     }
 
+    #[test]
+    fn directory_is_effectively_empty() {
+        let repo = Repository::open(GIT_PLATINUM).unwrap();
+        let master = Branch::local(refname!("master"));
+        let keepfiles = [".gitkeep"];
+
+        // `this/is/a/really/deeply/nested/directory/tree/.gitkeep`
+        for path in ["this", "this/is/a/really/deeply/nested/directory/tree"] {
+            let dir = repo.directory(&master, &path).unwrap();
+            assert!(
+                dir.is_effectively_empty(&repo, &keepfiles).unwrap(),
+                "{path}"
+            );
+            assert!(!dir.is_effectively_empty(&repo, &[]).unwrap(), "{path}");
+        }
+        let root = repo.root_dir(&master).unwrap();
+        assert!(!root.is_effectively_empty(&repo, &keepfiles).unwrap());
+    }
+
+    #[test]
+    fn directory_same_tree() {
+        let repo = Repository::open(GIT_PLATINUM).unwrap();
+        // v0.5.0 and v0.6.0 point to different commits with the same tree,
+        // so their root directories are the same, lazily loaded, tree.
+        let v5 = repo.resolve("v0.5.0").unwrap();
+        let v6 = repo.resolve("v0.6.0").unwrap();
+        assert_ne!(v5, v6);
+        assert_eq!(repo.root_dir(v5).unwrap(), repo.root_dir(v6).unwrap());
+    }
+
+    #[test]
+    fn directory_entries_not_ignored() {
+        let tmp =
+            WithTmpDir::new(|path| git2::Repository::init(path).map_err(io::Error::other)).unwrap();
+        let workdir = tmp.workdir().unwrap().to_path_buf();
+        for (path, content) in [
+            (
+                ".gitignore",
+                "*.log\n!keep.log\n/build/\n# a comment\ndocs/*.tmp\n",
+            ),
+            ("keep.log", ""),
+            ("debug.log", ""),
+            ("main.rs", ""),
+            ("build/out.o", ""),
+            ("docs/guide.md", ""),
+            ("docs/draft.tmp", ""),
+            ("docs/build/index.html", ""),
+            ("docs/.gitignore", "secret.txt\n!keep.log\nnested/\n"),
+            ("docs/secret.txt", ""),
+            ("docs/nested/file.txt", ""),
+        ] {
+            let path = workdir.join(path);
+            stdfs::create_dir_all(path.parent().unwrap()).unwrap();
+            stdfs::write(path, content).unwrap();
+        }
+        let mut index = tmp.index().unwrap();
+        // Forcefully add ignored files, as if they were committed anyway.
+        index
+            .add_all(["*"], git2::IndexAddOption::FORCE, None)
+            .unwrap();
+        let tree = tmp.find_tree(index.write_tree().unwrap()).unwrap();
+        let sig = git2::Signature::now("Ignorer", "ignorer@example.com").unwrap();
+        let oid = tmp.commit(None, &sig, &sig, "ignore", &tree, &[]).unwrap();
+
+        let repo = Repository::open(tmp.path()).unwrap();
+        let names = |path: &str| {
+            repo.entries_not_ignored(radicle_git_ext::Oid::from(oid), &path)
+                .unwrap()
+                .names()
+                .cloned()
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(names(""), vec![".gitignore", "docs", "keep.log", "main.rs"]);
+        // Only the root `build` directory is anchored.
+        assert_eq!(names("docs"), vec![".gitignore", "build", "guide.md"]);
+        assert!(names("build").is_empty());
+
+        // Everything is still listed when not filtering.
+        let root = repo.root_dir(radicle_git_ext::Oid::from(oid)).unwrap();
+        assert_eq!(root.entries(&repo).unwrap().count(), 6);
+    }
+
+    #[test]
+    fn directory_entries_not_ignored_patterns() {
+        let tmp =
+            WithTmpDir::new(|path| git2::Repository::init(path).map_err(io::Error::other)).unwrap();
+        let workdir = tmp.workdir().unwrap().to_path_buf();
+        let long = "a".repeat(64);
+        for (path, content) in [
+            (
+                ".gitignore",
+                "src/a**b\nlib/**/x.rs\n*a*a*a*a*a*a*a*a*a*a*a*a*a*a*a*a*a*a*a*a*b\n",
+            ),
+            ("src/axxb", ""),
+            ("src/ax/yb", ""),
+            ("lib/x.rs", ""),
+            ("lib/y.rs", ""),
+            ("lib/deep/er/x.rs", ""),
+            (&long, ""),
+            ("aaaaaaaaaaaaaaaaaaaaaaaab", ""),
+        ] {
+            let path = workdir.join(path);
+            stdfs::create_dir_all(path.parent().unwrap()).unwrap();
+            stdfs::write(path, content).unwrap();
+        }
+        let mut index = tmp.index().unwrap();
+        index
+            .add_all(["*"], git2::IndexAddOption::FORCE, None)
+            .unwrap();
+        let tree = tmp.find_tree(index.write_tree().unwrap()).unwrap();
+        let sig = git2::Signature::now("Ignorer", "ignorer@example.com").unwrap();
+        let oid = tmp
+            .commit(None, &sig, &sig, "patterns", &tree, &[])
+            .unwrap();
+
+        let repo = Repository::open(tmp.path()).unwrap();
+        let names = |path: &str| {
+            repo.entries_not_ignored(radicle_git_ext::Oid::from(oid), &path)
+                .unwrap()
+                .names()
+                .cloned()
+                .collect::<Vec<_>>()
+        };
+
+        // The pattern with many `*` does not backtrack exponentially on a
+        // name that it doesn't match.
+        assert_eq!(names(""), vec![".gitignore", long.as_str(), "lib", "src"]);
+        // `**` within a segment doesn't match across directories.
+        assert_eq!(names("src"), vec!["ax"]);
+        assert_eq!(names("src/ax"), vec!["yb"]);
+        // `**/` matches zero or more directories.
+        assert_eq!(names("lib"), vec!["deep", "y.rs"]);
+        assert!(names("lib/deep/er").is_empty());
+    }
+
+    #[test]
+    fn directory_case_conflicts() {
+        let tmp =
+            WithTmpDir::new(|path| git2::Repository::init_bare(path).map_err(io::Error::other))
+                .unwrap();
+        let blob = tmp.blob(b"content").unwrap();
+        let tree = |entries: &[(&str, git2::Oid, i32)]| {
+            let mut builder = tmp.treebuilder(None).unwrap();
+            for (name, oid, mode) in entries {
+                builder.insert(name, *oid, *mode).unwrap();
+            }
+            builder.write().unwrap()
+        };
+        let docs = tree(&[("guide.md", blob, 0o100644)]);
+        let root = tree(&[
+            ("README.md", blob, 0o100644),
+            ("readme.md", blob, 0o100644),
+            ("Docs", docs, 0o040000),
+            ("docs", docs, 0o040000),
+            ("main.rs", blob, 0o100644),
+        ]);
+        let sig = git2::Signature::now("Folder", "folder@example.com").unwrap();
+        let oid = tmp
+            .commit(None, &sig, &sig, "case", &tmp.find_tree(root).unwrap(), &[])
+            .unwrap();
+
+        let repo = Repository::open(tmp.path()).unwrap();
+        let root = repo.root_dir(radicle_git_ext::Oid::from(oid)).unwrap();
+        assert_eq!(
+            root.case_conflicts(&repo).unwrap(),
+            vec![
+                vec![
+                    Path::new("Docs").to_path_buf(),
+                    Path::new("docs").to_path_buf()
+                ],
+                vec![
+                    Path::new("Docs/guide.md").to_path_buf(),
+                    Path::new("docs/guide.md").to_path_buf()
+                ],
+                vec![
+                    Path::new("README.md").to_path_buf(),
+                    Path::new("readme.md").to_path_buf()
+                ],
+            ]
+        );
+
+        let platinum = Repository::open(GIT_PLATINUM).unwrap();
+        let root = platinum
+            .root_dir(Branch::local(refname!("master")))
+            .unwrap();
+        assert!(root.case_conflicts(&platinum).unwrap().is_empty());
+    }
+
+    #[test]
+    fn directory_entry_count() {
+        let repo = Repository::open(GIT_PLATINUM).unwrap();
+        let master = Branch::local(refname!("master"));
+
+        assert_eq!(repo.entry_count(&master, &"").unwrap(), 8);
+        let src = repo.directory(&master, &"src").unwrap();
+        assert_eq!(
+            repo.entry_count(&master, &"src").unwrap(),
+            src.entries(&repo).unwrap().count()
+        );
+        assert!(repo.entry_count(&master, &"src/memory.rs").is_err());
+    }
+
+    #[test]
+    fn directory_page() {
+        let repo = Repository::open(GIT_PLATINUM).unwrap();
+        let master = Branch::local(refname!("master"));
+        let all = repo
+            .root_dir(&master)
+            .unwrap()
+            .entries(&repo)
+            .unwrap()
+            .collect::<Vec<_>>();
+
+        let (page, total) = repo.directory_page(&master, &"", 0, 3).unwrap();
+        assert_eq!(total, all.len());
+        assert_eq!(page, all[..3]);
+
+        let (page, total) = repo.directory_page(&master, &"", 3, 100).unwrap();
+        assert_eq!(total, all.len());
+        assert_eq!(page, all[3..]);
+
+        let (page, _) = repo.directory_page(&master, &"", all.len(), 3).unwrap();
+        assert!(page.is_empty());
+
+        // Entries of nested directories keep their location.
+        let (page, total) = repo.directory_page(&master, &"src", 1, 1).unwrap();
+        assert_eq!(total, 2);
+        assert_eq!(page[0].path(), Path::new("src/memory.rs"));
+    }
+
+    #[test]
+    fn directory_object_missing() {
+        let tmp =
+            WithTmpDir::new(|path| git2::Repository::init_bare(path).map_err(io::Error::other))
+                .unwrap();
+        let blob = tmp.blob(b"fetched on demand").unwrap();
+        let mut sub = tmp.treebuilder(None).unwrap();
+        sub.insert("nested.txt", blob, 0o100644).unwrap();
+        let sub = sub.write().unwrap();
+        let mut root = tmp.treebuilder(None).unwrap();
+        root.insert("file.txt", blob, 0o100644).unwrap();
+        root.insert("sub", sub, 0o040000).unwrap();
+        let root = tmp.find_tree(root.write().unwrap()).unwrap();
+        let sig = git2::Signature::now("Promisor", "promisor@example.com").unwrap();
+        let oid = tmp.commit(None, &sig, &sig, "partial", &root, &[]).unwrap();
+
+        // Simulate a partial clone by removing the loose objects.
+        for missing in [blob, sub] {
+            let hex = missing.to_string();
+            stdfs::remove_file(tmp.path().join("objects").join(&hex[..2]).join(&hex[2..])).unwrap();
+        }
+
+        let repo = Repository::open(tmp.path()).unwrap();
+        let root = repo.root_dir(radicle_git_ext::Oid::from(oid)).unwrap();
+        let file = root.find_file(&"file.txt", &repo).unwrap();
+        assert_eq!(
+            file.content(&repo).err(),
+            Some(fs::error::File::ObjectMissing(blob.into()))
+        );
+        let sub_dir = root.find_directory(&"sub", &repo).unwrap();
+        assert_eq!(
+            sub_dir.entries(&repo).err(),
+            Some(fs::error::Directory::ObjectMissing(sub.into()))
+        );
+
+        let err = repo
+            .file(radicle_git_ext::Oid::from(oid), &"file.txt")
+            .and_then(|file| Ok(file.content(&repo)?.size()))
+            .unwrap_err();
+        assert_eq!(err.missing_object(), Some(blob.into()));
+    }
+
+    #[test]
+    fn directory_path_diff() {
+        let repo = Repository::open(GIT_PLATINUM).unwrap();
+        let master = repo.root_dir(Branch::local(refname!("master"))).unwrap();
+        let diff_test = repo.root_dir(Branch::local(refname!("diff-test"))).unwrap();
+
+        let diff = master.path_diff(&diff_test, &repo).unwrap();
+        assert_eq!(
+            diff.created,
+            vec![Path::new("LICENSE"), Path::new("emoji.txt")]
+        );
+        assert_eq!(
+            diff.deleted,
+            vec![Path::new("text/arrows.txt"), Path::new("text/emoji.txt")]
+        );
+        assert_eq!(diff.changed, vec![Path::new("README.md")]);
+
+        assert_eq!(
+            master.path_diff(&master, &repo).unwrap(),
+            fs::PathDiff::default()
+        );
+    }
+
     #[test]
     fn directory_size() {
         let repo = Repository::open(GIT_PLATINUM).unwrap();
@@ -141,4 +455,46 @@ mod directory {
             "a0dd9122d33dff2a35f564d564db127152c88e02"
         );
     }
+
+    #[test]
+    fn file_lfs_pointer() {
+        let tmp =
+            WithTmpDir::new(|path| git2::Repository::init_bare(path).map_err(io::Error::other))
+                .unwrap();
+        let oid = "4d7a214614ab2935c943f9e0ff69d22eadbb8f32b1258daaa5e2ca24d17e2393";
+        let pointer =
+            format!("version https://git-lfs.github.com/spec/v1\noid sha256:{oid}\nsize 4200000\n");
+        let pointer = tmp.blob(pointer.as_bytes()).unwrap();
+        let text = tmp
+            .blob(b"version https://git-lfs.github.com/spec/v1 is not a pointer\n")
+            .unwrap();
+        let mut root = tmp.treebuilder(None).unwrap();
+        root.insert("image.png", pointer, 0o100644).unwrap();
+        root.insert("notes.txt", text, 0o100644).unwrap();
+        let root = tmp.find_tree(root.write().unwrap()).unwrap();
+        let sig = git2::Signature::now("Large", "large@example.com").unwrap();
+        let commit = tmp.commit(None, &sig, &sig, "lfs", &root, &[]).unwrap();
+
+        let repo = Repository::open(tmp.path()).unwrap();
+        let root = repo.root_dir(radicle_git_ext::Oid::from(commit)).unwrap();
+        let content = |name: &str| {
+            root.find_file(&name, &repo)
+                .unwrap()
+                .content(&repo)
+                .unwrap()
+                .lfs_pointer()
+        };
+        assert_eq!(
+            content("image.png"),
+            Some(fs::LfsPointer {
+                oid: oid.to_owned(),
+                size: 4200000
+            })
+        );
+        assert_eq!(content("notes.txt"), None);
+        assert_eq!(
+            serde_json::to_value(content("image.png")).unwrap(),
+            serde_json::json!({ "oid": oid, "size": 4200000 })
+        );
+    }
 }
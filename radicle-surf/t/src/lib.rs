@@ -4,15 +4,80 @@
 #[cfg(test)]
 const GIT_PLATINUM: &str = "../data/git-platinum";
 
+/// Creates an empty bare repository in a temporary directory.
+#[cfg(test)]
+fn tmp_repo() -> test_helpers::tempdir::WithTmpDir<git2::Repository> {
+    test_helpers::tempdir::WithTmpDir::new(|path| {
+        git2::Repository::init_bare(path).map_err(std::io::Error::other)
+    })
+    .unwrap()
+}
+
+/// Commits a tree made of `files`, given as `(path, content, mode)`, on top
+/// of `parents`, and returns the id of the commit.
+///
+/// Paths can contain directories, e.g. `src/lib.rs`.
+#[cfg(test)]
+fn commit_files(
+    repo: &git2::Repository,
+    message: &str,
+    files: &[(&str, &[u8], i32)],
+    parents: &[radicle_git_ext::Oid],
+) -> radicle_git_ext::Oid {
+    let mut index = git2::Index::new().unwrap();
+    for (path, content, mode) in files {
+        let entry = git2::IndexEntry {
+            ctime: git2::IndexTime::new(0, 0),
+            mtime: git2::IndexTime::new(0, 0),
+            dev: 0,
+            ino: 0,
+            mode: *mode as u32,
+            uid: 0,
+            gid: 0,
+            file_size: content.len() as u32,
+            id: repo.blob(content).unwrap(),
+            flags: path.len().min(0xfff) as u16,
+            flags_extended: 0,
+            path: path.as_bytes().to_vec(),
+        };
+        index.add(&entry).unwrap();
+    }
+    let tree = repo.find_tree(index.write_tree_to(repo).unwrap()).unwrap();
+    let parents = parents
+        .iter()
+        .map(|oid| repo.find_commit(**oid).unwrap())
+        .collect::<Vec<_>>();
+    let sig = git2::Signature::now("Tester", "tester@example.com").unwrap();
+    repo.commit(
+        None,
+        &sig,
+        &sig,
+        message,
+        &tree,
+        &parents.iter().collect::<Vec<_>>(),
+    )
+    .unwrap()
+    .into()
+}
+
 #[cfg(test)]
 mod file_system;
 
 #[cfg(test)]
 mod source;
 
+#[cfg(test)]
+mod archive;
+
 #[cfg(test)]
 mod branch;
 
+#[cfg(test)]
+mod cache;
+
+#[cfg(test)]
+mod cherry;
+
 #[cfg(test)]
 mod code_browsing;
 
@@ -1,6 +1,9 @@
+use std::{io, str::FromStr};
+
 use pretty_assertions::{assert_eq, assert_ne};
 use radicle_git_ext::ref_format::{name::component, refname, refspec};
-use radicle_surf::{Branch, Error, Glob, Repository};
+use radicle_surf::{Branch, Error, Glob, Oid, Repository};
+use test_helpers::tempdir::WithTmpDir;
 
 use super::GIT_PLATINUM;
 
@@ -119,3 +122,61 @@ fn silver_namespace() -> Result<(), Error> {
 
     Ok(())
 }
+
+#[test]
+fn namespace_head() -> Result<(), Error> {
+    let tmp = WithTmpDir::new(|path| git2::Repository::init_bare(path).map_err(io::Error::other))
+        .unwrap();
+    let sig = git2::Signature::now("Head Hunter", "head@hunter.com")?;
+    let tree = tmp.find_tree(tmp.treebuilder(None)?.write()?)?;
+    let oid = tmp.commit(None, &sig, &sig, "initial", &tree, &[])?;
+    tmp.reference(
+        "refs/namespaces/golden/refs/namespaces/silver/refs/heads/main",
+        oid,
+        false,
+        "create main",
+    )?;
+    tmp.reference_symbolic(
+        "refs/namespaces/golden/refs/namespaces/silver/HEAD",
+        "refs/namespaces/golden/refs/namespaces/silver/refs/heads/main",
+        false,
+        "create HEAD",
+    )?;
+
+    let repo = Repository::open(tmp.path())?;
+    assert_eq!(repo.namespace_head(&"golden/silver".parse()?)?, oid.into());
+    assert!(repo.namespace_head(&"golden".parse()?).is_err());
+
+    // Inside the namespace, `HEAD` and the references it points to are
+    // those of the namespace.
+    repo.switch_namespace(&refname!("golden/silver"))?;
+    assert_eq!(repo.head()?, oid.into());
+    assert_eq!(
+        repo.resolve_reference(&refname!("HEAD"))?,
+        (
+            oid.into(),
+            vec![refname!("HEAD"), refname!("refs/heads/main")]
+        )
+    );
+
+    Ok(())
+}
+
+#[test]
+fn diff_across_namespaces() -> Result<(), Error> {
+    let repo = Repository::open(GIT_PLATINUM)?;
+    let master = Branch::local(refname!("master"));
+    let diff =
+        repo.diff_across_namespaces(&"golden".parse()?, &"golden/silver".parse()?, &master)?;
+
+    let golden = Oid::from_str("a0dd9122d33dff2a35f564d564db127152c88e02")?;
+    let silver = Oid::from_str("27acd68c7504755aa11023300890bb85bbd69d45")?;
+    assert_eq!(diff, repo.diff(golden, silver)?);
+
+    // The branch must exist in both namespaces.
+    assert!(repo
+        .diff_across_namespaces(&"golden".parse()?, &"me".parse()?, &master)
+        .is_err());
+
+    Ok(())
+}
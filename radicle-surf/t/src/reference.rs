@@ -1,5 +1,11 @@
-use radicle_git_ext::ref_format::refspec;
-use radicle_surf::{Glob, Repository};
+use std::{collections::HashMap, io, str::FromStr};
+
+use radicle_git_ext::{
+    ref_format::{name::component, qualified, refname, refspec},
+    Oid,
+};
+use radicle_surf::{Branch, Glob, RefUpdate, Repository};
+use test_helpers::tempdir::WithTmpDir;
 
 use super::GIT_PLATINUM;
 
@@ -23,6 +29,96 @@ fn test_branches() {
     }
 }
 
+#[test]
+fn test_branch_tips() {
+    let repo = Repository::open(GIT_PLATINUM).unwrap();
+    let tips = repo
+        .branch_tips(Glob::all_heads())
+        .unwrap()
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+    assert_eq!(tips.len(), 4);
+    for (branch, tip) in tips {
+        assert_eq!(repo.commit(&branch).unwrap().id, tip);
+    }
+}
+
+#[test]
+fn test_references() {
+    let repo = Repository::open(GIT_PLATINUM).unwrap();
+    let refs = repo
+        .references(
+            &Glob::categories(refname!("tags"), refspec::pattern!("v0.1.0"))
+                .insert(refname!("tags"), refspec::pattern!("v0.6.0")),
+        )
+        .unwrap()
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+    // Annotated tags are not peeled.
+    assert_eq!(
+        refs,
+        vec![
+            (
+                qualified!("refs/tags/v0.1.0"),
+                Oid::from_str("d3464e33d75c75c99bfb90fa2e9d16efc0b7d0e3").unwrap()
+            ),
+            (
+                qualified!("refs/tags/v0.6.0"),
+                Oid::from_str("4d1f4af2703074d37cb877f4fdbe36322c8e541d").unwrap()
+            ),
+        ]
+    );
+
+    // References within a namespace have the namespace stripped.
+    repo.switch_namespace(&refname!("golden")).unwrap();
+    let refs = repo
+        .references(&Glob::all_category(refname!("tags")))
+        .unwrap()
+        .map(|r| r.map(|(name, _)| name))
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+    assert_eq!(
+        refs,
+        vec![
+            qualified!("refs/tags/v0.1.0"),
+            qualified!("refs/tags/v0.2.0")
+        ]
+    );
+}
+
+#[test]
+fn test_diff_refs() {
+    let repo = Repository::open(GIT_PLATINUM).unwrap();
+    let scope = Glob::all_category(refname!("heads"));
+    let current = repo
+        .references(&scope)
+        .unwrap()
+        .map(|r| r.map(|(name, oid)| (name.to_string(), oid)))
+        .collect::<Result<HashMap<_, _>, _>>()
+        .unwrap();
+    assert!(repo.diff_refs(&current, &scope).unwrap().is_empty());
+
+    let master = current["refs/heads/master"];
+    let dev = current["refs/heads/dev"];
+    let gone = Oid::from_str("d3464e33d75c75c99bfb90fa2e9d16efc0b7d0e3").unwrap();
+    let mut old = current;
+    old.insert("refs/heads/master".to_owned(), dev);
+    old.remove("refs/heads/dev");
+    old.insert("refs/heads/gone".to_owned(), gone);
+
+    let diff = repo.diff_refs(&old, &scope).unwrap();
+    assert_eq!(diff.created, vec![("refs/heads/dev".to_owned(), dev)]);
+    assert_eq!(diff.deleted, vec![("refs/heads/gone".to_owned(), gone)]);
+    assert_eq!(
+        diff.updated,
+        vec![RefUpdate {
+            name: "refs/heads/master".to_owned(),
+            old: dev,
+            new: master,
+        }]
+    );
+}
+
 #[test]
 fn test_tag_snapshot() {
     let repo = Repository::open(GIT_PLATINUM).unwrap();
@@ -36,6 +132,58 @@ fn test_tag_snapshot() {
     assert_eq!(root_dir.entries(&repo).unwrap().entries().count(), 1);
 }
 
+#[test]
+fn test_tree_refs() {
+    let repo = Repository::open(GIT_PLATINUM).unwrap();
+    let master = Branch::local(refname!("master"));
+    let tree = repo.root_dir(&master).unwrap().id();
+
+    let branches = repo.tree_branches(tree, Glob::all_heads().into()).unwrap();
+    assert_eq!(branches, vec![master]);
+
+    let branches = repo
+        .tree_branches(tree, Glob::all_remotes().branches())
+        .unwrap();
+    assert!(branches.contains(&Branch::remote(component!("origin"), refname!("master"))));
+
+    // `v0.6.0` is an annotated tag, and its commit has the same tree as
+    // the commit of `v0.5.0`.
+    let v6 = repo.resolve("v0.6.0").unwrap();
+    let tree = repo.root_dir(v6).unwrap().id();
+    let tags = repo
+        .tree_tags(tree, &Glob::all_tags())
+        .unwrap()
+        .into_iter()
+        .map(|tag| tag.short_name().clone())
+        .collect::<Vec<_>>();
+    assert_eq!(tags, vec![refname!("v0.5.0"), refname!("v0.6.0")]);
+
+    // Annotated tags of blobs, e.g. of a public key, are skipped, rather
+    // than failing.
+    let tmp = WithTmpDir::new(|path| git2::Repository::init_bare(path).map_err(io::Error::other))
+        .unwrap();
+    let sig = git2::Signature::now("Tagger", "tagger@example.com").unwrap();
+    let mut builder = tmp.treebuilder(None).unwrap();
+    builder
+        .insert("file", tmp.blob(b"content").unwrap(), 0o100644)
+        .unwrap();
+    let tree = tmp.find_tree(builder.write().unwrap()).unwrap();
+    let commit = tmp.commit(None, &sig, &sig, "tagged", &tree, &[]).unwrap();
+    let blob = tmp.find_object(tmp.blob(b"key").unwrap(), None).unwrap();
+    tmp.reference("refs/tags/commit", commit, false, "commit")
+        .unwrap();
+    tmp.tag("key", &blob, &sig, "A key", false).unwrap();
+    let repo = Repository::open(tmp.path()).unwrap();
+    let tree = repo.root_dir(Oid::from(commit)).unwrap().id();
+    let tags = repo
+        .tree_tags(tree, &Glob::all_tags())
+        .unwrap()
+        .into_iter()
+        .map(|tag| tag.short_name().clone())
+        .collect::<Vec<_>>();
+    assert_eq!(tags, vec![refname!("commit")]);
+}
+
 #[test]
 fn test_namespaces() {
     let repo = Repository::open(GIT_PLATINUM).unwrap();
@@ -53,3 +201,53 @@ fn test_namespaces() {
         .unwrap();
     assert_eq!(namespaces.count(), 3);
 }
+
+#[test]
+fn test_packed_refs() {
+    let tmp = WithTmpDir::new(|path| git2::Repository::init_bare(path).map_err(io::Error::other))
+        .unwrap();
+    let sig = git2::Signature::now("Packer", "packer@example.com").unwrap();
+    let tree = tmp
+        .find_tree(tmp.treebuilder(None).unwrap().write().unwrap())
+        .unwrap();
+    let one = tmp.commit(None, &sig, &sig, "one", &tree, &[]).unwrap();
+    let two = tmp.commit(None, &sig, &sig, "two", &tree, &[]).unwrap();
+
+    // `refs/heads/both` is packed, and its loose ref takes precedence.
+    std::fs::write(
+        tmp.path().join("packed-refs"),
+        format!(
+            "# pack-refs with: peeled fully-peeled sorted \n\
+             {one} refs/heads/both\n\
+             {one} refs/heads/packed\n\
+             {one} refs/tags/v1\n"
+        ),
+    )
+    .unwrap();
+    tmp.reference("refs/heads/both", two, true, "loose")
+        .unwrap();
+    tmp.reference("refs/heads/loose", two, true, "loose")
+        .unwrap();
+
+    let repo = Repository::open(tmp.path()).unwrap();
+    let mut tips = repo
+        .branch_tips(Glob::all_heads())
+        .unwrap()
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+    tips.sort();
+    assert_eq!(
+        tips,
+        vec![
+            (Branch::local(refname!("both")), two.into()),
+            (Branch::local(refname!("loose")), two.into()),
+            (Branch::local(refname!("packed")), one.into()),
+        ]
+    );
+    let tags = repo
+        .tag_names(&Glob::all_tags())
+        .unwrap()
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+    assert_eq!(tags, vec![qualified!("refs/tags/v1")]);
+}
@@ -1,7 +1,8 @@
-use std::str::FromStr;
+use std::{io, str::FromStr};
 
-use radicle_git_ext::ref_format::{name::component, refname};
-use radicle_surf::{Branch, Error, Oid, Repository};
+use radicle_git_ext::ref_format::{name::component, refname, refspec};
+use radicle_surf::{repo::error, Branch, Error, Glob, Oid, Repository, ResolvedRev, Tag};
+use test_helpers::tempdir::WithTmpDir;
 
 use super::GIT_PLATINUM;
 
@@ -90,3 +91,222 @@ fn tag() -> Result<(), Error> {
 
     Ok(())
 }
+
+#[test]
+fn tags_with_commits() -> Result<(), Error> {
+    let repo = Repository::open(GIT_PLATINUM)?;
+    let tags = repo
+        .tags_with_commits(&Glob::all_tags())?
+        .into_iter()
+        .map(|(tag, commit)| (tag.short_name().to_string(), commit.id))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        tags,
+        [
+            ("v0.1.0", "d3464e33d75c75c99bfb90fa2e9d16efc0b7d0e3"),
+            ("v0.2.0", "2429f097664f9af0c5b7b389ab998b2199ffa977"),
+            ("v0.3.0", "19bec071db6474af89c866a1bd0e4b1ff76e2b97"),
+            ("v0.4.0", "91b69e00cd8e5a07e20942e9e4457d83ce7a3ff1"),
+            ("v0.5.0", "80ded66281a4de2889cc07293a8f10947c6d57fe"),
+            // An annotated tag, peeled to its commit.
+            ("v0.6.0", "d6880352fc7fda8f521ae9b7357668b17bb5bad5"),
+        ]
+        .into_iter()
+        .map(|(name, oid)| (name.to_string(), Oid::from_str(oid).unwrap()))
+        .collect::<Vec<_>>()
+    );
+
+    let (tag, _) = repo
+        .tags_with_commits(&Glob::tags(refspec::pattern!("v0.6.0")))?
+        .pop()
+        .unwrap();
+    assert!(matches!(tag, Tag::Annotated { .. }));
+
+    Ok(())
+}
+
+#[test]
+fn resolve() -> Result<(), Error> {
+    let repo = Repository::open(GIT_PLATINUM)?;
+
+    let master = repo.resolve("master")?;
+    assert_eq!(
+        master,
+        Oid::from_str("a0dd9122d33dff2a35f564d564db127152c88e02")?
+    );
+
+    // Annotated tags are peeled to their commit.
+    let tag = repo.resolve("refs/tags/v0.6.0")?;
+    assert_eq!(
+        tag,
+        Oid::from_str("d6880352fc7fda8f521ae9b7357668b17bb5bad5")?
+    );
+
+    let short = repo.resolve("3873745c8")?;
+    assert_eq!(
+        short,
+        Oid::from_str("3873745c8f6ffb45c990eb23b491d4b4b6182f95")?
+    );
+
+    let err = repo.resolve("does-not-exist").unwrap_err();
+    assert!(
+        matches!(&err, Error::Repo(error::Repo::RevisionNotFound(rev)) if rev == "does-not-exist"),
+        "{err}"
+    );
+    let err = repo.resolve("master@{").unwrap_err();
+    assert!(
+        matches!(&err, Error::Repo(error::Repo::InvalidRevision(rev)) if rev == "master@{"),
+        "{err}"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn resolve_reflog_and_upstream() -> Result<(), Error> {
+    let repo = Repository::open(GIT_PLATINUM)?;
+    let master = Oid::from_str("a0dd9122d33dff2a35f564d564db127152c88e02")?;
+    assert_eq!(repo.resolve("master@{0}")?, master);
+    assert_eq!(repo.resolve("master@{upstream}")?, master);
+    assert_eq!(repo.resolve("master@{u}")?, master);
+    // `HEAD` was on `diff-test` before it was moved to `dev`.
+    assert_eq!(
+        repo.resolve("HEAD@{1}")?,
+        Oid::from_str("f4ee21b82639f78387aa81510cddab7bcf80554d")?
+    );
+
+    let err = repo.resolve("master@{1}").unwrap_err();
+    assert!(
+        matches!(&err, Error::Repo(error::Repo::NoReflogEntry(rev)) if rev == "master@{1}"),
+        "{err}"
+    );
+    // The entry of a reference that doesn't exist is not a reflog error.
+    let err = repo.resolve("does-not-exist@{1}").unwrap_err();
+    assert!(
+        matches!(&err, Error::Repo(error::Repo::RevisionNotFound(_))),
+        "{err}"
+    );
+
+    let tmp = WithTmpDir::new(|path| git2::Repository::init_bare(path).map_err(io::Error::other))
+        .unwrap();
+    let sig = git2::Signature::now("Upstream", "upstream@example.com")?;
+    let tree = tmp.find_tree(tmp.treebuilder(None)?.write()?)?;
+    tmp.commit(Some("refs/heads/main"), &sig, &sig, "main", &tree, &[])?;
+    let repo = Repository::open(tmp.path())?;
+    let err = repo.resolve("main@{upstream}").unwrap_err();
+    assert!(
+        matches!(&err, Error::Repo(error::Repo::NoUpstream(rev)) if rev == "main@{upstream}"),
+        "{err}"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn resolve_reference() -> Result<(), Error> {
+    let tmp = WithTmpDir::new(|path| git2::Repository::init_bare(path).map_err(io::Error::other))
+        .unwrap();
+    let sig = git2::Signature::now("Resolver", "resolver@example.com")?;
+    let tree = tmp.find_tree(tmp.treebuilder(None)?.write()?)?;
+    let oid = tmp.commit(None, &sig, &sig, "initial", &tree, &[])?;
+    tmp.reference("refs/heads/main", oid, false, "main")?;
+    tmp.reference_symbolic("refs/heads/alias", "refs/heads/main", false, "alias")?;
+    tmp.set_head("refs/heads/alias")?;
+
+    let repo = Repository::open(tmp.path())?;
+    let (resolved, chain) = repo.resolve_reference(&refname!("HEAD"))?;
+    assert_eq!(resolved, Oid::from(oid));
+    assert_eq!(
+        chain,
+        vec![
+            refname!("HEAD"),
+            refname!("refs/heads/alias"),
+            refname!("refs/heads/main")
+        ]
+    );
+
+    // A cycle of symbolic references is an error, rather than a loop.
+    tmp.reference_symbolic("refs/heads/a", "refs/heads/b", false, "a")?;
+    tmp.reference_symbolic("refs/heads/b", "refs/heads/a", false, "b")?;
+    let err = repo
+        .resolve_reference(&refname!("refs/heads/a"))
+        .unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "symbolic reference cycle: refs/heads/a -> refs/heads/b -> refs/heads/a"
+    );
+
+    // A symbolic reference to an invalid name, written by hand.
+    std::fs::write(tmp.path().join("refs/heads/bad"), "ref: refs/heads/a..b\n").unwrap();
+    let err = repo
+        .resolve_reference(&refname!("refs/heads/bad"))
+        .unwrap_err();
+    assert!(
+        matches!(&err, Error::Repo(error::Repo::InvalidSymrefTarget(target)) if target == "refs/heads/a..b"),
+        "{err:?}"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn resolve_any() -> Result<(), Error> {
+    let repo = Repository::open(GIT_PLATINUM)?;
+    let dev = Oid::from_str("27acd68c7504755aa11023300890bb85bbd69d45")?;
+
+    assert_eq!(
+        repo.resolve_any("27acd68c7504755aa11023300890bb85bbd69d45")?,
+        ResolvedRev::Oid(dev)
+    );
+    assert_eq!(repo.resolve_any("27acd68")?, ResolvedRev::ShortOid(dev));
+    assert_eq!(
+        repo.resolve_any("dev")?,
+        ResolvedRev::Branch(Branch::local(refname!("dev")), dev)
+    );
+
+    let tag = repo.resolve_any("v0.6.0")?;
+    assert!(matches!(tag, ResolvedRev::Tag(_)));
+    assert_eq!(
+        tag.id(),
+        Oid::from_str("4d1f4af2703074d37cb877f4fdbe36322c8e541d")?
+    );
+
+    assert_eq!(
+        repo.resolve_any("refs/heads/dev")?,
+        ResolvedRev::Reference(
+            refname!("refs/heads/dev").qualified().unwrap().to_owned(),
+            dev
+        )
+    );
+    assert_eq!(
+        repo.resolve_any("dev~1")?,
+        ResolvedRev::Revspec(Oid::from_str("1820cb07c1a890016ca5578aa652fd4d4c38967e")?)
+    );
+
+    assert!(repo.resolve_any("not-a-revision").is_err());
+
+    Ok(())
+}
+
+#[test]
+fn ancestor() -> Result<(), Error> {
+    let repo = Repository::open(GIT_PLATINUM)?;
+    let dev = Branch::local(refname!("dev"));
+
+    assert_eq!(repo.ancestor(&dev, 0)?, repo.resolve("dev")?);
+    assert_eq!(
+        repo.ancestor(&dev, 1)?,
+        Oid::from_str("1820cb07c1a890016ca5578aa652fd4d4c38967e")?
+    );
+    assert_eq!(repo.ancestor(&dev, 3)?, repo.resolve("dev~3")?);
+
+    // `dev` has 8 commits along its first parents.
+    assert!(repo.ancestor(&dev, 7).is_ok());
+    let err = repo.ancestor(&dev, 8).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "revision not found: 27acd68c7504755aa11023300890bb85bbd69d45~8"
+    );
+
+    Ok(())
+}
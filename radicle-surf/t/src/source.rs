@@ -1,8 +1,9 @@
-use std::path::PathBuf;
+use std::{io, path::PathBuf};
 
-use radicle_git_ext::ref_format::refname;
+use radicle_git_ext::{ref_format::refname, Oid};
 use radicle_surf::{Branch, Glob, Repository};
 use serde_json::json;
+use test_helpers::tempdir::WithTmpDir;
 
 const GIT_PLATINUM: &str = "../data/git-platinum";
 
@@ -175,6 +176,74 @@ fn repo_blob() {
     assert_eq!(json_ref, json_owned);
 }
 
+#[test]
+fn repo_blob_filtered() {
+    let tmp = WithTmpDir::new(|path| git2::Repository::init_bare(path).map_err(io::Error::other))
+        .unwrap();
+    let blob = |content: &str| tmp.blob(content.as_bytes()).unwrap();
+    let mut sub = tmp.treebuilder(None).unwrap();
+    for (name, content) in [(".gitattributes", "*.txt -text\n"), ("raw.txt", "a\n")] {
+        sub.insert(name, blob(content), 0o100644).unwrap();
+    }
+    let mut nested = tmp.treebuilder(None).unwrap();
+    nested.insert("b.c", blob("$Id$"), 0o100644).unwrap();
+    let mut src = tmp.treebuilder(None).unwrap();
+    src.insert("ab.c", blob("$Id$"), 0o100644).unwrap();
+    src.insert("a", nested.write().unwrap(), 0o040000).unwrap();
+    let mut root = tmp.treebuilder(None).unwrap();
+    for (name, content) in [
+        (
+            ".gitattributes",
+            "*.txt text eol=crlf\n*.dat -text\nid.c ident\nauto.md text=auto eol=crlf\n\
+             raw.txt binary\nsrc/a**c ident\n*a*a*a*a*a*a*a*a*a*a*a*a*a*a*a*a*a*a*a*a*b ident\n",
+        ),
+        ("raw.txt", "a\n"),
+        ("notes.txt", "a\nb\r\nc\n"),
+        ("data.dat", "a\n"),
+        ("id.c", "// $Id$\n"),
+        ("auto.md", "a\r\nb\n"),
+        ("plain.rs", "a\n"),
+    ] {
+        root.insert(name, blob(content), 0o100644).unwrap();
+    }
+    root.insert("a".repeat(64), blob("$Id$"), 0o100644).unwrap();
+    root.insert("src", src.write().unwrap(), 0o040000).unwrap();
+    root.insert("sub", sub.write().unwrap(), 0o040000).unwrap();
+    let tree = tmp.find_tree(root.write().unwrap()).unwrap();
+    let sig = git2::Signature::now("Filter", "filter@example.com").unwrap();
+    let oid = Oid::from(tmp.commit(None, &sig, &sig, "filters", &tree, &[]).unwrap());
+
+    let repo = Repository::open(tmp.path()).unwrap();
+    let filtered = |path: &str| repo.blob_filtered(oid, &path).unwrap().content().to_vec();
+
+    assert_eq!(filtered("notes.txt"), b"a\r\nb\r\nc\r\n");
+    assert_eq!(filtered("data.dat"), b"a\n");
+    assert_eq!(filtered("plain.rs"), b"a\n");
+    // Files committed with CRLF are not converted automatically.
+    assert_eq!(filtered("auto.md"), b"a\r\nb\n");
+    // Nested `.gitattributes` take precedence.
+    assert_eq!(filtered("sub/raw.txt"), b"a\n");
+    // `binary` unsets `text`.
+    assert_eq!(filtered("raw.txt"), b"a\n");
+    // `**` within a segment doesn't match across directories.
+    assert_ne!(filtered("src/ab.c"), b"$Id$");
+    assert_eq!(filtered("src/a/b.c"), b"$Id$");
+    // The pattern with many `*` does not backtrack exponentially on a name
+    // that it doesn't match.
+    assert_eq!(filtered(&"a".repeat(64)), b"$Id$");
+
+    let id = repo.blob(oid, &"id.c").unwrap().object_id();
+    assert_eq!(filtered("id.c"), format!("// $Id: {id} $\n").as_bytes());
+    assert_eq!(repo.blob_filtered(oid, &"id.c").unwrap().object_id(), id);
+
+    tmp.config()
+        .unwrap()
+        .set_bool("core.autocrlf", true)
+        .unwrap();
+    assert_eq!(filtered("plain.rs"), b"a\r\n");
+    assert_eq!(filtered("data.dat"), b"a\n");
+}
+
 #[test]
 fn tree_ordering() {
     let repo = Repository::open(GIT_PLATINUM).unwrap();
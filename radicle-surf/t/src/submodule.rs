@@ -8,3 +8,66 @@ fn test_submodule_failure() {
     let repo = Repository::discover(".").unwrap();
     repo.root_dir(Branch::local(refname!("main"))).unwrap();
 }
+
+#[test]
+fn test_submodules() {
+    use std::io;
+
+    use radicle_surf::{Repository, Submodule};
+    use test_helpers::tempdir::WithTmpDir;
+
+    let tmp = WithTmpDir::new(|path| git2::Repository::init_bare(path).map_err(io::Error::other))
+        .unwrap();
+    let gitmodules = r#"
+# Vendored dependencies
+[submodule "lib"]
+	path = lib
+	url = https://example.com/lib.git
+	branch = "main" ; the default branch
+[submodule "docs"]
+	path = docs
+	url = ../docs.git
+[submodule "broken"]
+	path = broken
+"#;
+    let pinned = git2::Oid::from_str("d6880352fc7fda8f521ae9b7357668b17bb5bad5").unwrap();
+    let sig = git2::Signature::now("Sub Module", "sub@module.com").unwrap();
+    let mut builder = tmp.treebuilder(None).unwrap();
+    let blob = tmp.blob(gitmodules.as_bytes()).unwrap();
+    builder.insert(".gitmodules", blob, 0o100644).unwrap();
+    builder.insert("lib", pinned, 0o160000).unwrap();
+    let tree = tmp.find_tree(builder.write().unwrap()).unwrap();
+    let commit = tmp
+        .commit(None, &sig, &sig, "Add submodules", &tree, &[])
+        .unwrap();
+
+    let repo = Repository::open(tmp.path()).unwrap();
+    let submodules = repo.submodules(commit.to_string()).unwrap();
+    assert_eq!(
+        submodules,
+        vec![
+            Submodule {
+                name: "docs".to_string(),
+                path: "docs".into(),
+                url: "../docs.git".to_string(),
+                branch: None,
+                oid: None,
+            },
+            Submodule {
+                name: "lib".to_string(),
+                path: "lib".into(),
+                url: "https://example.com/lib.git".to_string(),
+                branch: Some("main".to_string()),
+                oid: Some(pinned.into()),
+            },
+        ]
+    );
+
+    let empty = tmp
+        .find_tree(tmp.treebuilder(None).unwrap().write().unwrap())
+        .unwrap();
+    let plain = tmp
+        .commit(None, &sig, &sig, "No submodules", &empty, &[])
+        .unwrap();
+    assert!(repo.submodules(plain.to_string()).unwrap().is_empty());
+}